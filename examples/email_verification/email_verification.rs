@@ -63,7 +63,7 @@ async fn main() -> Result<()> {
 
   println!("   ✓ Verification token generated:");
   println!("     - Token: {}...", &verification_token.token[..16]);
-  println!("     - For Email: {}", verification_token.email);
+  println!("     - For Email: {}", verification_token.identifier);
   println!("     - Expires At: {}", verification_token.expires_at);
   println!("\n   📧 In a real application, you would send this token via email.");
   println!("      For example:");
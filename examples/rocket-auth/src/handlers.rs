@@ -32,10 +32,7 @@ pub async fn register(
 ) -> std::result::Result<Json<RegisterResponse>, Custom<Json<ErrorResponse>>> {
   let result = state
     .auth
-    .register(Register {
-      email: request.email.clone(),
-      password: request.password.clone(),
-    })
+    .register_ref(RegisterRef::new(&request.email, &request.password))
     .await;
 
   match result {
@@ -46,14 +43,16 @@ pub async fn register(
 
       let verification_result = state
         .auth
-        .send_email_verification(SendEmailVerification {
+        .send_email_verification_detailed(SendEmailVerification {
           user_id: user_id.clone(),
         })
         .await;
 
-      let message = match verification_result {
-        Ok(_) => format!("User registered successfully. A verification email has been sent to {}.", email),
-        Err(_) => "User registered successfully. Failed to send verification email, but you can request a new one.".to_string(),
+      let message = match verification_result.map(|outcome| outcome.status) {
+        Ok(SendStatus::Sent) => format!("User registered successfully. A verification email has been sent to {}.", email),
+        Ok(SendStatus::Queued) => format!("User registered successfully. A verification email to {} is on its way.", email),
+        Ok(SendStatus::TokenOnly) => "User registered successfully. Email delivery is not configured; request a verification token to continue.".to_string(),
+        Ok(SendStatus::Failed) | Err(_) => "User registered successfully. Failed to send verification email, but you can request a new one.".to_string(),
       };
 
       Ok(Json(RegisterResponse {
@@ -87,10 +86,7 @@ pub async fn login(
 ) -> std::result::Result<Json<LoginResponse>, Custom<Json<ErrorResponse>>> {
   let result = state
     .auth
-    .login(Login {
-      email: request.email.clone(),
-      password: request.password.clone(),
-    })
+    .login_ref(LoginRef::new(&request.email, &request.password))
     .await;
 
   match result {
@@ -189,7 +185,7 @@ pub async fn send_verification(
   match result {
     Ok(verification) => Ok(Json(SendVerificationResponse {
       token: verification.token,
-      email: verification.email,
+      email: verification.identifier,
       expires_at: verification.expires_at,
       message: "Verification email sent successfully".to_string(),
     })),
@@ -534,7 +530,7 @@ pub async fn resend_verification(
   match result {
     Ok(verification) => Ok(Json(ResendVerificationResponse {
       token: verification.token,
-      email: verification.email,
+      email: verification.identifier,
       expires_at: verification.expires_at,
       message: "Verification email resent successfully".to_string(),
     })),
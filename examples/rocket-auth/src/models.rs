@@ -138,29 +138,8 @@ impl ErrorResponse {
 
   /// Create error response from AuthError
   pub fn from_auth_error(err: &authkit::AuthError) -> Self {
-    let error = match err {
-      authkit::AuthError::DatabaseError(_) => "DatabaseError",
-      authkit::AuthError::UserAlreadyExists(_) => "UserAlreadyExists",
-      authkit::AuthError::UserNotFound => "UserNotFound",
-      authkit::AuthError::InvalidCredentials => "InvalidCredentials",
-      authkit::AuthError::InvalidSession => "InvalidSession",
-      authkit::AuthError::WeakPassword(_) => "WeakPassword",
-      authkit::AuthError::InvalidEmailFormat => "InvalidEmailFormat",
-      authkit::AuthError::MissingDatabase => "MissingDatabase",
-      authkit::AuthError::MissingPasswordStrategy => "MissingPasswordStrategy",
-      authkit::AuthError::PasswordHashingError(_) => "PasswordHashingError",
-      authkit::AuthError::TokenGenerationError(_) => "TokenGenerationError",
-      authkit::AuthError::InternalError(_) => "InternalError",
-      authkit::AuthError::InvalidToken(_) => "InvalidToken",
-      authkit::AuthError::TokenAlreadyUsed(_) => "TokenAlreadyUsed",
-      authkit::AuthError::EmailAlreadyVerified(_) => "EmailAlreadyVerified",
-      authkit::AuthError::TokenExpired(_) => "TokenExpired",
-      authkit::AuthError::EmailSendFailed(_) => "EmailSendFailed",
-      authkit::AuthError::RateLimitExceeded(_) => "RateLimitExceeded",
-    };
-
     Self {
-      error: error.to_string(),
+      error: err.code().to_string(),
       message: err.to_string(),
     }
   }
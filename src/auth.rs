@@ -2,16 +2,35 @@ use crate::database::DatabaseTrait;
 use crate::email::EmailSender;
 #[cfg(feature = "email-queue")]
 use crate::email_job::{EmailQueue, EmailWorkerConfig, EmailWorkerHandle};
-use crate::error::Result;
+use crate::error::{AuthError, Result};
+use crate::metrics::MetricsRecorder;
 use crate::operations::email_verification::{
-  ResendEmailVerification, SendEmailVerification, VerifyEmail,
+  BatchVerificationResult, ResendEmailVerification, SendEmailVerification, SendVerificationOutcome,
+  VerificationStatusReport, VerifyEmail,
 };
-use crate::operations::{Login, Logout, Register, Verify};
+use crate::operations::{
+  AcceptInvitation, AddEmail, ChangePassword, ConsumeVerification, Invitation, InviteUser,
+  IssueVerification, Login, LoginRef, Logout, LogoutScope, Register, RegisterOutcome, RegisterRef,
+  RegisterWithHash, RevokeSession, RotateSession, SessionPage, SetPrimaryEmail, SourceVerification,
+  TokenKind, TokenSource, VerificationOnRegister, VerifiedIdentifier, VerifiedSession, Verify,
+  VerifyRef, VerifySecondaryEmail,
+};
+use crate::rate_limit::RateLimitStore;
+use crate::security::signing::TokenSigner;
+use crate::security::tokens::TokenGenerator;
+#[cfg(feature = "sms")]
+use crate::sms::SmsSender;
 use crate::strategies::password::PasswordStrategy;
 use crate::strategies::session::SessionStrategy;
-use crate::strategies::token::TokenStrategy;
-use crate::types::{Session, User, VerificationToken};
+use crate::strategies::token::{TokenStrategy, TokenType};
+use crate::types::{
+  Account, Session, SessionInfo, UnverifiedIdentifier, User, UserEmail, VerificationToken,
+};
+use crate::validation::email::EmailValidator;
+use crate::validation::limits::InputLimits;
+use crate::validation::password::PasswordPolicy;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct Auth {
@@ -24,20 +43,129 @@ pub(crate) struct AuthInner {
   pub(crate) session_strategy: Box<dyn SessionStrategy>,
   pub(crate) token_strategy: Box<dyn TokenStrategy>,
   pub(crate) email_sender: Option<Arc<Box<dyn EmailSender>>>,
+  #[cfg(feature = "sms")]
+  pub(crate) sms_sender: Option<Arc<Box<dyn SmsSender>>>,
+  pub(crate) token_generator: Arc<dyn TokenGenerator>,
+  pub(crate) metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+  pub(crate) rate_limit_store: Arc<dyn RateLimitStore>,
+
+  /// Secret for deriving CSRF tokens, set by `AuthBuilder::csrf_secret`
+  pub(crate) csrf_signer: Option<TokenSigner>,
 
   /// Whether to automatically send verification email on registration
   /// Defaults to false
-  pub(crate) send_verification_on_register: bool,
+  pub(crate) send_verification_on_register: VerificationOnRegister,
+
+  /// Whether a failed verification email on registration fails `register`
+  /// Defaults to false
+  pub(crate) fail_registration_on_email_error: bool,
+
+  /// Whether verifying an already verified email returns the user
+  /// Defaults to false
+  pub(crate) idempotent_email_verify: bool,
+
+  /// Whether `register_detailed` returns a verification token when no email
+  /// sender is configured
+  /// Defaults to false
+  pub(crate) return_verification_token_on_register: bool,
+
+  /// Whether `register_detailed` logs the new user in
+  /// Defaults to false
+  pub(crate) session_on_register: bool,
 
   /// Whether login requires email to be verified
   /// Defaults to false
   pub(crate) require_email_verification: bool,
 
+  /// Emails (lowercased) that may log in unverified
+  /// Defaults to none
+  pub(crate) verification_exempt_emails: std::collections::HashSet<String>,
+
+  /// Whether an unverified login resends the verification email
+  /// Defaults to false
+  pub(crate) resend_verification_on_login: bool,
+
+  /// Maximum sizes for emails, passwords, names, and tokens
+  pub(crate) input_limits: InputLimits,
+
+  /// Whether surrounding whitespace is stripped from submitted tokens
+  /// Defaults to true
+  pub(crate) trim_token_input: bool,
+
+  /// How email addresses are validated
+  pub(crate) email_validator: EmailValidator,
+
+  /// Requirements for new passwords
+  pub(crate) password_policy: PasswordPolicy,
+
+  /// Provider name of email/password accounts, lowercased
+  /// Defaults to "credential"
+  pub(crate) credential_provider: String,
+
+  /// Idle time after which `verify` rejects a session
+  /// Defaults to None (sessions only expire at `expires_at`)
+  pub(crate) inactivity_timeout: Option<Duration>,
+
+  /// Lifetime of sessions created with `Login.remember_me`
+  /// Defaults to 30 days
+  pub(crate) remember_me_duration: Duration,
+
+  /// Furthest ahead `extend_session` may push a session's expiry
+  /// Defaults to 30 days
+  pub(crate) max_session_extension: Duration,
+
+  /// Fresh-token retries when a new session's token is already taken
+  /// Defaults to 3
+  pub(crate) session_collision_retries: u32,
+
+  /// Which token source `verify_from_sources` tries first
+  /// Defaults to the Authorization header
+  pub(crate) preferred_token_source: TokenSource,
+
+  /// Which sessions `logout` deletes
+  /// Defaults to only the given session
+  pub(crate) logout_scope: LogoutScope,
+
+  /// Whether a password change deletes the user's sessions
+  /// Defaults to true
+  pub(crate) invalidate_sessions_on_password_change: bool,
+
+  /// Minimum time between verification emails to the same address
+  /// Defaults to None (no cooldown)
+  pub(crate) email_send_cooldown: Option<Duration>,
+
+  /// Maximum verification resends per user in a rolling 24 hours
+  /// Defaults to None (unlimited)
+  pub(crate) max_verification_resends: Option<u32>,
+
+  /// Failed logins allowed per email within a window
+  /// Defaults to None (unlimited)
+  pub(crate) login_attempt_limit: Option<(u32, Duration)>,
+
+  /// Lifetime of codes from `issue_verification_link_code`
+  /// Defaults to 1 hour
+  pub(crate) verification_link_code_ttl: Duration,
+
+  /// Whether resends reuse a still-valid (encrypted) verification token
+  /// Defaults to false
+  #[cfg(feature = "encrypted-tokens")]
+  pub(crate) reuse_valid_verification_tokens: bool,
+
+  /// Cipher for tokens held by persistent email jobs
+  /// Defaults to None (stored as issued)
+  #[cfg(all(feature = "persistent-queue", feature = "encrypted-tokens"))]
+  pub(crate) token_cipher: Option<crate::security::encryption::TokenCipher>,
+
+  /// Queue of the worker started by `start_email_worker`, if still running
   #[cfg(feature = "email-queue")]
-  pub(crate) email_queue: Option<EmailQueue>,
+  pub(crate) email_worker: crate::email_job::WorkerSlot,
 
   #[cfg(feature = "email-queue")]
   pub(crate) email_worker_config: Option<EmailWorkerConfig>,
+
+  /// Destination of operation and email spans
+  #[cfg(feature = "otel")]
+  pub(crate) telemetry: crate::telemetry::Telemetry,
 }
 
 impl std::fmt::Debug for AuthInner {
@@ -47,44 +175,879 @@ impl std::fmt::Debug for AuthInner {
         "send_verification_on_register",
         &self.send_verification_on_register,
       )
+      .field(
+        "fail_registration_on_email_error",
+        &self.fail_registration_on_email_error,
+      )
+      .field("idempotent_email_verify", &self.idempotent_email_verify)
+      .field(
+        "return_verification_token_on_register",
+        &self.return_verification_token_on_register,
+      )
+      .field("session_on_register", &self.session_on_register)
       .field(
         "require_email_verification",
         &self.require_email_verification,
       )
+      .field(
+        "verification_exempt_emails",
+        &self.verification_exempt_emails,
+      )
+      .field(
+        "resend_verification_on_login",
+        &self.resend_verification_on_login,
+      )
+      .field("input_limits", &self.input_limits)
+      .field("trim_token_input", &self.trim_token_input)
+      .field("email_validator", &self.email_validator)
+      .field("credential_provider", &self.credential_provider)
+      .field("password_policy", &self.password_policy)
+      .field("inactivity_timeout", &self.inactivity_timeout)
+      .field("remember_me_duration", &self.remember_me_duration)
+      .field("max_session_extension", &self.max_session_extension)
+      .field("session_collision_retries", &self.session_collision_retries)
+      .field("preferred_token_source", &self.preferred_token_source)
+      .field("logout_scope", &self.logout_scope)
+      .field(
+        "invalidate_sessions_on_password_change",
+        &self.invalidate_sessions_on_password_change,
+      )
+      .field("email_send_cooldown", &self.email_send_cooldown)
+      .field("max_verification_resends", &self.max_verification_resends)
+      .field("login_attempt_limit", &self.login_attempt_limit)
+      .field(
+        "verification_link_code_ttl",
+        &self.verification_link_code_ttl,
+      )
       .finish_non_exhaustive()
   }
 }
 
+impl AuthInner {
+  /// A submitted token as it should be looked up
+  pub(crate) fn token_input<'a>(&self, token: &'a str) -> &'a str {
+    if self.trim_token_input {
+      token.trim()
+    } else {
+      token
+    }
+  }
+}
+
 impl Auth {
   pub fn builder() -> crate::builder::AuthBuilder {
     crate::builder::AuthBuilder::new()
   }
+  /// Build an `Auth` with default strategies from a database URL
+  ///
+  /// The backend is picked from the URL scheme (see [`Database::connect`]).
+  /// The schema is not touched; call [`Auth::migrate`] afterwards if the
+  /// tables may not exist yet.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # use authkit::Auth;
+  /// # async fn run() -> authkit::Result<()> {
+  /// let auth = Auth::from_database_url("sqlite://auth.db").await?;
+  /// auth.migrate().await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  ///
+  /// [`Database::connect`]: crate::Database::connect
+  pub async fn from_database_url(url: &str) -> Result<Self> {
+    let db = crate::types::Database::connect(url).await?;
+    Self::builder().database(db).build()
+  }
+  /// Create all AuthKit tables and indexes if they don't already exist
+  ///
+  /// Safe to call on every startup. Columns the application adds to
+  /// `users` are left alone, but must be nullable or have a default:
+  /// AuthKit's inserts only list its own columns. Existing tables don't gain
+  /// newer columns; see [`Auth::migrate_email_verification`].
+  pub async fn migrate(&self) -> Result<()> {
+    self.inner.db.migrate().await
+  }
+  /// Add the email verification columns to an existing `users` table
+  ///
+  /// For deployments whose `users` table predates `email_verified` and
+  /// `email_verified_at`: `migrate` leaves existing tables alone, so run
+  /// this once before enabling email verification. Existing users start
+  /// out unverified. Idempotent; does nothing when the columns exist.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// auth.migrate().await?;
+  /// auth.migrate_email_verification().await?;
+  /// ```
+  pub async fn migrate_email_verification(&self) -> Result<()> {
+    self.inner.db.migrate_email_verification().await
+  }
+  /// Check that the configured strategies and database actually work
+  ///
+  /// Hashes and verifies a throwaway password, generates a token and
+  /// derives its stored session and verification forms (hashed or
+  /// encrypted, as configured), and pings the database. Call at startup to
+  /// catch, e.g., a hashing cost that is too slow or an unreachable
+  /// database before serving traffic. Nothing is written.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// auth.migrate().await?;
+  /// auth.self_test().await?;
+  /// ```
+  pub async fn self_test(&self) -> Result<()> {
+    crate::operations::self_test::execute(self).await
+  }
   pub async fn register(&self, request: Register) -> Result<User> {
-    crate::operations::register::execute(self, request).await
+    self.register_ref(request.borrowed()).await
+  }
+  /// Register a user from borrowed input
+  ///
+  /// Same as [`Auth::register`] without requiring owned `String`s.
+  pub async fn register_ref(&self, request: RegisterRef<'_>) -> Result<User> {
+    self
+      .instrument(
+        "register",
+        crate::operations::register::execute(self, request),
+      )
+      .await
+  }
+  /// Register a user and report what else registration did
+  ///
+  /// Like [`Auth::register`], but with
+  /// `AuthBuilder::return_verification_token_on_register` enabled and no
+  /// email sender configured, the outcome carries a freshly issued email
+  /// verification token for the application to deliver however it likes.
+  /// With `AuthBuilder::session_on_register` enabled (and email verification
+  /// not required), it also carries a session for the new user.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let outcome = auth.register_detailed(Register::new(email, password)).await?;
+  /// if let Some(verification) = outcome.verification {
+  ///     push_to_client(&verification.token);
+  /// }
+  /// if let Some(session) = outcome.session {
+  ///     set_session_cookie(&session.token);
+  /// }
+  /// ```
+  pub async fn register_detailed(&self, request: Register) -> Result<RegisterOutcome> {
+    self
+      .instrument(
+        "register",
+        crate::operations::register::execute_detailed(self, request.borrowed()),
+      )
+      .await
+  }
+  /// Register a user with an already-hashed password
+  ///
+  /// Skips password hashing and the password policy, but still validates the
+  /// email and rejects duplicates. No verification email is sent. Intended
+  /// for trusted callers such as migrations: **the caller is responsible for
+  /// the hash's security**, and it must be a format the configured password
+  /// strategy can verify (e.g. an argon2 PHC string) or login will fail.
+  pub async fn register_with_hash(&self, request: RegisterWithHash) -> Result<User> {
+    crate::operations::register::execute_with_hash(self, request).await
+  }
+  /// Check whether an email is valid and not yet registered
+  ///
+  /// An email is taken if it is any user's primary or secondary address,
+  /// the same check `register` makes. Returns
+  /// `AuthError::InvalidEmailFormat` for malformed emails. Useful for
+  /// "email already taken" hints on signup forms, at the cost of revealing
+  /// which emails have accounts.
+  pub async fn is_email_available(&self, email: &str) -> Result<bool> {
+    crate::operations::register::is_email_available(self, email).await
+  }
+  /// List the accounts (authentication providers) linked to a user
+  ///
+  /// Useful for "connected accounts" settings pages. Credentials are never
+  /// included.
+  pub async fn list_accounts(&self, user_id: &str) -> Result<Vec<Account>> {
+    crate::operations::accounts::list(self, user_id).await
+  }
+  /// List a user's active sessions, newest first
+  ///
+  /// For "where you're logged in" pages. Each entry carries the session `id`
+  /// for [`Auth::revoke_session`]; the secret token is never included.
+  ///
+  /// Sessions are ordered by `created_at` descending, then by `id`
+  /// ascending, so sessions created in the same second come back in the
+  /// same order on every call.
+  pub async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionInfo>> {
+    crate::operations::sessions::list(self, user_id).await
+  }
+  /// Revoke one of a user's sessions by id
+  ///
+  /// Fails with `AuthError::InvalidSession` if the session doesn't exist or
+  /// belongs to another user.
+  pub async fn revoke_session(&self, request: RevokeSession) -> Result<()> {
+    crate::operations::sessions::revoke(self, request).await
+  }
+  /// Move a session's expiry to `new_expires_at` (Unix seconds)
+  ///
+  /// For explicitly keeping a session alive, e.g. through a long-running
+  /// task, independent of sliding expiry. The new expiry may be earlier or
+  /// later than the current one, but must be in the future and no further
+  /// ahead than `AuthBuilder::max_session_extension`; otherwise this fails
+  /// with `AuthError::InvalidInput`. Fails with `AuthError::InvalidSession`
+  /// for unknown or expired sessions.
+  pub async fn extend_session(&self, token: &str, new_expires_at: i64) -> Result<Session> {
+    crate::operations::sessions::extend(self, token, new_expires_at).await
   }
   pub async fn login(&self, request: Login) -> Result<Session> {
-    crate::operations::login::execute(self, request).await
+    self.login_ref(request.borrowed()).await
+  }
+  /// Log in from borrowed input
+  ///
+  /// Same as [`Auth::login`] without requiring owned `String`s, e.g.
+  /// `auth.login_ref(LoginRef::new(&body.email, &body.password))`.
+  pub async fn login_ref(&self, request: LoginRef<'_>) -> Result<Session> {
+    self
+      .instrument("login", crate::operations::login::execute(self, request))
+      .await
+  }
+  /// Check a user's password without creating a session
+  ///
+  /// Returns `Ok(false)` for a wrong password and
+  /// `AuthError::InvalidCredentials` if the user has no usable password
+  /// hash (none set, or corrupted). Use it to re-confirm identity before
+  /// sensitive actions such as deleting an account.
+  pub async fn verify_password(&self, user_id: &str, password: &str) -> Result<bool> {
+    crate::operations::login::verify_password(self, user_id, password).await
+  }
+  /// Change a user's password, confirming the current one first
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::InvalidCredentials` if the current password is wrong or
+  ///   the user has no password
+  /// - `AuthError::WeakPassword` if the new password fails the policy
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// auth.change_password(ChangePassword {
+  ///     user_id: user.id.clone(),
+  ///     current_password: body.current,
+  ///     new_password: body.new,
+  /// })
+  /// .await?;
+  /// ```
+  pub async fn change_password(&self, request: ChangePassword) -> Result<()> {
+    crate::operations::change_password::execute(self, request).await
+  }
+  /// Hash a password with the configured password strategy
+  ///
+  /// For passwords stored outside AuthKit's tables, e.g. in an invitations
+  /// table, with the same algorithm and parameters as user passwords. Check
+  /// them with [`Auth::verify_password_hash`]. The password is only checked
+  /// against `InputLimits`, not the password policy.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let hash = auth.hash_password(&invite.password).await?;
+  /// ```
+  pub async fn hash_password(&self, password: &str) -> Result<String> {
+    self.inner.input_limits.check_password(password)?;
+    self.inner.password_strategy.hash_password(password).await
   }
+  /// Check a password against a hash from [`Auth::hash_password`]
+  ///
+  /// Returns `Ok(false)` for a wrong password; a hash the configured
+  /// strategy can't parse is an error.
+  pub async fn verify_password_hash(&self, password: &str, hash: &str) -> Result<bool> {
+    self.inner.input_limits.check_password(password)?;
+    self
+      .inner
+      .password_strategy
+      .verify_password(password, hash)
+      .await
+  }
+  /// Resolve a session token to its user
+  ///
+  /// Fails closed: unknown, expired or malformed tokens, and session lookups
+  /// that fail in the database, all return `AuthError::InvalidSession`.
   pub async fn verify(&self, request: Verify) -> Result<User> {
-    crate::operations::verify::execute(self, request).await
+    self.verify_ref(request.borrowed()).await
+  }
+  /// Verify a borrowed session token
+  ///
+  /// Same as [`Auth::verify`] without copying the token into a `String`.
+  pub async fn verify_ref(&self, request: VerifyRef<'_>) -> Result<User> {
+    self
+      .instrument("verify", crate::operations::verify::execute(self, request))
+      .await
   }
+  /// Resolve a session token to just its user's ID
+  ///
+  /// Same checks as [`Auth::verify`], but only the session row is read; for
+  /// rate limiters and middleware that key on the user without needing
+  /// their profile.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let user_id = auth.resolve_user_id(&token).await?;
+  /// limiter.check(&user_id)?;
+  /// ```
+  pub async fn resolve_user_id(&self, token: &str) -> Result<String> {
+    self
+      .instrument(
+        "verify",
+        crate::operations::verify::resolve_user_id(self, token),
+      )
+      .await
+  }
+  /// Verify a session token and also report how long the session has left
+  ///
+  /// Same checks and cost as [`Auth::verify`]; useful for clients that
+  /// refresh or rotate sessions before they expire.
+  pub async fn verify_full(&self, request: Verify) -> Result<VerifiedSession> {
+    self
+      .instrument(
+        "verify",
+        crate::operations::verify::execute_full(self, request.borrowed()),
+      )
+      .await
+  }
+  /// Verify a session token taken from an Authorization header and/or a cookie
+  ///
+  /// Returns the user along with which source authenticated, so callers can
+  /// e.g. set cache headers differently for cookie and API-token requests.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let verified = auth
+  ///     .verify_from_sources(Some("Bearer abc123"), cookie_value.as_deref())
+  ///     .await?;
+  ///
+  /// if verified.source == TokenSource::Cookie {
+  ///     // ...
+  /// }
+  /// ```
+  pub async fn verify_from_sources(
+    &self,
+    header: Option<&str>,
+    cookie: Option<&str>,
+  ) -> Result<SourceVerification> {
+    crate::operations::verify::execute_from_sources(self, header, cookie).await
+  }
+  /// Derive the CSRF token for a session
+  ///
+  /// Send it to the client in a readable cookie or embed it in forms (see
+  /// the `http` module helpers); state-changing requests then echo it back
+  /// for [`Auth::verify_csrf`]. Fails with `AuthError::ConfigError` unless
+  /// `AuthBuilder::csrf_secret` is set.
+  pub fn csrf_token(&self, session_token: &str) -> Result<String> {
+    let signer = self
+      .inner
+      .csrf_signer
+      .as_ref()
+      .ok_or_else(|| AuthError::ConfigError("csrf_token requires a csrf_secret".to_string()))?;
+    Ok(crate::security::csrf::derive(signer, session_token))
+  }
+  /// Check that a submitted CSRF token belongs to the session
+  ///
+  /// Only ties the two together; verify the session itself as usual.
+  /// Returns `false` when no `csrf_secret` is configured.
+  pub fn verify_csrf(&self, session_token: &str, csrf_token: &str) -> bool {
+    self
+      .inner
+      .csrf_signer
+      .as_ref()
+      .is_some_and(|signer| crate::security::csrf::verify(signer, session_token, csrf_token))
+  }
+  /// Log out by deleting the token's session
+  ///
+  /// With `AuthBuilder::logout_scope(LogoutScope::AllSessions)`, every
+  /// session of the token's user is deleted instead. Unknown tokens are
+  /// ignored.
   pub async fn logout(&self, request: Logout) -> Result<()> {
     crate::operations::logout::execute(self, request).await
   }
+  /// Rotate a session token, invalidating the old one
+  ///
+  /// Returns the same session with a new token. Use after re-authentication
+  /// or 2FA to prevent session fixation.
+  pub async fn rotate_session(&self, request: RotateSession) -> Result<Session> {
+    crate::operations::rotate_session::execute(self, request).await
+  }
+  /// Walk all sessions in pages ordered by id, for maintenance tooling
+  ///
+  /// Pass `None` for the first page, then each page's `next_cursor` until it
+  /// is `None`. Expired sessions are included. Each session's `token` is the
+  /// stored value, i.e. a hash when `hash_session_tokens` is enabled.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let mut cursor = None;
+  /// loop {
+  ///     let page = auth.scan_sessions(cursor.as_deref(), 500).await?;
+  ///     for session in &page.sessions {
+  ///         audit(session);
+  ///     }
+  ///     match page.next_cursor {
+  ///         Some(next) => cursor = Some(next),
+  ///         None => break,
+  ///     }
+  /// }
+  /// ```
+  pub async fn scan_sessions(&self, after: Option<&str>, limit: u32) -> Result<SessionPage> {
+    crate::operations::scan_sessions::execute(self, after, limit).await
+  }
   pub async fn send_email_verification(
     &self,
     request: SendEmailVerification,
   ) -> Result<VerificationToken> {
-    crate::operations::email_verification::send_email_verification(self, request).await
+    self
+      .instrument(
+        "send_email_verification",
+        crate::operations::email_verification::send_email_verification(self, request),
+      )
+      .await
   }
+  /// Send a verification email, reporting how it was delivered
+  ///
+  /// Like [`Auth::send_email_verification`], but the result says whether
+  /// the email was sent, queued, left to the application (no sender), or
+  /// failed to send. A failed send is not an error here: the token is
+  /// still valid and can be delivered again.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let outcome = auth
+  ///     .send_email_verification_detailed(SendEmailVerification { user_id })
+  ///     .await?;
+  /// let message = match outcome.status {
+  ///     SendStatus::Sent | SendStatus::Queued => "Check your inbox",
+  ///     SendStatus::TokenOnly => "Verification token issued",
+  ///     SendStatus::Failed => "We couldn't send the email, please request a new one",
+  /// };
+  /// ```
+  pub async fn send_email_verification_detailed(
+    &self,
+    request: SendEmailVerification,
+  ) -> Result<SendVerificationOutcome> {
+    self
+      .instrument(
+        "send_email_verification",
+        crate::operations::email_verification::send_email_verification_detailed(self, request),
+      )
+      .await
+  }
+  /// Send verification emails to several users at once
+  ///
+  /// For imports and bulk onboarding. Tokens are issued for every user
+  /// first, then each email is queued or sent as with
+  /// [`Auth::send_email_verification_detailed`]. Returns one entry per user
+  /// id, in order; a user that can't be sent a verification (unknown,
+  /// already verified, in cooldown) gets an error without stopping the rest.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let results = auth.send_verification_emails(&imported_ids).await;
+  /// for entry in results {
+  ///     if let Err(e) = entry.result {
+  ///         eprintln!("{}: {}", entry.user_id, e);
+  ///     }
+  /// }
+  /// ```
+  pub async fn send_verification_emails(
+    &self,
+    user_ids: &[String],
+  ) -> Vec<BatchVerificationResult> {
+    crate::operations::email_verification::send_verification_emails(self, user_ids).await
+  }
+  /// Issue a 6-digit email verification code, valid for 10 minutes
+  ///
+  /// For OTP-style verification instead of links. The code is sent through
+  /// the email sender (as `EmailContext::token`) when one is configured, and
+  /// returned either way. Confirm it with
+  /// [`Auth::verify_email_with_identifier`]. Subject to the same send
+  /// cooldown and resend cap as verification links.
+  pub async fn send_email_verification_code(
+    &self,
+    request: SendEmailVerification,
+  ) -> Result<VerificationToken> {
+    self
+      .instrument(
+        "send_email_verification_code",
+        crate::operations::email_verification::send_email_verification_code(self, request),
+      )
+      .await
+  }
+  /// Verify an email address with a code from
+  /// [`Auth::send_email_verification_code`]
+  ///
+  /// The code is looked up by the email it was issued to, so a code never
+  /// matches another user's address. After 5 wrong codes for an email, its
+  /// outstanding codes are invalidated and a new one has to be requested.
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::InvalidToken` if no code for `email` matches
+  /// - `AuthError::RateLimitExceeded` if the email had too many wrong codes
+  ///   in the last 15 minutes
+  /// - `AuthError::TokenAlreadyUsed` if the code was already consumed
+  /// - `AuthError::TokenExpired` if the code has expired
+  /// - `AuthError::EmailAlreadyVerified` if the email is already verified
+  pub async fn verify_email_with_identifier(&self, email: &str, code: &str) -> Result<User> {
+    self
+      .instrument(
+        "verify_email_with_identifier",
+        crate::operations::email_verification::verify_email_with_identifier(self, email, code),
+      )
+      .await
+  }
+  /// Verify an email address with a token
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::InvalidToken` if the token doesn't exist
+  /// - `AuthError::TokenAlreadyUsed` if the token was already consumed
+  /// - `AuthError::TokenExpired` if the token has expired
+  /// - `AuthError::EmailAlreadyVerified` if the email is already verified
+  ///
+  /// With `AuthBuilder::idempotent_email_verify`, a token that would fail
+  /// with `TokenAlreadyUsed` or `EmailAlreadyVerified` returns the verified
+  /// user instead.
   pub async fn verify_email(&self, request: VerifyEmail) -> Result<User> {
-    crate::operations::email_verification::verify_email(self, request).await
+    self
+      .instrument(
+        "verify_email",
+        crate::operations::email_verification::verify_email(self, request),
+      )
+      .await
   }
+  /// Issue a one-time code to put in a verification link
+  ///
+  /// Keeps the verification token out of URLs: the email links to your
+  /// landing page with this code, and the page's handler exchanges it
+  /// server-side with [`Auth::exchange_verification_code`] before
+  /// redirecting to a URL without it. A code that later leaks through
+  /// browser history or a `Referer` header is already spent. Codes expire
+  /// after `AuthBuilder::verification_link_code_ttl` (1 hour by default).
+  /// Nothing is sent.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let code = auth.issue_verification_link_code(&user.id).await?;
+  /// let link = format!("https://example.com/verify?code={}", code.token);
+  /// mailer.send(&code.identifier, &link).await?;
+  ///
+  /// // GET /verify?code=...
+  /// auth.exchange_verification_code(&query.code).await?;
+  /// return Redirect::to("/verified");
+  /// ```
+  pub async fn issue_verification_link_code(&self, user_id: &str) -> Result<VerificationToken> {
+    crate::operations::email_verification::issue_verification_link_code(self, user_id).await
+  }
+  /// Verify a user's email with a code from
+  /// [`Auth::issue_verification_link_code`]
+  ///
+  /// Each code works once. Errors as [`Auth::verify_email`] does, e.g.
+  /// `AuthError::TokenAlreadyUsed` for a code that was already exchanged.
+  pub async fn exchange_verification_code(&self, code: &str) -> Result<User> {
+    self
+      .instrument(
+        "verify_email",
+        crate::operations::email_verification::exchange_verification_code(self, code),
+      )
+      .await
+  }
+
   pub async fn resend_email_verification(
     &self,
     request: ResendEmailVerification,
   ) -> Result<VerificationToken> {
-    crate::operations::email_verification::resend_email_verification(self, request).await
+    self
+      .instrument(
+        "resend_email_verification",
+        crate::operations::email_verification::resend_email_verification(self, request),
+      )
+      .await
+  }
+  /// Find users who registered before `cutoff` (Unix seconds) and never
+  /// verified their email, oldest first
+  ///
+  /// Intended for reminder jobs that resend verification to stale signups.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let week_ago = now - 7 * 24 * 60 * 60;
+  /// for user in auth.find_unverified_users_registered_before(week_ago).await? {
+  ///     auth
+  ///         .resend_email_verification(ResendEmailVerification { email: user.email })
+  ///         .await?;
+  /// }
+  /// ```
+  pub async fn find_unverified_users_registered_before(&self, cutoff: i64) -> Result<Vec<User>> {
+    crate::operations::email_verification::find_unverified_users_registered_before(self, cutoff)
+      .await
+  }
+  /// Get a user's email verification status in one call
+  ///
+  /// Reports whether the email is verified (and when), and whether an
+  /// unused verification token is outstanding along with its expiry. Fails
+  /// with `AuthError::UserNotFound` for unknown users.
+  pub async fn verification_status(&self, user_id: &str) -> Result<VerificationStatusReport> {
+    crate::operations::email_verification::verification_status(self, user_id).await
+  }
+  /// Find the user an email verification token belongs to, without
+  /// consuming it
+  ///
+  /// Meant for support tooling ("my verification link doesn't work").
+  /// Expired and already-used tokens still resolve their user; the token
+  /// itself is left untouched. Returns `None` for unknown tokens.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// if let Some(user) = auth.user_for_verification_token(&token).await? {
+  ///     let status = auth.verification_status(&user.id).await?;
+  ///     println!("{} verified: {}", user.email, status.email_verified);
+  /// }
+  /// ```
+  pub async fn user_for_verification_token(&self, token: &str) -> Result<Option<User>> {
+    crate::operations::email_verification::user_for_verification_token(self, token).await
+  }
+  /// Issue a verification token for any identifier (email, phone number, ...)
+  ///
+  /// Unlike `send_email_verification`, nothing is sent; deliver the token
+  /// yourself and confirm it with [`Auth::consume_verification`]. Fails with
+  /// `AuthError::InvalidInput` for an empty identifier, or for a
+  /// `TokenType::Custom` whose name is empty or used by a built-in type.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let token = auth
+  ///     .issue_verification(IssueVerification {
+  ///         identifier: "+15555550123".into(),
+  ///         token_type: TokenType::Custom("whatsapp_verification"),
+  ///         user_id: Some(user.id.clone()),
+  ///         expires_in: Duration::from_secs(10 * 60),
+  ///     })
+  ///     .await?;
+  /// sms.send(&token.identifier, &token.token).await?;
+  /// ```
+  pub async fn issue_verification(&self, request: IssueVerification) -> Result<VerificationToken> {
+    crate::operations::identifier_verification::issue(self, request).await
+  }
+  /// Verify a token from [`Auth::issue_verification`] and mark it as used
+  ///
+  /// Returns the identifier the token was issued for. The token only
+  /// verifies against the `token_type` it was issued with. Custom token
+  /// types are checked as in [`Auth::issue_verification`].
+  pub async fn consume_verification(
+    &self,
+    request: ConsumeVerification,
+  ) -> Result<VerifiedIdentifier> {
+    crate::operations::identifier_verification::consume(self, request).await
+  }
+  /// Invite a user by email, without a password
+  ///
+  /// Creates the user and returns an invitation token valid for 7 days;
+  /// nothing is sent, so deliver the token yourself. The user can't log in
+  /// until they accept with [`Auth::accept_invitation`].
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let invitation = auth
+  ///     .invite_user(InviteUser {
+  ///         email: "new.hire@example.com".into(),
+  ///         name: Some("New Hire".into()),
+  ///     })
+  ///     .await?;
+  /// mailer.send_invite(&invitation.user.email, &invitation.verification.token).await?;
+  /// ```
+  pub async fn invite_user(&self, request: InviteUser) -> Result<Invitation> {
+    crate::operations::invitation::invite(self, request).await
+  }
+  /// Accept an invitation from [`Auth::invite_user`] and set a password
+  ///
+  /// Consumes the token, then creates the credential account and marks the
+  /// email verified in one transaction. The password must satisfy the
+  /// configured `PasswordPolicy`. Returns the now-verified user; log them in
+  /// as usual to get a session.
+  pub async fn accept_invitation(&self, request: AcceptInvitation) -> Result<User> {
+    crate::operations::invitation::accept(self, request).await
+  }
+  /// Log out every user by deleting all sessions
+  ///
+  /// **Dangerous:** meant for incident response, e.g. after a session
+  /// secret or database leak. Every session token stops working
+  /// immediately and all users must log in again. Returns the number of
+  /// sessions deleted.
+  ///
+  /// Takes effect from the instant it runs: every session existing then is
+  /// deleted in one statement, all or nothing, while logins that complete
+  /// afterwards are kept.
+  pub async fn logout_all_users(&self) -> Result<u64> {
+    crate::operations::incident::logout_all_users(self).await
+  }
+  /// Invalidate every outstanding verification token across all users
+  ///
+  /// **Dangerous:** meant for incident response, e.g. a suspected breach.
+  /// Deletes all verification, reset, magic-link and custom tokens, or only
+  /// those of `token_type`, so links and codes already sent stop working.
+  /// Returns the number of tokens deleted.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// // Kill every password-reset link, leave the rest alone
+  /// let revoked = auth.invalidate_all_tokens(Some(TokenType::PasswordReset)).await?;
+  /// ```
+  pub async fn invalidate_all_tokens(&self, token_type: Option<TokenType>) -> Result<u64> {
+    crate::operations::incident::invalidate_all_tokens(self, token_type).await
+  }
+  /// Report whether a token is a session token, a verification token, or
+  /// neither, without verifying or consuming it
+  ///
+  /// Expired and already-used tokens are still classified; pass the token
+  /// to the matching operation to check that it's valid.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// match auth.classify_token(&token).await? {
+  ///     TokenKind::Session => { /* auth.verify(...) */ }
+  ///     TokenKind::Verification(kind) if kind == "email_verification" => {
+  ///         auth.verify_email(VerifyEmail { token }).await?;
+  ///     }
+  ///     _ => return Err(AuthError::InvalidToken("Unrecognized token".into())),
+  /// }
+  /// ```
+  pub async fn classify_token(&self, token: &str) -> Result<TokenKind> {
+    crate::operations::classify_token::execute(self, token).await
+  }
+  /// Add an unverified secondary email address to a user
+  ///
+  /// Returns a verification token for the new address, which is also sent
+  /// through the email sender if one is configured. A failed send is logged
+  /// and the token is still returned. Confirm it with
+  /// [`Auth::verify_secondary_email`].
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::UserNotFound` if the user doesn't exist
+  /// - `AuthError::UserAlreadyExists` if any user already uses the address
+  pub async fn add_email(&self, request: AddEmail) -> Result<VerificationToken> {
+    crate::operations::user_emails::add_email(self, request).await
+  }
+  /// Verify a secondary email address with a token from [`Auth::add_email`]
+  pub async fn verify_secondary_email(&self, request: VerifySecondaryEmail) -> Result<UserEmail> {
+    crate::operations::user_emails::verify_secondary_email(self, request).await
+  }
+  /// Make a verified secondary email the user's primary (login) email
+  ///
+  /// The previous primary email is kept as a secondary address.
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::EmailNotFound` if the address isn't one of the user's emails
+  /// - `AuthError::EmailNotVerified` if the address hasn't been verified yet
+  pub async fn set_primary_email(&self, request: SetPrimaryEmail) -> Result<User> {
+    crate::operations::user_emails::set_primary_email(self, request).await
+  }
+  /// Issue and send a new verification token for an unverified secondary
+  /// email
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::EmailNotFound` if the address isn't one of the user's
+  ///   secondary emails
+  /// - `AuthError::EmailAlreadyVerified` if the address is already verified
+  /// - `AuthError::Cooldown` if `email_send_cooldown` hasn't elapsed since the
+  ///   last send to the address
+  /// - `AuthError::RateLimitExceeded` if the user has used up
+  ///   `max_verification_resends` for secondary emails
+  pub async fn resend_secondary_email_verification(
+    &self,
+    user_id: &str,
+    email: &str,
+  ) -> Result<VerificationToken> {
+    crate::operations::user_emails::resend_secondary_email_verification(self, user_id, email).await
+  }
+  /// List all email addresses of a user, primary first
+  pub async fn list_emails(&self, user_id: &str) -> Result<Vec<UserEmail>> {
+    crate::operations::user_emails::list_emails(self, user_id).await
+  }
+  /// List the user's email addresses and phone number still awaiting
+  /// verification
+  ///
+  /// For a settings page offering "resend" per channel: resend an email with
+  /// [`Auth::resend_email_verification`] (primary) or
+  /// [`Auth::resend_secondary_email_verification`] (secondary), or a phone
+  /// code with `Auth::send_phone_verification`.
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::UserNotFound` if the user doesn't exist
+  pub async fn unverified_identifiers(&self, user_id: &str) -> Result<Vec<UnverifiedIdentifier>> {
+    crate::operations::user_emails::unverified_identifiers(self, user_id).await
+  }
+
+  /// Set a user's phone number and send it a numeric verification code
+  ///
+  /// The code is returned as well; if the SMS sender fails, the error is
+  /// logged and the code can still be delivered another way.
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::InvalidPhoneFormat` if the number isn't in E.164 format
+  /// - `AuthError::UserNotFound` if the user doesn't exist
+  /// - `AuthError::PhoneAlreadyVerified` if this number is already verified
+  #[cfg(feature = "sms")]
+  pub async fn send_phone_verification(
+    &self,
+    request: crate::operations::SendPhoneVerification,
+  ) -> Result<VerificationToken> {
+    crate::operations::phone_verification::send_phone_verification(self, request).await
+  }
+  /// Verify a user's phone number with the code sent by SMS
+  ///
+  /// After 5 wrong codes for the number, its outstanding codes are
+  /// invalidated and attempts fail with `AuthError::RateLimitExceeded` for
+  /// 15 minutes.
+  #[cfg(feature = "sms")]
+  pub async fn verify_phone(
+    &self,
+    request: crate::operations::VerifyPhone,
+  ) -> Result<crate::operations::VerifiedPhone> {
+    crate::operations::phone_verification::verify_phone(self, request).await
+  }
+
+  /// Recover a verification token's plaintext from its stored value
+  ///
+  /// Only available when tokens are encrypted at rest
+  /// (`AuthBuilder::token_encryption_key`). Intended for support tooling.
+  #[cfg(feature = "encrypted-tokens")]
+  pub fn decrypt_verification_token(&self, stored: &str) -> Result<String> {
+    self.inner.token_strategy.decrypt_stored_token(stored)
+  }
+
+  /// The store holding rate-limit counters
+  ///
+  /// Shared by every clone of this `Auth`, and across instances when a
+  /// shared store is configured with `AuthBuilder::rate_limit_store`.
+  pub fn rate_limit_store(&self) -> &dyn RateLimitStore {
+    self.inner.rate_limit_store.as_ref()
   }
 
   /// Check if an email sender is configured
@@ -92,9 +1055,41 @@ impl Auth {
     self.inner.email_sender.is_some()
   }
 
+  /// Run an operation through the metrics recorder, if one is installed
+  #[cfg(not(feature = "otel"))]
+  async fn instrument<T>(
+    &self,
+    op: &str,
+    operation: impl std::future::Future<Output = Result<T>>,
+  ) -> Result<T> {
+    crate::metrics::instrument(self.inner.metrics_recorder.as_ref(), op, operation).await
+  }
+
+  /// Run an operation through the metrics recorder, if one is installed,
+  /// inside an `authkit.{op}` span
+  #[cfg(feature = "otel")]
+  async fn instrument<T: crate::telemetry::Traced>(
+    &self,
+    op: &str,
+    operation: impl std::future::Future<Output = Result<T>>,
+  ) -> Result<T> {
+    let operation = crate::metrics::instrument(self.inner.metrics_recorder.as_ref(), op, operation);
+    self
+      .inner
+      .telemetry
+      .in_span(
+        format!("authkit.{}", op),
+        &opentelemetry::Context::current(),
+        Vec::new(),
+        operation,
+      )
+      .await
+  }
+
   /// Check if verification emails are sent automatically on registration
   pub fn sends_verification_on_register(&self) -> bool {
-    self.inner.send_verification_on_register
+    self.inner.send_verification_on_register != VerificationOnRegister::Never
+      && self.inner.email_sender.is_some()
   }
 
   /// Check if login requires email verification
@@ -142,25 +1137,131 @@ impl Auth {
       .clone()
       .expect("email_queue must be configured");
 
+    #[cfg(feature = "persistent-queue")]
+    let (queue, worker) = if config.persistent {
+      let store =
+        crate::email_job::JobStore::new(self.inner.db.clone(), self.inner.token_generator.clone());
+      #[cfg(feature = "encrypted-tokens")]
+      let store = store.with_cipher(self.inner.token_cipher.clone());
+      crate::email_job::create_persistent_email_queue(email_sender, config, store)
+    } else {
+      crate::email_job::create_email_queue(email_sender, config)
+    };
+    #[cfg(not(feature = "persistent-queue"))]
     let (queue, worker) = crate::email_job::create_email_queue(email_sender, config);
+    let worker = worker.with_metrics_recorder(self.inner.metrics_recorder.clone());
+    #[cfg(feature = "otel")]
+    let worker = worker.with_telemetry(self.inner.telemetry.clone());
 
     let handle = tokio::spawn(worker.run());
+    self.inner.email_worker.set(&queue);
 
     EmailWorkerHandle::new(handle, queue)
   }
 
+  /// Check whether the email worker is running and accepting jobs
+  ///
+  /// Returns false before `start_email_worker` is called, and after the
+  /// worker panics, is aborted, or its handle is shut down or dropped. While
+  /// unhealthy, operations send emails synchronously instead.
+  #[cfg(feature = "email-queue")]
+  pub fn email_worker_healthy(&self) -> bool {
+    self.inner.email_worker.queue().is_some()
+  }
+
+  /// Start a replacement email worker
+  ///
+  /// Use after `email_worker_healthy` reports false. Jobs that were still
+  /// queued to a crashed worker are lost, unless the queue is persistent
+  /// (`EmailWorkerConfig::persistent`). Operations switch to the new
+  /// worker's queue immediately.
+  ///
+  /// # Panics
+  ///
+  /// Same as [`Auth::start_email_worker`].
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// if !auth.email_worker_healthy() {
+  ///     log::warn!("email worker died, restarting");
+  ///     handle = auth.restart_email_worker();
+  /// }
+  /// ```
+  #[cfg(feature = "email-queue")]
+  pub fn restart_email_worker(&self) -> EmailWorkerHandle {
+    self.start_email_worker()
+  }
+
   /// Check if email queue is enabled
   #[cfg(feature = "email-queue")]
   pub fn has_email_queue(&self) -> bool {
-    self.inner.email_queue.is_some()
+    self.inner.email_worker_config.is_some()
   }
 
-  /// Get a clone of the email queue (if enabled)
+  /// Get a clone of the running email worker's queue
+  ///
+  /// `None` until `start_email_worker` is called, or once the worker stops.
   #[cfg(feature = "email-queue")]
   pub fn email_queue(&self) -> Option<EmailQueue> {
-    self.inner.email_queue.clone()
+    self.inner.email_worker.queue()
+  }
+
+  /// Queue the same custom email to many recipients
+  ///
+  /// Each recipient gets an `EmailJobType::Custom` job with `template_id`
+  /// and `data`. Delivery is paced by `EmailWorkerConfig::max_send_rate`.
+  /// Returns the number of jobs queued.
+  ///
+  /// # Errors
+  ///
+  /// - `AuthError::ConfigError` if no email queue is configured
+  /// - `AuthError::EmailSendFailed` if the worker isn't running or the
+  ///   queue is full
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let queued = auth
+  ///     .enqueue_announcement(subscribers, "product_update", data)
+  ///     .await?;
+  /// ```
+  #[cfg(feature = "email-queue")]
+  pub async fn enqueue_announcement(
+    &self,
+    recipients: impl IntoIterator<Item = impl Into<String>>,
+    template_id: &str,
+    data: std::collections::HashMap<String, String>,
+  ) -> Result<usize> {
+    if !self.has_email_queue() {
+      return Err(crate::AuthError::ConfigError(
+        "email queue is not configured".to_string(),
+      ));
+    }
+    let queue = self.email_queue().ok_or_else(|| {
+      crate::AuthError::EmailSendFailed("email worker is not running".to_string())
+    })?;
+
+    let jobs = recipients
+      .into_iter()
+      .map(|recipient| {
+        crate::email_job::EmailJob::custom(recipient.into(), template_id.to_string(), data.clone())
+      })
+      .collect();
+
+    queue
+      .enqueue_batch(jobs)
+      .await
+      .map_err(|e| crate::AuthError::EmailSendFailed(e.to_string()))
   }
 }
 
-unsafe impl Send for Auth {}
-unsafe impl Sync for Auth {}
+// Compile-time check: Auth must be shareable across tasks and threads.
+// Every field of AuthInner has to be Send + Sync on its own (trait objects
+// carry explicit `Send + Sync` bounds), so a non-thread-safe addition fails
+// to compile here instead of being masked by an unsafe impl.
+const _: () = {
+  const fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<Auth>();
+  assert_send_sync::<AuthInner>();
+};
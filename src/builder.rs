@@ -3,29 +3,169 @@ use crate::email::EmailSender;
 #[cfg(feature = "email-queue")]
 use crate::email_job::EmailWorkerConfig;
 use crate::error::{AuthError, Result};
+use crate::metrics::MetricsRecorder;
+use crate::operations::{LogoutScope, TokenSource, VerificationOnRegister};
+use crate::rate_limit::{InMemoryRateLimitStore, RateLimitStore};
+use crate::retry::RetryPolicy;
+use crate::security::signing::{TokenSigner, MIN_SECRET_LENGTH};
+use crate::security::tokens::{OsTokenGenerator, TokenGenerator, TokenHashAlgo};
+#[cfg(feature = "sms")]
+use crate::sms::SmsSender;
 use crate::strategies::password::PasswordStrategyType;
-use crate::strategies::session::SessionStrategyType;
-use crate::strategies::token::TokenStrategyType;
+use crate::strategies::session::{SessionStrategyOptions, SessionStrategyType};
+use crate::strategies::token::{TokenStrategyOptions, TokenStrategyType};
+use crate::token_store::TokenStore;
 use crate::types::Database;
+use crate::validation::email::EmailValidator;
+use crate::validation::limits::InputLimits;
+use crate::validation::password::PasswordPolicy;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest `session_token_prefix` accepted; more gives no better spread
+const MAX_SESSION_TOKEN_PREFIX: usize = 16;
 
 pub struct AuthBuilder {
   database: Option<Database>,
   password_strategy: Option<PasswordStrategyType>,
   session_strategy: Option<SessionStrategyType>,
+  hash_session_tokens: bool,
+  session_token_prefix: Option<usize>,
+  session_signer: Option<TokenSigner>,
+  csrf_signer: Option<TokenSigner>,
   token_strategy: Option<TokenStrategyType>,
+  token_hash: TokenHashAlgo,
   email_sender: Option<Box<dyn EmailSender>>,
+  token_generator: Option<Arc<dyn TokenGenerator>>,
+  metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+  rate_limit_store: Option<Arc<dyn RateLimitStore>>,
+  token_store: Option<Arc<dyn TokenStore>>,
 
   /// Whether to automatically send verification email on registration
   /// Defaults to false
-  send_verification_on_register: bool,
+  send_verification_on_register: VerificationOnRegister,
+
+  /// Whether a failed verification email on registration fails `register`
+  /// Defaults to false
+  fail_registration_on_email_error: bool,
+
+  /// Whether verifying an already verified email returns the user
+  /// Defaults to false
+  idempotent_email_verify: bool,
+
+  /// Whether `register_detailed` returns a verification token when no email
+  /// sender is configured
+  /// Defaults to false
+  return_verification_token_on_register: bool,
+
+  /// Whether `register_detailed` logs the new user in
+  /// Defaults to false
+  session_on_register: bool,
 
   /// Whether login requires email to be verified
   /// Defaults to false
   require_email_verification: bool,
 
+  /// Emails (lowercased) that may log in unverified
+  /// Defaults to none
+  verification_exempt_emails: HashSet<String>,
+
+  /// Whether an unverified login resends the verification email
+  /// Defaults to false
+  resend_verification_on_login: bool,
+
+  /// Maximum sizes for emails, passwords, names, and tokens
+  input_limits: InputLimits,
+
+  /// Whether surrounding whitespace is stripped from submitted tokens
+  /// Defaults to true
+  trim_token_input: bool,
+
+  /// How email addresses are validated
+  /// Defaults to `EmailValidator::Basic`
+  email_validator: EmailValidator,
+
+  /// Provider name of email/password accounts
+  /// Defaults to "credential"
+  credential_provider: String,
+
+  /// Requirements for new passwords
+  /// Defaults to 8+ characters with upper, lower and digit
+  password_policy: PasswordPolicy,
+
+  /// Idle time after which `verify` rejects a session
+  /// Defaults to None (sessions only expire at `expires_at`)
+  inactivity_timeout: Option<Duration>,
+
+  /// Lifetime of sessions created with `Login.remember_me`
+  /// Defaults to 30 days
+  remember_me_duration: Duration,
+
+  /// Furthest ahead `Auth::extend_session` may push a session's expiry
+  /// Defaults to 30 days
+  max_session_extension: Duration,
+
+  /// How many times login retries with a fresh token when the new session's
+  /// token collides with an existing one
+  /// Defaults to 3
+  session_collision_retries: u32,
+
+  /// Which token source `verify_from_sources` tries first
+  /// Defaults to the Authorization header
+  preferred_token_source: TokenSource,
+
+  /// Which sessions `Auth::logout` deletes
+  /// Defaults to only the given session
+  logout_scope: LogoutScope,
+
+  /// Whether a password change deletes the user's sessions
+  /// Defaults to true
+  invalidate_sessions_on_password_change: bool,
+
+  /// Minimum time between verification emails to the same address
+  /// Defaults to None (no cooldown)
+  email_send_cooldown: Option<Duration>,
+
+  /// Maximum verification resends per user in a rolling 24 hours
+  /// Defaults to None (unlimited)
+  max_verification_resends: Option<u32>,
+
+  /// Failed logins allowed per email within a window
+  /// Defaults to None (unlimited)
+  login_attempt_limit: Option<(u32, Duration)>,
+
+  /// Lifetime of codes from `issue_verification_link_code`
+  /// Defaults to 1 hour
+  verification_link_code_ttl: Duration,
+
+  /// Retry policy for database calls that fail with a transient error
+  /// Defaults to None (no retries)
+  db_retry: Option<RetryPolicy>,
+
+  /// Maximum password hashes/verifications running at once
+  /// Defaults to None (unbounded)
+  max_concurrent_hashes: Option<usize>,
+
+  /// Whether resends reuse a still-valid (encrypted) verification token
+  /// Defaults to false
+  #[cfg(feature = "encrypted-tokens")]
+  reuse_valid_verification_tokens: bool,
+
+  /// Key for encrypting verification tokens at rest
+  #[cfg(feature = "encrypted-tokens")]
+  token_encryption_key: Option<[u8; 32]>,
+
+  #[cfg(feature = "sms")]
+  sms_sender: Option<Box<dyn SmsSender>>,
+
   #[cfg(feature = "email-queue")]
   email_queue_config: Option<EmailWorkerConfig>,
+
+  /// Tracer for operation and email spans
+  /// Defaults to None (the global tracer provider)
+  #[cfg(feature = "otel")]
+  tracer: Option<opentelemetry::global::BoxedTracer>,
 }
 
 impl AuthBuilder {
@@ -34,12 +174,53 @@ impl AuthBuilder {
       database: None,
       password_strategy: None,
       session_strategy: None,
+      hash_session_tokens: false,
+      session_token_prefix: None,
+      session_signer: None,
+      csrf_signer: None,
       token_strategy: None,
+      token_hash: TokenHashAlgo::default(),
       email_sender: None,
-      send_verification_on_register: false,
+      token_generator: None,
+      metrics_recorder: None,
+      rate_limit_store: None,
+      token_store: None,
+      send_verification_on_register: VerificationOnRegister::Never,
+      fail_registration_on_email_error: false,
+      idempotent_email_verify: false,
+      return_verification_token_on_register: false,
+      session_on_register: false,
       require_email_verification: false,
+      verification_exempt_emails: HashSet::new(),
+      resend_verification_on_login: false,
+      input_limits: InputLimits::default(),
+      trim_token_input: true,
+      email_validator: EmailValidator::default(),
+      credential_provider: "credential".to_string(),
+      password_policy: PasswordPolicy::default(),
+      inactivity_timeout: None,
+      remember_me_duration: Duration::from_secs(30 * 24 * 60 * 60),
+      max_session_extension: Duration::from_secs(30 * 24 * 60 * 60),
+      session_collision_retries: 3,
+      preferred_token_source: TokenSource::default(),
+      logout_scope: LogoutScope::default(),
+      invalidate_sessions_on_password_change: true,
+      email_send_cooldown: None,
+      max_verification_resends: None,
+      login_attempt_limit: None,
+      verification_link_code_ttl: Duration::from_secs(60 * 60),
+      db_retry: None,
+      max_concurrent_hashes: None,
+      #[cfg(feature = "encrypted-tokens")]
+      reuse_valid_verification_tokens: false,
+      #[cfg(feature = "encrypted-tokens")]
+      token_encryption_key: None,
+      #[cfg(feature = "sms")]
+      sms_sender: None,
       #[cfg(feature = "email-queue")]
       email_queue_config: None,
+      #[cfg(feature = "otel")]
+      tracer: None,
     }
   }
   pub fn database(mut self, db: Database) -> Self {
@@ -54,11 +235,276 @@ impl AuthBuilder {
     self.session_strategy = Some(strategy);
     self
   }
+
+  /// Store session tokens as SHA-256 hashes instead of plaintext
+  ///
+  /// Recommended, so a leaked database can't be used to hijack live
+  /// sessions. Off by default because turning it on for a database that
+  /// already holds plaintext tokens logs those sessions out: stored tokens
+  /// and their hashes look alike, so there is no safe fallback lookup. New
+  /// deployments should enable it from the start.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .hash_session_tokens(true)
+  ///     .build()?;
+  /// ```
+  pub fn hash_session_tokens(mut self, enabled: bool) -> Self {
+    self.hash_session_tokens = enabled;
+    self
+  }
+
+  /// Store the first `len` characters of each stored session token in
+  /// `sessions.token_prefix`
+  ///
+  /// For operators who partition or shard large session tables. The prefix
+  /// is taken from the stored value (the hash, with `hash_session_tokens`),
+  /// so it reveals nothing the `token` column doesn't. It is indexed, but
+  /// AuthKit never relies on it: lookups always match the full token.
+  /// `len` must be between 1 and 16.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::postgres(&url).await?)
+  ///     .session_token_prefix(4)
+  ///     .build()?;
+  /// ```
+  pub fn session_token_prefix(mut self, len: usize) -> Self {
+    self.session_token_prefix = Some(len);
+    self
+  }
+
+  /// Sign session tokens with HMAC-SHA256, accepting older secrets too
+  ///
+  /// New sessions are signed with `current`. Tokens signed with any secret in
+  /// `previous` keep verifying, so a secret can be rotated without logging
+  /// everyone out: move the old secret into `previous`, then drop it once its
+  /// sessions have expired. Tokens without a valid signature are rejected
+  /// before touching the database. Secrets must be at least 32 bytes.
+  ///
+  /// Enabling signing logs out sessions issued while it was off.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .session_signing_secrets(new_secret, vec![old_secret])
+  ///     .build()?;
+  /// ```
+  pub fn session_signing_secrets(
+    mut self,
+    current: impl Into<Vec<u8>>,
+    previous: Vec<Vec<u8>>,
+  ) -> Self {
+    self.session_signer = Some(TokenSigner::new(current.into(), previous));
+    self
+  }
+
+  /// Set the server secret for CSRF tokens
+  ///
+  /// Enables `Auth::csrf_token` and `Auth::verify_csrf` for the
+  /// double-submit pattern with cookie sessions. CSRF tokens are derived
+  /// from the session token, so nothing is stored; changing the secret
+  /// invalidates every outstanding CSRF token. Must be at least 32 bytes.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .csrf_secret(load_csrf_secret()?)
+  ///     .build()?;
+  /// ```
+  pub fn csrf_secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+    self.csrf_signer = Some(TokenSigner::new(secret.into(), Vec::new()));
+    self
+  }
   pub fn token_strategy(mut self, strategy: TokenStrategyType) -> Self {
     self.token_strategy = Some(strategy);
     self
   }
 
+  /// Set the maximum accepted input sizes
+  ///
+  /// Oversized inputs are rejected with `AuthError::InvalidInput` before any
+  /// hashing or database work. See [`InputLimits`] for the defaults.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .input_limits(InputLimits::default().with_max_email_len(254))
+  ///     .build()?;
+  /// ```
+  pub fn input_limits(mut self, limits: InputLimits) -> Self {
+    self.input_limits = limits;
+    self
+  }
+
+  /// Strip surrounding whitespace from tokens passed to `verify`, `logout`
+  /// and `verify_email`
+  ///
+  /// Tokens copied from an email often pick up a trailing space or newline,
+  /// which would otherwise fail the lookup. Only leading and trailing
+  /// whitespace is removed; generated tokens never contain any.
+  ///
+  /// Enabled by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .trim_token_input(false)
+  ///     .build()?;
+  /// ```
+  pub fn trim_token_input(mut self, trim: bool) -> Self {
+    self.trim_token_input = trim;
+    self
+  }
+
+  /// Choose how email addresses are validated
+  ///
+  /// Applies to registration, `is_email_available` and `add_email`.
+  /// Defaults to `EmailValidator::Basic`; `EmailValidator::Strict` (with the
+  /// `strict-email` feature) accepts any RFC 5322 address.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_validator(EmailValidator::Strict)
+  ///     .build()?;
+  /// ```
+  pub fn email_validator(mut self, validator: EmailValidator) -> Self {
+    self.email_validator = validator;
+    self
+  }
+
+  /// Set the password requirements for registration
+  ///
+  /// Defaults to `PasswordPolicy::default()`: 8 to 128 characters with an
+  /// uppercase letter, a lowercase letter and a digit. `build` fails with
+  /// `AuthError::ConfigError` for a zero minimum or a maximum below it.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .password_policy(PasswordPolicy::default().without_complexity().with_min_length(12))
+  ///     .build()?;
+  /// ```
+  pub fn password_policy(mut self, policy: PasswordPolicy) -> Self {
+    self.password_policy = policy;
+    self
+  }
+
+  /// Set the provider name stored on email/password accounts
+  ///
+  /// Defaults to `"credential"`. Set it to match an existing accounts table
+  /// that labels password accounts differently; registration, login,
+  /// password checks and primary email changes all use it. Like other
+  /// providers it is lowercased, and `build` fails with
+  /// `AuthError::ConfigError` if it is blank or invalid.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::postgres(&url).await?)
+  ///     .credential_provider_name("local")
+  ///     .build()?;
+  /// ```
+  pub fn credential_provider_name(mut self, name: impl Into<String>) -> Self {
+    self.credential_provider = name.into();
+    self
+  }
+
+  /// Reject sessions that have been idle for longer than `timeout`
+  ///
+  /// `verify` records each use of a session; once the gap since the last
+  /// use exceeds the timeout it returns `AuthError::SessionExpired`, even if
+  /// the session's absolute expiry hasn't passed. Disabled by default, in
+  /// which case `verify` doesn't write to the database.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .inactivity_timeout(Duration::from_secs(15 * 60))
+  ///     .build()?;
+  /// ```
+  pub fn inactivity_timeout(mut self, timeout: Duration) -> Self {
+    self.inactivity_timeout = Some(timeout);
+    self
+  }
+
+  /// Set the lifetime of "remember me" sessions
+  ///
+  /// Applies to logins with `remember_me: true`; other sessions last 24
+  /// hours. Defaults to 30 days.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .remember_me_duration(Duration::from_secs(14 * 24 * 60 * 60))
+  ///     .build()?;
+  /// ```
+  pub fn remember_me_duration(mut self, duration: Duration) -> Self {
+    self.remember_me_duration = duration;
+    self
+  }
+
+  /// Cap how far `Auth::extend_session` can extend a session
+  ///
+  /// An extension may set the expiry at most `max` from now; later expiries
+  /// fail with `AuthError::InvalidInput`. Defaults to 30 days.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .max_session_extension(Duration::from_secs(12 * 60 * 60))
+  ///     .build()?;
+  /// ```
+  pub fn max_session_extension(mut self, max: Duration) -> Self {
+    self.max_session_extension = max;
+    self
+  }
+
+  /// Retry session creation when the new token is already taken
+  ///
+  /// Session tokens are random, so a collision should never happen, but if
+  /// one does the insert fails with a unique violation. Login then retries
+  /// with a fresh token up to `retries` times before returning the
+  /// `AuthError::DatabaseError`. Defaults to 3; 0 disables retrying.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .session_collision_retries(5)
+  ///     .build()?;
+  /// ```
+  pub fn session_collision_retries(mut self, retries: u32) -> Self {
+    self.session_collision_retries = retries;
+    self
+  }
+
   /// Set a custom email sender for verification emails
   ///
   /// If not set, verification tokens are generated but emails are not sent automatically.
@@ -91,95 +537,741 @@ impl AuthBuilder {
     self
   }
 
-  /// Configure whether to automatically send verification email on registration
+  /// Set the SMS sender used for phone verification codes
   ///
-  /// When set to `true`, a verification email is automatically sent when a user registers
-  /// (requires `email_sender` to be configured).
-  ///
-  /// When set to `false` (default), registration creates the user but does NOT send a
-  /// verification email. The application must call `send_email_verification()`
-  /// manually if email verification is desired.
+  /// Without a sender, `send_phone_verification` still issues codes and
+  /// returns them for the application to deliver.
   ///
   /// # Example
   ///
   /// ```rust,ignore
-  /// // Enable automatic verification emails on registration
   /// let auth = Auth::builder()
   ///     .database(Database::sqlite("auth.db").await?)
-  ///     .email_sender(Box::new(MyEmailSender))
-  ///     .send_verification_on_register(true)
+  ///     .sms_sender(Box::new(TwilioSender))
   ///     .build()?;
-  ///
-  /// // Register user (verification email sent automatically)
-  /// let user = auth.register(Register { ... }).await?;
   /// ```
-  pub fn send_verification_on_register(mut self, enabled: bool) -> Self {
-    self.send_verification_on_register = enabled;
+  #[cfg(feature = "sms")]
+  pub fn sms_sender(mut self, sender: Box<dyn SmsSender>) -> Self {
+    self.sms_sender = Some(sender);
     self
   }
 
-  /// Configure whether login requires email verification
+  /// Replace the source of randomness for tokens and ids
   ///
-  /// When set to `true`, users cannot login until their email is verified.
-  /// Login will return `AuthError::EmailNotVerified` for unverified users.
+  /// Defaults to `OsTokenGenerator` (the OS secure RNG). Installing a seeded
+  /// generator makes session tokens, verification tokens and ids reproducible,
+  /// which is useful in tests. Never use a predictable generator in production.
   ///
-  /// When set to `false` (default), users can login regardless of email verification status.
+  /// A generator that only overrides `TokenGenerator::generate_id` keeps
+  /// secure tokens while producing ids in another format, such as numeric
+  /// strings for a schema whose existing ids are integers.
   ///
   /// # Example
   ///
   /// ```rust,ignore
-  /// // Require email verification before login
+  /// struct SequenceIds(AtomicU64);
+  ///
+  /// impl TokenGenerator for SequenceIds {
+  ///     fn fill_bytes(&self, dest: &mut [u8]) {
+  ///         OsTokenGenerator.fill_bytes(dest);
+  ///     }
+  ///
+  ///     fn generate_id(&self) -> String {
+  ///         self.0.fetch_add(1, Ordering::Relaxed).to_string()
+  ///     }
+  /// }
+  ///
   /// let auth = Auth::builder()
   ///     .database(Database::sqlite("auth.db").await?)
-  ///     .email_sender(Box::new(MyEmailSender))
-  ///     .send_verification_on_register(true)
-  ///     .require_email_verification(true)
+  ///     .token_generator(SequenceIds(AtomicU64::new(1)))
   ///     .build()?;
+  /// ```
+  pub fn token_generator(mut self, generator: impl TokenGenerator + 'static) -> Self {
+    self.token_generator = Some(Arc::new(generator));
+    self
+  }
+
+  /// Record operation timings and outcome counters
   ///
-  /// // Register user
-  /// let user = auth.register(Register { ... }).await?;
+  /// See [`MetricsRecorder`] for the operation and counter names. Without a
+  /// recorder no timing is done.
   ///
-  /// // This will fail with EmailNotVerified error
-  /// let session = auth.login(Login { ... }).await; // Error!
+  /// # Example
   ///
-  /// // User must verify email first, then login will succeed
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .metrics_recorder(PrometheusRecorder)
+  ///     .build()?;
   /// ```
-  pub fn require_email_verification(mut self, required: bool) -> Self {
-    self.require_email_verification = required;
+  pub fn metrics_recorder(mut self, recorder: impl MetricsRecorder + 'static) -> Self {
+    self.metrics_recorder = Some(Arc::new(recorder));
     self
   }
 
-  /// Enable email job queue for async background email processing
-  ///
-  /// When enabled, emails are queued and sent in a background task
-  /// instead of blocking the request. This significantly improves
-  /// registration response times.
-  ///
-  /// # Requirements
+  /// Store rate-limit counters somewhere other than process memory
   ///
-  /// - An `email_sender` must be configured
-  /// - You must call `auth.start_email_worker()` after building
+  /// Defaults to [`InMemoryRateLimitStore`], which limits each app instance
+  /// separately. Install a shared store (e.g. Redis) so limits hold across
+  /// every instance. See [`RateLimitStore`] for the contract.
   ///
   /// # Example
   ///
   /// ```rust,ignore
   /// let auth = Auth::builder()
   ///     .database(Database::sqlite("auth.db").await?)
-  ///     .email_sender(Box::new(MyEmailSender))
-  ///     .email_queue(EmailWorkerConfig::default())
+  ///     .rate_limit_store(RedisRateLimitStore::new(client))
   ///     .build()?;
+  /// ```
+  pub fn rate_limit_store(mut self, store: impl RateLimitStore + 'static) -> Self {
+    self.rate_limit_store = Some(Arc::new(store));
+    self
+  }
+
+  /// Keep verification tokens in a store other than the main database
   ///
-  /// // Start the background worker
-  /// let worker_handle = auth.start_email_worker();
+  /// Defaults to the SQL `verification` table. Install e.g. a Redis-backed
+  /// store to keep short-lived tokens out of the database that holds users
+  /// and sessions. See [`TokenStore`] for the contract and its limits.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::postgres(&url).await?)
+  ///     .token_store(RedisTokenStore::new(client))
+  ///     .build()?;
   /// ```
-  #[cfg(feature = "email-queue")]
-  pub fn email_queue(mut self, config: EmailWorkerConfig) -> Self {
-    self.email_queue_config = Some(config);
+  pub fn token_store(mut self, store: impl TokenStore + 'static) -> Self {
+    self.token_store = Some(Arc::new(store));
     self
   }
 
-  pub fn build(self) -> Result<Auth> {
-    let database = self.database.ok_or(AuthError::MissingDatabase)?;
+  /// Configure whether to automatically send verification email on registration
+  ///
+  /// - `VerificationOnRegister::Never` (default, or `false`): registration
+  ///   creates the user but does NOT send a verification email. The
+  ///   application must call `send_email_verification()` manually.
+  /// - `VerificationOnRegister::IfSenderPresent` (or `true`): send when an
+  ///   `email_sender` is configured. Without one, `build()` logs a warning
+  ///   and registration skips the email.
+  /// - `VerificationOnRegister::Always`: send on every registration;
+  ///   `build()` returns `AuthError::ConfigError` if no `email_sender` is set.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// // Enable automatic verification emails on registration
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_sender(Box::new(MyEmailSender))
+  ///     .send_verification_on_register(VerificationOnRegister::Always)
+  ///     .build()?;
+  ///
+  /// // Register user (verification email sent automatically)
+  /// let user = auth.register(Register { ... }).await?;
+  /// ```
+  pub fn send_verification_on_register(mut self, mode: impl Into<VerificationOnRegister>) -> Self {
+    self.send_verification_on_register = mode.into();
+    self
+  }
+
+  /// Configure whether `register` fails when the verification email can't be sent
+  ///
+  /// When set to `false` (default), a send failure is logged and registration
+  /// still succeeds; the user can request a new email with
+  /// `resend_email_verification()`.
+  ///
+  /// When set to `true`, the sender's error is returned from `register`. Note
+  /// that the user and account have already been created at that point.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_sender(Box::new(MyEmailSender))
+  ///     .send_verification_on_register(true)
+  ///     .fail_registration_on_email_error(true)
+  ///     .build()?;
+  /// ```
+  pub fn fail_registration_on_email_error(mut self, enabled: bool) -> Self {
+    self.fail_registration_on_email_error = enabled;
+    self
+  }
+
+  /// Make `verify_email` succeed for an email that is already verified
+  ///
+  /// Users often click a verification link twice. When enabled, a valid or
+  /// already used token whose user is verified returns the user instead of
+  /// `AuthError::TokenAlreadyUsed`/`EmailAlreadyVerified`. Unknown and
+  /// expired tokens still fail.
+  ///
+  /// Disabled by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .idempotent_email_verify(true)
+  ///     .build()?;
+  /// ```
+  pub fn idempotent_email_verify(mut self, enabled: bool) -> Self {
+    self.idempotent_email_verify = enabled;
+    self
+  }
+
+  /// Issue a verification token on register for the application to deliver
+  ///
+  /// For flows where the client, not AuthKit, sends the verification
+  /// message (e.g. a SPA with its own mailer). When enabled and no
+  /// `email_sender` is configured, registration issues an email verification
+  /// token and `Auth::register_detailed` returns it, so no separate
+  /// `send_email_verification` call is needed. Plain `Auth::register` can't
+  /// return a token and doesn't issue one. With an email sender, tokens are
+  /// emailed as usual and never returned.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .return_verification_token_on_register(true)
+  ///     .build()?;
+  /// ```
+  pub fn return_verification_token_on_register(mut self, enabled: bool) -> Self {
+    self.return_verification_token_on_register = enabled;
+    self
+  }
+
+  /// Create a session for new users in `Auth::register_detailed`
+  ///
+  /// For frictionless onboarding: the outcome carries a session, so the
+  /// client is signed in without a separate `login` (and a second password
+  /// hash). Ignored while `require_email_verification` is enabled, since
+  /// a new user can't have verified their email yet.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .session_on_register(true)
+  ///     .build()?;
+  /// ```
+  pub fn session_on_register(mut self, enabled: bool) -> Self {
+    self.session_on_register = enabled;
+    self
+  }
+
+  /// Configure whether login requires email verification
+  ///
+  /// When set to `true`, users cannot login until their email is verified.
+  /// Login will return `AuthError::EmailNotVerified` for unverified users.
+  ///
+  /// When set to `false` (default), users can login regardless of email verification status.
+  ///
+  /// The requirement applies to email/password logins, i.e. accounts of the
+  /// `credential_provider_name` provider, which are the only logins
+  /// `Auth::login` performs.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// // Require email verification before login
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_sender(Box::new(MyEmailSender))
+  ///     .send_verification_on_register(true)
+  ///     .require_email_verification(true)
+  ///     .build()?;
+  ///
+  /// // Register user
+  /// let user = auth.register(Register { ... }).await?;
+  ///
+  /// // This will fail with EmailNotVerified error
+  /// let session = auth.login(Login { ... }).await; // Error!
+  ///
+  /// // User must verify email first, then login will succeed
+  /// ```
+  pub fn require_email_verification(mut self, required: bool) -> Self {
+    self.require_email_verification = required;
+    self
+  }
+
+  /// Let these addresses log in unverified under `require_email_verification`
+  ///
+  /// For seed and admin accounts that must be usable before any email
+  /// infrastructure exists. Matching ignores ASCII case. Keep the list
+  /// short: an exempt address is trusted without proof of ownership.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .require_email_verification(true)
+  ///     .verification_exempt_emails(HashSet::from(["admin@example.com".to_string()]))
+  ///     .build()?;
+  /// ```
+  pub fn verification_exempt_emails(mut self, emails: HashSet<String>) -> Self {
+    self.verification_exempt_emails = emails
+      .into_iter()
+      .map(|email| email.to_ascii_lowercase())
+      .collect();
+    self
+  }
+
+  /// Resend the verification email when a login fails with
+  /// `EmailNotVerified`
+  ///
+  /// Saves the client a separate "resend" call: the login error still
+  /// carries the email, and a fresh verification email is on its way. The
+  /// resend respects `email_send_cooldown`; a resend skipped or failed for
+  /// any reason is logged and doesn't change the login error. Requires an
+  /// email sender.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_sender(Box::new(MyEmailSender))
+  ///     .require_email_verification(true)
+  ///     .email_send_cooldown(Duration::from_secs(60))
+  ///     .resend_verification_on_login(true)
+  ///     .build()?;
+  /// ```
+  pub fn resend_verification_on_login(mut self, enabled: bool) -> Self {
+    self.resend_verification_on_login = enabled;
+    self
+  }
+
+  /// Configure which token source `Auth::verify_from_sources` tries first
+  ///
+  /// Defaults to `TokenSource::Header`. If the preferred source is missing or
+  /// holds an invalid token, the other source is tried.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// // Browser-first app: prefer the session cookie over the bearer header
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .preferred_token_source(TokenSource::Cookie)
+  ///     .build()?;
+  /// ```
+  pub fn preferred_token_source(mut self, source: TokenSource) -> Self {
+    self.preferred_token_source = source;
+    self
+  }
+
+  /// Configure which sessions `Auth::logout` deletes
+  ///
+  /// Defaults to `LogoutScope::SingleSession`, which deletes only the session
+  /// whose token is given. `LogoutScope::AllSessions` logs the token's user
+  /// out everywhere.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .logout_scope(LogoutScope::AllSessions)
+  ///     .build()?;
+  /// ```
+  pub fn logout_scope(mut self, scope: LogoutScope) -> Self {
+    self.logout_scope = scope;
+    self
+  }
+
+  /// Delete all of a user's sessions when their password changes
+  ///
+  /// Applies to every password change, e.g. `Auth::change_password`, so a
+  /// session stolen before the change stops working. The caller has to log
+  /// in again with the new password.
+  ///
+  /// Enabled by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// // Keep other devices signed in after a password change
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .invalidate_sessions_on_password_change(false)
+  ///     .build()?;
+  /// ```
+  pub fn invalidate_sessions_on_password_change(mut self, invalidate: bool) -> Self {
+    self.invalidate_sessions_on_password_change = invalidate;
+    self
+  }
+
+  /// Limit how often a verification email can be sent to the same address
+  ///
+  /// Protects users from inbox flooding (mail bombing) via repeated
+  /// `send_email_verification`/`resend_email_verification` calls. Within the
+  /// cooldown window further requests fail with `AuthError::Cooldown`, which
+  /// reports the seconds left so a UI can show a countdown.
+  ///
+  /// Disabled by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_send_cooldown(Duration::from_secs(60))
+  ///     .build()?;
+  /// ```
+  pub fn email_send_cooldown(mut self, cooldown: Duration) -> Self {
+    self.email_send_cooldown = Some(cooldown);
+    self
+  }
+
+  /// Cap how often a user can request verification emails
+  ///
+  /// `resend_email_verification` and `send_email_verification_code` fail
+  /// with `AuthError::RateLimitExceeded` once the user has been sent `max` + 1
+  /// verification emails (the first plus `max` resends) in the last 24
+  /// hours, whether or not a resend reused its token. Complements the
+  /// per-address `email_send_cooldown`.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .max_verification_resends(5)
+  ///     .build()?;
+  /// ```
+  pub fn max_verification_resends(mut self, max: u32) -> Self {
+    self.max_verification_resends = Some(max);
+    self
+  }
+
+  /// Limit failed logins per email address
+  ///
+  /// After `max` failed logins for an address within `window`, `login`
+  /// fails with `AuthError::RateLimitExceeded` until the window ends, even
+  /// with the right password. A successful login clears the count. Counts
+  /// are kept in the `rate_limit_store`. `build` fails with
+  /// `AuthError::ConfigError` for a zero `max` or `window`.
+  ///
+  /// Disabled by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .login_attempt_limit(5, Duration::from_secs(15 * 60))
+  ///     .build()?;
+  /// ```
+  pub fn login_attempt_limit(mut self, max: u32, window: Duration) -> Self {
+    self.login_attempt_limit = Some((max, window));
+    self
+  }
+
+  /// Set how long verification link codes stay valid
+  ///
+  /// Codes from `Auth::issue_verification_link_code` work once, within this
+  /// window. Keep it short but long enough for the email to be read;
+  /// `build` fails with `AuthError::ConfigError` for zero.
+  ///
+  /// Defaults to 1 hour.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .verification_link_code_ttl(Duration::from_secs(15 * 60))
+  ///     .build()?;
+  /// ```
+  pub fn verification_link_code_ttl(mut self, ttl: Duration) -> Self {
+    self.verification_link_code_ttl = ttl;
+    self
+  }
+
+  /// Retry database calls that fail with a transient error
+  ///
+  /// Connection resets and pool timeouts (see `AuthError::is_transient`) are
+  /// retried with backoff per `policy`; other errors fail immediately as
+  /// before. `build` fails with `AuthError::ConfigError` for an unusable
+  /// policy (zero attempts, zero base delay, ...).
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::postgres(&url).await?)
+  ///     .db_retry(RetryPolicy::default())
+  ///     .build()?;
+  /// ```
+  pub fn db_retry(mut self, policy: RetryPolicy) -> Self {
+    self.db_retry = Some(policy);
+    self
+  }
+
+  /// Cap how many password hashes and verifications run at once
+  ///
+  /// Argon2 is deliberately expensive in CPU and memory, so a login storm
+  /// can otherwise exhaust the machine. Beyond `max` concurrent operations,
+  /// `register`, `login` and the other password operations wait for a slot.
+  /// `build` fails with `AuthError::ConfigError` for zero or for more than
+  /// `tokio::sync::Semaphore::MAX_PERMITS`.
+  ///
+  /// Unbounded by default.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .max_concurrent_hashes(num_cpus::get())
+  ///     .build()?;
+  /// ```
+  pub fn max_concurrent_hashes(mut self, max: usize) -> Self {
+    self.max_concurrent_hashes = Some(max);
+    self
+  }
+
+  /// Choose how verification tokens are hashed before they are stored
+  ///
+  /// Defaults to `TokenHashAlgo::Sha256`. Use `Sha512` to match an existing
+  /// system, or `HmacSha256` so stolen hashes are useless without the key.
+  /// The HMAC secret must be at least 32 bytes; `build` fails with
+  /// `AuthError::ConfigError` otherwise. Tokens issued under a different
+  /// algorithm no longer verify.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .token_hash(TokenHashAlgo::HmacSha256 {
+  ///         secret: load_secret_from_kms()?,
+  ///     })
+  ///     .build()?;
+  /// ```
+  pub fn token_hash(mut self, algo: TokenHashAlgo) -> Self {
+    self.token_hash = algo;
+    self
+  }
+
+  /// Encrypt verification tokens at rest instead of hashing them
+  ///
+  /// By default tokens are stored as one-way SHA-256 hashes. With a key set,
+  /// they are encrypted with AES-256-GCM so support tooling can recover them
+  /// via `Auth::decrypt_verification_token`. Tokens issued under a different
+  /// key (or hashed) will no longer verify.
+  ///
+  /// Keep the key out of source control and the database.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .token_encryption_key(load_key_from_kms()?)
+  ///     .build()?;
+  /// ```
+  #[cfg(feature = "encrypted-tokens")]
+  pub fn token_encryption_key(mut self, key: [u8; 32]) -> Self {
+    self.token_encryption_key = Some(key);
+    self
+  }
+
+  /// Resend a still-valid verification token instead of minting a new one
+  ///
+  /// When enabled, `resend_email_verification` looks for an unused,
+  /// unexpired verification token for the user and sends it again, so links
+  /// in earlier emails keep working. Only encrypted tokens can be recovered,
+  /// so this requires `token_encryption_key`; `build` fails with
+  /// `AuthError::ConfigError` without one.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .token_encryption_key(load_key_from_kms()?)
+  ///     .reuse_valid_verification_tokens(true)
+  ///     .build()?;
+  /// ```
+  #[cfg(feature = "encrypted-tokens")]
+  pub fn reuse_valid_verification_tokens(mut self, enabled: bool) -> Self {
+    self.reuse_valid_verification_tokens = enabled;
+    self
+  }
+
+  /// Enable email job queue for async background email processing
+  ///
+  /// When enabled, emails are queued and sent in a background task
+  /// instead of blocking the request. This significantly improves
+  /// registration response times.
+  ///
+  /// # Requirements
+  ///
+  /// - An `email_sender` must be configured
+  /// - You must call `auth.start_email_worker()` after building
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .email_sender(Box::new(MyEmailSender))
+  ///     .email_queue(EmailWorkerConfig::default())
+  ///     .build()?;
+  ///
+  /// // Start the background worker
+  /// let worker_handle = auth.start_email_worker();
+  /// ```
+  #[cfg(feature = "email-queue")]
+  pub fn email_queue(mut self, config: EmailWorkerConfig) -> Self {
+    self.email_queue_config = Some(config);
+    self
+  }
+
+  /// Send OpenTelemetry spans to a specific tracer
+  ///
+  /// Operations are traced as `authkit.register`, `authkit.login`,
+  /// `authkit.verify`, ... and queued emails as `authkit.email.send`, with
+  /// `authkit.outcome` and a hashed `authkit.user_id_hash` attribute. Spans
+  /// nest under the caller's current context, which also follows email jobs
+  /// into the background worker. Without a tracer, spans go to the global
+  /// tracer provider.
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let provider = SdkTracerProvider::builder()
+  ///     .with_batch_exporter(otlp_exporter)
+  ///     .build();
+  ///
+  /// let auth = Auth::builder()
+  ///     .database(Database::sqlite("auth.db").await?)
+  ///     .tracer(provider.tracer("authkit"))
+  ///     .build()?;
+  /// ```
+  #[cfg(feature = "otel")]
+  pub fn tracer<T>(mut self, tracer: T) -> Self
+  where
+    T: opentelemetry::trace::Tracer + Send + Sync + 'static,
+    T::Span: Send + Sync + 'static,
+  {
+    self.tracer = Some(opentelemetry::global::BoxedTracer::new(Box::new(tracer)));
+    self
+  }
+
+  /// Reject option combinations that would otherwise only fail at first use
+  fn validate(&self) -> Result<()> {
+    if self.inactivity_timeout == Some(Duration::ZERO) {
+      return Err(AuthError::ConfigError(
+        "inactivity_timeout must be greater than zero".to_string(),
+      ));
+    }
+
+    if self.verification_link_code_ttl.as_secs() == 0 {
+      return Err(AuthError::ConfigError(
+        "verification_link_code_ttl must be at least one second".to_string(),
+      ));
+    }
+
+    if self.remember_me_duration.is_zero() {
+      return Err(AuthError::ConfigError(
+        "remember_me_duration must be greater than zero".to_string(),
+      ));
+    }
+
+    crate::validation::provider::normalize(&self.credential_provider)
+      .map_err(|e| AuthError::ConfigError(format!("invalid credential_provider_name: {}", e)))?;
+
+    if self.password_policy.min_length == 0
+      || self.password_policy.max_length < self.password_policy.min_length
+    {
+      return Err(AuthError::ConfigError(
+        "password_policy lengths must satisfy 0 < min_length <= max_length".to_string(),
+      ));
+    }
+
+    if let Some((max, window)) = self.login_attempt_limit {
+      if max == 0 || window.is_zero() {
+        return Err(AuthError::ConfigError(
+          "login_attempt_limit needs a nonzero max and window".to_string(),
+        ));
+      }
+    }
+
+    if self.max_session_extension.is_zero() {
+      return Err(AuthError::ConfigError(
+        "max_session_extension must be greater than zero".to_string(),
+      ));
+    }
+
+    if let Some(signer) = &self.session_signer {
+      signer.validate("session signing secrets")?;
+    }
+
+    if let Some(signer) = &self.csrf_signer {
+      signer.validate("csrf_secret")?;
+    }
+
+    if let Some(policy) = &self.db_retry {
+      policy
+        .check()
+        .map_err(|e| AuthError::ConfigError(format!("db_retry: {}", e)))?;
+    }
+
+    if let Some(len) = self.session_token_prefix {
+      if !(1..=MAX_SESSION_TOKEN_PREFIX).contains(&len) {
+        return Err(AuthError::ConfigError(format!(
+          "session_token_prefix must be between 1 and {}",
+          MAX_SESSION_TOKEN_PREFIX
+        )));
+      }
+    }
+
+    if self.max_concurrent_hashes == Some(0) {
+      return Err(AuthError::ConfigError(
+        "max_concurrent_hashes must be at least 1".to_string(),
+      ));
+    }
+
+    if let Some(max) = self.max_concurrent_hashes {
+      if max > tokio::sync::Semaphore::MAX_PERMITS {
+        return Err(AuthError::ConfigError(format!(
+          "max_concurrent_hashes must be at most {}",
+          tokio::sync::Semaphore::MAX_PERMITS
+        )));
+      }
+    }
+
+    if let TokenHashAlgo::HmacSha256 { secret } = &self.token_hash {
+      if secret.len() < MIN_SECRET_LENGTH {
+        return Err(AuthError::ConfigError(format!(
+          "token_hash HMAC secret must be at least {} bytes",
+          MIN_SECRET_LENGTH
+        )));
+      }
+    }
+
+    #[cfg(feature = "encrypted-tokens")]
+    if self.reuse_valid_verification_tokens && self.token_encryption_key.is_none() {
+      return Err(AuthError::ConfigError(
+        "reuse_valid_verification_tokens requires a token_encryption_key".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
+
+  pub fn build(self) -> Result<Auth> {
+    self.validate()?;
+    let database = self.database.ok_or_else(AuthError::missing_database)?;
 
     #[cfg(feature = "argon2")]
     let password_strategy = self
@@ -190,27 +1282,90 @@ impl AuthBuilder {
     #[cfg(not(feature = "argon2"))]
     let password_strategy = self
       .password_strategy
-      .ok_or(AuthError::MissingPasswordStrategy)?
+      .ok_or_else(AuthError::missing_password_strategy)?
       .create_strategy()?;
 
-    let session_strategy = self.session_strategy.unwrap_or_default().create_strategy();
+    let password_strategy = match self.max_concurrent_hashes {
+      Some(max) => Box::new(
+        crate::strategies::password::limited::LimitedPasswordStrategy::new(password_strategy, max),
+      ),
+      None => password_strategy,
+    };
+
+    let session_strategy =
+      self
+        .session_strategy
+        .unwrap_or_default()
+        .create_strategy(SessionStrategyOptions {
+          hash_tokens: self.hash_session_tokens,
+          token_prefix_len: self.session_token_prefix,
+          signer: self.session_signer,
+        });
 
-    let db_trait = crate::database::create_database_trait(database.inner);
+    let mut db_trait = crate::database::create_database_trait(database.inner);
+    if let Some(policy) = self.db_retry {
+      db_trait = Box::new(crate::database::retry::RetryingDatabase::new(
+        db_trait, policy,
+      ));
+    }
     let db_arc = Arc::new(db_trait);
 
-    let token_strategy = self.token_strategy.unwrap_or_default().create_strategy();
+    let token_generator = self
+      .token_generator
+      .unwrap_or_else(|| Arc::new(OsTokenGenerator));
+    let rate_limit_store = self
+      .rate_limit_store
+      .unwrap_or_else(|| Arc::new(InMemoryRateLimitStore::new()));
+
+    let token_options = TokenStrategyOptions {
+      token_generator: token_generator.clone(),
+      hash_algo: self.token_hash,
+      store: self.token_store,
+      rate_limit_store: rate_limit_store.clone(),
+      #[cfg(feature = "encrypted-tokens")]
+      cipher: self
+        .token_encryption_key
+        .map(crate::security::encryption::TokenCipher::new),
+    };
+    let token_strategy = self
+      .token_strategy
+      .unwrap_or_default()
+      .create_strategy(token_options);
 
     let email_sender = self.email_sender.map(Arc::new);
 
+    if email_sender.is_none() {
+      if self.resend_verification_on_login {
+        return Err(AuthError::ConfigError(
+          "resend_verification_on_login requires an email_sender".to_string(),
+        ));
+      }
+      match self.send_verification_on_register {
+        VerificationOnRegister::Always => {
+          return Err(AuthError::ConfigError(
+            "send_verification_on_register(Always) requires an email_sender".to_string(),
+          ));
+        }
+        VerificationOnRegister::IfSenderPresent => {
+          log::warn!(
+            "send_verification_on_register is enabled but no email_sender is configured; \
+             verification emails won't be sent on register"
+          );
+        }
+        VerificationOnRegister::Never => {}
+      }
+    }
+
     // Build email queue if configured
     #[cfg(feature = "email-queue")]
-    let (email_queue, email_worker_config) = {
-      if let (Some(config), Some(ref sender)) = (&self.email_queue_config, &email_sender) {
-        let (queue, _worker) = crate::email_job::create_email_queue(sender.clone(), config.clone());
-        (Some(queue), Some(config.clone()))
-      } else {
-        (None, None)
+    let email_worker_config = match (self.email_queue_config, &email_sender) {
+      (Some(config), Some(_)) => {
+        config
+          .validate()
+          .map_err(|e| AuthError::ConfigError(e.to_string()))?;
+        Some(config)
       }
+      _ => None,
     };
 
     Ok(Auth {
@@ -220,12 +1375,48 @@ impl AuthBuilder {
         session_strategy,
         token_strategy,
         email_sender,
+        #[cfg(feature = "sms")]
+        sms_sender: self.sms_sender.map(Arc::new),
+        token_generator,
+        metrics_recorder: self.metrics_recorder,
+        rate_limit_store,
+        csrf_signer: self.csrf_signer,
         send_verification_on_register: self.send_verification_on_register,
+        fail_registration_on_email_error: self.fail_registration_on_email_error,
+        idempotent_email_verify: self.idempotent_email_verify,
+        return_verification_token_on_register: self.return_verification_token_on_register,
+        session_on_register: self.session_on_register,
         require_email_verification: self.require_email_verification,
+        verification_exempt_emails: self.verification_exempt_emails,
+        resend_verification_on_login: self.resend_verification_on_login,
+        input_limits: self.input_limits,
+        trim_token_input: self.trim_token_input,
+        email_validator: self.email_validator,
+        password_policy: self.password_policy,
+        credential_provider: crate::validation::provider::normalize(&self.credential_provider)?,
+        inactivity_timeout: self.inactivity_timeout,
+        remember_me_duration: self.remember_me_duration,
+        max_session_extension: self.max_session_extension,
+        session_collision_retries: self.session_collision_retries,
+        preferred_token_source: self.preferred_token_source,
+        logout_scope: self.logout_scope,
+        invalidate_sessions_on_password_change: self.invalidate_sessions_on_password_change,
+        email_send_cooldown: self.email_send_cooldown,
+        max_verification_resends: self.max_verification_resends,
+        login_attempt_limit: self.login_attempt_limit,
+        verification_link_code_ttl: self.verification_link_code_ttl,
+        #[cfg(feature = "encrypted-tokens")]
+        reuse_valid_verification_tokens: self.reuse_valid_verification_tokens,
+        #[cfg(all(feature = "persistent-queue", feature = "encrypted-tokens"))]
+        token_cipher: self
+          .token_encryption_key
+          .map(crate::security::encryption::TokenCipher::new),
         #[cfg(feature = "email-queue")]
-        email_queue,
+        email_worker: Default::default(),
         #[cfg(feature = "email-queue")]
         email_worker_config,
+        #[cfg(feature = "otel")]
+        telemetry: crate::telemetry::Telemetry::new(self.tracer),
       }),
     })
   }
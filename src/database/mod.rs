@@ -1,13 +1,76 @@
 pub mod models;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+pub(crate) mod retry;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
 use crate::error::Result;
 use crate::types::{DatabaseInner, User};
 use async_trait::async_trait;
-use models::{DbAccount, DbSession, DbUser, DbUserWithAccount, DbVerification};
+#[cfg(feature = "persistent-queue")]
+use models::DbEmailJob;
+#[cfg(feature = "sms")]
+use models::DbUserPhone;
+use models::{
+  DbAccount, DbSession, DbSessionWithUser, DbUser, DbUserEmail, DbUserWithAccount, DbVerification,
+  DbVerificationSend, DbVerificationStatus,
+};
+
+/// Lookup indexes created by `migrate`, one statement each
+const LOOKUP_INDEXES: &[&str] = &[
+  "CREATE INDEX IF NOT EXISTS idx_accounts_user_id ON accounts(user_id)",
+  "CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id)",
+  "CREATE INDEX IF NOT EXISTS idx_sessions_token_prefix ON sessions(token_prefix)",
+  "CREATE INDEX IF NOT EXISTS idx_user_emails_user_id ON user_emails(user_id)",
+  "CREATE INDEX IF NOT EXISTS idx_verification_identifier ON verification(identifier)",
+  "CREATE INDEX IF NOT EXISTS idx_verification_user_id ON verification(user_id)",
+  "CREATE INDEX IF NOT EXISTS idx_verification_sends_identifier ON verification_sends(identifier, token_type, sent_at)",
+  "CREATE INDEX IF NOT EXISTS idx_verification_sends_user_id ON verification_sends(user_id, token_type, sent_at)",
+];
+
+/// Create the lookup indexes, tolerating concurrent `migrate` calls
+async fn create_lookup_indexes<'c, E>(executor: E) -> Result<()>
+where
+  E: sqlx::Executor<'c> + Copy,
+{
+  for statement in LOOKUP_INDEXES {
+    create_index(executor, statement).await?;
+  }
+
+  Ok(())
+}
+
+/// Run one `CREATE INDEX IF NOT EXISTS`, treating a lost creation race as
+/// success
+///
+/// `IF NOT EXISTS` is checked before the catalog insert, so on Postgres two
+/// sessions creating the same index can both pass it; the loser fails with
+/// `duplicate_table` (42P07) or a unique violation on the `pg_class`
+/// catalog (23505). Either way the index exists afterwards.
+async fn create_index<'c, E>(executor: E, statement: &'static str) -> Result<()>
+where
+  E: sqlx::Executor<'c>,
+{
+  match executor.execute(statement).await {
+    Ok(_) => Ok(()),
+    Err(e) if is_index_creation_race(&e) => {
+      log::debug!("Index already created concurrently ({}): {}", statement, e);
+      Ok(())
+    }
+    Err(e) => Err(e.into()),
+  }
+}
+
+fn is_index_creation_race(err: &sqlx::Error) -> bool {
+  match err {
+    sqlx::Error::Database(e) => {
+      matches!(e.code().as_deref(), Some("42P07" | "23505"))
+        || e.message().contains("already exists")
+    }
+    _ => false,
+  }
+}
 
 /// Core database trait for AuthKit
 ///
@@ -17,6 +80,20 @@ use models::{DbAccount, DbSession, DbUser, DbUserWithAccount, DbVerification};
 /// - Email verification: adds email_verified columns to users
 #[async_trait]
 pub(crate) trait DatabaseTrait: Send + Sync {
+  // ==========================================
+  // Schema Operations
+  // ==========================================
+
+  /// Create all tables and indexes if they don't already exist
+  async fn migrate(&self) -> Result<()>;
+
+  /// Add the `email_verified` and `email_verified_at` columns to a `users`
+  /// table created without them; a no-op when they exist
+  async fn migrate_email_verification(&self) -> Result<()>;
+
+  /// Run a trivial query to check the database is reachable
+  async fn ping(&self) -> Result<()>;
+
   // ==========================================
   // User Operations
   // ==========================================
@@ -53,6 +130,26 @@ pub(crate) trait DatabaseTrait: Send + Sync {
   /// Requires: email_verification feature columns (email_verified, email_verified_at)
   async fn find_user_by_email_with_verification(&self, email: &str) -> Result<Option<DbUser>>;
 
+  /// Find users with an unverified email created before a point in time, oldest first
+  /// Requires: email_verification feature columns (email_verified, email_verified_at)
+  async fn find_unverified_users_created_before(&self, cutoff: i64) -> Result<Vec<User>>;
+
+  // ==========================================
+  // Phone Verification Operations (sms feature)
+  // ==========================================
+
+  /// Set a user's phone number, resetting its verification state
+  #[cfg(feature = "sms")]
+  async fn set_user_phone(&self, user_id: &str, phone: &str, updated_at: i64) -> Result<()>;
+
+  /// Find a user's phone number and its verification state
+  #[cfg(feature = "sms")]
+  async fn find_user_phone(&self, user_id: &str) -> Result<Option<DbUserPhone>>;
+
+  /// Mark a user's phone number as verified
+  #[cfg(feature = "sms")]
+  async fn mark_phone_verified(&self, user_id: &str, verified_at: i64) -> Result<()>;
+
   // ==========================================
   // Account Operations
   // ==========================================
@@ -77,60 +174,161 @@ pub(crate) trait DatabaseTrait: Send + Sync {
     provider_account_id: &str,
   ) -> Result<Option<DbAccount>>;
 
+  /// Find all accounts linked to a user, oldest first
+  async fn find_accounts_by_user_id(&self, user_id: &str) -> Result<Vec<DbAccount>>;
+
+  /// Replace the password hash stored on an account
+  async fn update_account_password_hash(
+    &self,
+    account_id: &str,
+    password_hash: &str,
+    updated_at: i64,
+  ) -> Result<()>;
+
+  /// Give an invited user their credential account and mark their email
+  /// verified, in one transaction
+  ///
+  /// With `verification_id`, the invitation's `verification` row is claimed
+  /// in the same transaction; returns `false` without changes if it was
+  /// already used. Fails without changes if the user already has a
+  /// credential account for `email`.
+  #[allow(clippy::too_many_arguments)]
+  async fn accept_invitation(
+    &self,
+    user_id: &str,
+    account_id: &str,
+    credential_provider: &str,
+    email: &str,
+    password_hash: &str,
+    verification_id: Option<&str>,
+    now: i64,
+  ) -> Result<bool>;
+
   /// Find user with their credential account (for email/password login)
+  /// `provider` is the configured credential provider name
   async fn find_user_with_credential_account(
     &self,
     email: &str,
+    provider: &str,
   ) -> Result<Option<DbUserWithAccount>>;
 
-  /// Find user with their credential account including email verification status
+  /// Find user with their credential account (under `provider`) including
+  /// email verification status
   /// Requires: email_verification feature columns (email_verified, email_verified_at)
   async fn find_user_with_credential_account_with_verification(
     &self,
     email: &str,
+    provider: &str,
   ) -> Result<Option<DbUserWithAccount>>;
 
   // ==========================================
-  // Session Operations
+  // Secondary Email Operations
   // ==========================================
 
-  /// Create a new session for a user
-  async fn create_session(
+  /// Add an unverified secondary email address to a user
+  async fn create_user_email(
     &self,
     id: &str,
-    token: &str,
     user_id: &str,
-    expires_at: i64,
-    ip_address: Option<&str>,
-    user_agent: Option<&str>,
+    email: &str,
+    created_at: i64,
+  ) -> Result<DbUserEmail>;
+
+  /// Find a secondary email address
+  async fn find_user_email(&self, email: &str) -> Result<Option<DbUserEmail>>;
+
+  /// Find all secondary email addresses of a user, oldest first
+  async fn find_user_emails_by_user_id(&self, user_id: &str) -> Result<Vec<DbUserEmail>>;
+
+  /// Mark a secondary email address as verified
+  async fn mark_user_email_verified(&self, email: &str, verified_at: i64) -> Result<()>;
+
+  /// Swap a secondary email with the user's primary email in one transaction
+  ///
+  /// The current primary becomes a secondary row with id `demoted_id`, keeping
+  /// its verification state. The credential account (provider
+  /// `credential_provider`) follows the new primary.
+  async fn promote_user_email(
+    &self,
+    user_id: &str,
+    email: &str,
+    demoted_id: &str,
+    credential_provider: &str,
+    now: i64,
   ) -> Result<()>;
 
+  // ==========================================
+  // Session Operations
+  // ==========================================
+
+  /// Create a new session for a user
+  async fn create_session(&self, session: &DbSession) -> Result<()>;
+
   /// Find a session by its token
   async fn find_session(&self, token: &str) -> Result<Option<DbSession>>;
 
+  /// Find a session and its user in a single query
+  /// Queries base user columns only - does not require email_verification feature
+  async fn find_session_with_user(&self, token: &str) -> Result<Option<DbSessionWithUser>>;
+
+  /// Find a session and its user in a single query, including email verification status
+  /// Requires: email_verification feature columns (email_verified, email_verified_at)
+  async fn find_session_with_user_with_verification(
+    &self,
+    token: &str,
+  ) -> Result<Option<DbSessionWithUser>>;
+
+  /// List sessions ordered by id, starting after `after_id`, at most `limit` rows
+  async fn scan_sessions(&self, after_id: Option<&str>, limit: u32) -> Result<Vec<DbSession>>;
+
+  /// List a user's unexpired sessions, newest first
+  /// Ordered by `created_at DESC, id ASC`, so same-second sessions keep a
+  /// stable order
+  async fn find_sessions_by_user_id(&self, user_id: &str, now: i64) -> Result<Vec<DbSession>>;
+
+  /// Delete a session by id, only if it belongs to `user_id`
+  /// Returns false if no such session exists
+  async fn delete_user_session(&self, user_id: &str, session_id: &str) -> Result<bool>;
+
+  /// Record activity on a session
+  async fn touch_session(&self, id: &str, now: i64) -> Result<()>;
+
+  /// Set a session's expiry; returns false if no session has that id
+  async fn update_session_expiry(&self, id: &str, expires_at: i64) -> Result<bool>;
+
   /// Delete a specific session
   async fn delete_session(&self, token: &str) -> Result<()>;
 
+  /// Replace a live session's token in place (single atomic UPDATE)
+  /// Returns false if no unexpired session matched `old_token`
+  async fn rotate_session_token(&self, old_token: &str, new_token: &str, now: i64) -> Result<bool>;
+
   /// Delete all expired sessions (cleanup utility)
   #[allow(dead_code)]
   async fn delete_expired_sessions(&self) -> Result<u64>;
 
+  /// Delete every session of one user
+  /// Returns the number of sessions deleted
+  ///
+  /// A single statement: it deletes all the sessions that exist when it
+  /// runs, or none, and never sessions created after it.
+  async fn delete_user_sessions(&self, user_id: &str) -> Result<u64>;
+
+  /// Delete every session of every user
+  /// Returns the number of sessions deleted
+  ///
+  /// Same contract as `delete_user_sessions`.
+  async fn delete_all_sessions(&self) -> Result<u64>;
+
   // ==========================================
   // Verification Token Operations
   // ==========================================
 
   /// Create a new verification token
+  ///
+  /// The token is stored unused; `verification.used_at` is ignored.
   #[allow(dead_code)]
-  async fn create_verification(
-    &self,
-    id: &str,
-    user_id: Option<&str>,
-    identifier: &str,
-    token_hash: &str,
-    token_type: &str,
-    expires_at: i64,
-    created_at: i64,
-  ) -> Result<()>;
+  async fn create_verification(&self, verification: &DbVerification) -> Result<()>;
 
   /// Find a verification token by its hash and type
   #[allow(dead_code)]
@@ -140,17 +338,124 @@ pub(crate) trait DatabaseTrait: Send + Sync {
     token_type: &str,
   ) -> Result<Option<DbVerification>>;
 
-  /// Mark a verification token as used
+  /// Find a verification token by its hash, whatever its type
+  async fn find_verification_by_hash(&self, token_hash: &str) -> Result<Option<DbVerification>>;
+
+  /// Find the most recently created verification token for an identifier and type
   #[allow(dead_code)]
-  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<()>;
+  async fn find_latest_verification(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>>;
+
+  /// Log a verification message sent to a user, and drop that user's log
+  /// entries older than `prune_before`
+  async fn record_verification_send(
+    &self,
+    send: &DbVerificationSend,
+    prune_before: i64,
+  ) -> Result<()>;
+
+  /// When a verification message of `token_type` was last sent to an
+  /// identifier
+  async fn find_latest_verification_send(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<i64>>;
+
+  /// Find a user's verification status and their newest unused, unexpired
+  /// token of `token_type` in a single query
+  async fn find_verification_status(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerificationStatus>>;
+
+  /// Find the newest unused, unexpired verification token issued to a user
+  #[cfg(feature = "encrypted-tokens")]
+  async fn find_valid_token_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerification>>;
+
+  /// Count verification messages sent to a user since a point in time
+  async fn count_verification_sends_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    since: i64,
+  ) -> Result<u64>;
+
+  /// Find all verification tokens for an identifier and type, newest first
+  async fn find_verifications_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<DbVerification>>;
+
+  /// Mark an unused verification as used. Returns `false` when the token
+  /// was already consumed, so concurrent callers can tell who won.
+  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<bool>;
 
   /// Delete a specific verification token by its hash
   #[allow(dead_code)]
   async fn delete_verification(&self, token_hash: &str) -> Result<()>;
 
+  /// Delete a user's unused verification tokens of a given type
+  /// Returns the number of tokens deleted
+  async fn delete_tokens_for_user(&self, user_id: &str, token_type: &str) -> Result<u64>;
+
+  /// Delete every verification token, or every token of one type
+  /// Returns the number of tokens deleted
+  async fn delete_all_verifications(&self, token_type: Option<&str>) -> Result<u64>;
+
   /// Delete all expired verification tokens (cleanup utility)
   #[allow(dead_code)]
   async fn delete_expired_verifications(&self) -> Result<u64>;
+
+  // ==========================================
+  // Email Job Operations
+  // (Requires persistent-queue feature)
+  // ==========================================
+
+  /// Store a new pending email job
+  #[cfg(feature = "persistent-queue")]
+  async fn insert_email_job(&self, job: &DbEmailJob) -> Result<()>;
+
+  /// Atomically claim the oldest pending email job, marking it `sending`
+  ///
+  /// Concurrent callers never claim the same job.
+  #[cfg(feature = "persistent-queue")]
+  async fn claim_next_email_job(&self, now: i64) -> Result<Option<DbEmailJob>>;
+
+  /// Record an attempt on a claimed job: its attempt count and last error
+  #[cfg(feature = "persistent-queue")]
+  async fn record_email_job_attempt(
+    &self,
+    id: &str,
+    attempts: i64,
+    last_error: Option<&str>,
+    now: i64,
+  ) -> Result<()>;
+
+  /// Mark a job `sent` or `failed`, clearing its token and data
+  #[cfg(feature = "persistent-queue")]
+  async fn finish_email_job(&self, id: &str, status: &str, now: i64) -> Result<()>;
+
+  /// Return `sending` jobs last updated before `claimed_before` to `pending`
+  ///
+  /// Recovers jobs whose worker stopped mid-send.
+  #[cfg(feature = "persistent-queue")]
+  async fn requeue_stale_email_jobs(&self, claimed_before: i64, now: i64) -> Result<u64>;
+
+  /// Delete `sent` and `failed` jobs last updated before `finished_before`
+  #[cfg(feature = "persistent-queue")]
+  async fn delete_finished_email_jobs(&self, finished_before: i64) -> Result<u64>;
 }
 
 pub(crate) fn create_database_trait(inner: DatabaseInner) -> Box<dyn DatabaseTrait> {
@@ -13,6 +13,33 @@ pub(crate) struct DbUser {
   pub email_verified_at: Option<i64>,
 }
 
+/// Database model for email_jobs table (persistent email queue)
+///
+/// `status` moves `pending` -> `sending` -> `sent` or `failed`; a failed
+/// attempt with attempts left goes back to `pending`.
+#[cfg(feature = "persistent-queue")]
+#[derive(Debug, Clone)]
+pub(crate) struct DbEmailJob {
+  pub id: String,
+  /// `EmailJobType::as_str` of the job
+  pub job_type: String,
+  /// Template of `EmailJobType::Custom` jobs
+  pub template_id: Option<String>,
+  pub recipient: String,
+  /// Plaintext token to deliver; cleared once the job is finished
+  pub token: String,
+  pub token_expires_at: i64,
+  pub user_id: String,
+  /// Template data as a JSON object; cleared once the job is finished
+  pub data: String,
+  pub status: String,
+  pub attempts: i64,
+  pub max_attempts: i64,
+  pub last_error: Option<String>,
+  pub created_at: i64,
+  pub updated_at: i64,
+}
+
 /// Database model for accounts table
 /// Links authentication providers to users
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +56,26 @@ pub(crate) struct DbAccount {
   pub updated_at: i64,
 }
 
+/// Database model for user_emails table (secondary email addresses)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DbUserEmail {
+  pub id: String,
+  pub user_id: String,
+  pub email: String,
+  pub verified: bool,
+  pub verified_at: Option<i64>,
+  pub created_at: i64,
+}
+
+/// Phone number columns of the users table
+#[cfg(feature = "sms")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DbUserPhone {
+  pub phone: Option<String>,
+  pub phone_verified: bool,
+  pub phone_verified_at: Option<i64>,
+}
+
 /// Database model for sessions table
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct DbSession {
@@ -39,6 +86,13 @@ pub(crate) struct DbSession {
   pub created_at: i64,
   pub ip_address: Option<String>,
   pub user_agent: Option<String>,
+  /// Whether the session was created with "remember me"
+  pub remember_me: bool,
+  /// Last time the session was used (Unix seconds)
+  pub last_activity_at: i64,
+  /// Leading characters of the stored token, a lookup hint only (see
+  /// `AuthBuilder::session_token_prefix`)
+  pub token_prefix: Option<String>,
 }
 
 /// Database model for verification table (tokens for password reset, magic links, etc.)
@@ -57,6 +111,20 @@ pub(crate) struct DbVerification {
   pub used_at: Option<i64>,
 }
 
+/// Database model for the verification_sends log
+///
+/// One row per verification message sent, whether it carried a new or a
+/// reused token; send cooldowns and resend limits are read from here.
+#[derive(Debug, Clone)]
+pub(crate) struct DbVerificationSend {
+  pub id: String,
+  pub user_id: String,
+  /// Address the message went to
+  pub identifier: String,
+  pub token_type: String,
+  pub sent_at: i64,
+}
+
 impl From<DbUser> for crate::types::User {
   fn from(db_user: DbUser) -> Self {
     crate::types::User {
@@ -71,6 +139,64 @@ impl From<DbUser> for crate::types::User {
   }
 }
 
+impl From<DbSession> for crate::types::Session {
+  fn from(db_session: DbSession) -> Self {
+    crate::types::Session {
+      id: db_session.id,
+      token: db_session.token,
+      user_id: db_session.user_id,
+      expires_at: db_session.expires_at,
+      created_at: db_session.created_at,
+      ip_address: db_session.ip_address,
+      user_agent: db_session.user_agent,
+      remember_me: db_session.remember_me,
+      last_activity_at: db_session.last_activity_at,
+    }
+  }
+}
+
+impl From<DbSession> for crate::types::SessionInfo {
+  fn from(db_session: DbSession) -> Self {
+    // token is deliberately dropped
+    crate::types::SessionInfo {
+      id: db_session.id,
+      user_id: db_session.user_id,
+      expires_at: db_session.expires_at,
+      created_at: db_session.created_at,
+      ip_address: db_session.ip_address,
+      user_agent: db_session.user_agent,
+      remember_me: db_session.remember_me,
+      last_activity_at: db_session.last_activity_at,
+    }
+  }
+}
+
+impl From<DbAccount> for crate::types::Account {
+  fn from(db_account: DbAccount) -> Self {
+    // password_hash is deliberately dropped
+    crate::types::Account {
+      id: db_account.id,
+      user_id: db_account.user_id,
+      provider: db_account.provider,
+      provider_account_id: db_account.provider_account_id,
+      created_at: db_account.created_at,
+      updated_at: db_account.updated_at,
+    }
+  }
+}
+
+impl From<DbUserEmail> for crate::types::UserEmail {
+  fn from(db_user_email: DbUserEmail) -> Self {
+    crate::types::UserEmail {
+      email: db_user_email.email,
+      primary: false,
+      verified: db_user_email.verified,
+      verified_at: db_user_email.verified_at,
+      created_at: db_user_email.created_at,
+    }
+  }
+}
+
 /// Helper struct for user with account info (for login operations)
 #[derive(Debug, Clone)]
 pub(crate) struct DbUserWithAccount {
@@ -83,3 +209,17 @@ impl DbUserWithAccount {
     self.account.password_hash.as_deref()
   }
 }
+
+/// A user with the expiry of their newest outstanding verification token
+#[derive(Debug, Clone)]
+pub(crate) struct DbVerificationStatus {
+  pub user: DbUser,
+  pub pending_token_expires_at: Option<i64>,
+}
+
+/// Helper struct for a session joined with its user (for verify)
+#[derive(Debug, Clone)]
+pub(crate) struct DbSessionWithUser {
+  pub session: DbSession,
+  pub user: DbUser,
+}
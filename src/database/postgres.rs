@@ -1,5 +1,12 @@
+#[cfg(feature = "persistent-queue")]
+use crate::database::models::DbEmailJob;
+#[cfg(feature = "sms")]
+use crate::database::models::DbUserPhone;
 #[cfg(feature = "postgres")]
-use crate::database::models::{DbAccount, DbSession, DbUser, DbUserWithAccount, DbVerification};
+use crate::database::models::{
+  DbAccount, DbSession, DbSessionWithUser, DbUser, DbUserEmail, DbUserWithAccount, DbVerification,
+  DbVerificationSend, DbVerificationStatus,
+};
 use crate::database::DatabaseTrait;
 use crate::error::Result;
 use crate::types::User;
@@ -24,10 +31,257 @@ impl PostgresDatabase {
 
     Ok(Self { pool })
   }
+
+  /// Bring tables created by an older version up to the current schema
+  ///
+  /// `CREATE TABLE IF NOT EXISTS` leaves existing tables untouched, so every
+  /// column added after a table first shipped is also added here.
+  async fn upgrade_columns(&self) -> Result<()> {
+    self.add_column_if_missing("users", "phone", "TEXT").await?;
+    self
+      .add_column_if_missing("users", "phone_verified", "BOOLEAN NOT NULL DEFAULT FALSE")
+      .await?;
+    self
+      .add_column_if_missing("users", "phone_verified_at", "BIGINT")
+      .await?;
+    self
+      .add_column_if_missing("sessions", "remember_me", "BOOLEAN NOT NULL DEFAULT FALSE")
+      .await?;
+    self
+      .add_column_if_missing("sessions", "token_prefix", "TEXT")
+      .await?;
+    if self
+      .add_column_if_missing("sessions", "last_activity_at", "BIGINT NOT NULL DEFAULT 0")
+      .await?
+    {
+      // Existing sessions count as last active when they were created
+      sqlx::query("UPDATE sessions SET last_activity_at = created_at WHERE last_activity_at = 0")
+        .execute(&self.pool)
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  /// Add a column unless the table already has it; returns whether it was
+  /// added
+  async fn add_column_if_missing(
+    &self,
+    table: &str,
+    column: &str,
+    definition: &str,
+  ) -> Result<bool> {
+    let exists: bool = sqlx::query_scalar(
+      r#"
+      SELECT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2
+      )
+      "#,
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(&self.pool)
+    .await?;
+    if exists {
+      return Ok(false);
+    }
+
+    // IF NOT EXISTS covers another `migrate` adding it since the check
+    let statement = format!(
+      "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {}",
+      table, column, definition
+    );
+    sqlx::query(&statement).execute(&self.pool).await?;
+
+    Ok(true)
+  }
 }
 
 #[async_trait]
 impl DatabaseTrait for PostgresDatabase {
+  // ==========================================
+  // Schema Operations
+  // ==========================================
+
+  async fn ping(&self) -> Result<()> {
+    sqlx::query("SELECT 1").execute(&self.pool).await?;
+    Ok(())
+  }
+
+  async fn migrate_email_verification(&self) -> Result<()> {
+    sqlx::query(
+      r#"
+      ALTER TABLE users
+        ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+        ADD COLUMN IF NOT EXISTS email_verified_at BIGINT
+      "#,
+    )
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn migrate(&self) -> Result<()> {
+    use sqlx::Executor;
+
+    // Users table with email_verification and phone columns
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+          id TEXT PRIMARY KEY,
+          email TEXT NOT NULL UNIQUE,
+          name TEXT,
+          created_at BIGINT NOT NULL,
+          updated_at BIGINT NOT NULL,
+          email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+          email_verified_at BIGINT,
+          phone TEXT,
+          phone_verified BOOLEAN NOT NULL DEFAULT FALSE,
+          phone_verified_at BIGINT
+        )
+        "#,
+      )
+      .await?;
+
+    // Accounts table
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS accounts (
+          id TEXT PRIMARY KEY,
+          user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+          provider TEXT NOT NULL,
+          provider_account_id TEXT NOT NULL,
+          password_hash TEXT,
+          created_at BIGINT NOT NULL,
+          updated_at BIGINT NOT NULL,
+          UNIQUE(provider, provider_account_id)
+        )
+        "#,
+      )
+      .await?;
+
+    // Sessions table
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS sessions (
+          id TEXT PRIMARY KEY,
+          user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+          token TEXT NOT NULL UNIQUE,
+          expires_at BIGINT NOT NULL,
+          created_at BIGINT NOT NULL,
+          ip_address TEXT,
+          user_agent TEXT,
+          remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+          last_activity_at BIGINT NOT NULL,
+          token_prefix TEXT
+        )
+        "#,
+      )
+      .await?;
+
+    // Verification table
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS verification (
+          id TEXT PRIMARY KEY,
+          user_id TEXT REFERENCES users(id) ON DELETE CASCADE,
+          identifier TEXT NOT NULL,
+          token_hash TEXT NOT NULL UNIQUE,
+          token_type TEXT NOT NULL,
+          expires_at BIGINT NOT NULL,
+          created_at BIGINT NOT NULL,
+          used_at BIGINT
+        )
+        "#,
+      )
+      .await?;
+
+    // Log of verification messages sent, for cooldowns and resend limits
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS verification_sends (
+          id TEXT PRIMARY KEY,
+          user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+          identifier TEXT NOT NULL,
+          token_type TEXT NOT NULL,
+          sent_at BIGINT NOT NULL
+        )
+        "#,
+      )
+      .await?;
+
+    // Secondary email addresses (the primary email stays in users.email)
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS user_emails (
+          id TEXT PRIMARY KEY,
+          user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+          email TEXT NOT NULL UNIQUE,
+          verified BOOLEAN NOT NULL DEFAULT FALSE,
+          verified_at BIGINT,
+          created_at BIGINT NOT NULL
+        )
+        "#,
+      )
+      .await?;
+
+    // Columns must exist before the indexes on them
+    self.upgrade_columns().await?;
+
+    // One statement per index, so a creation race with another `migrate`
+    // (rolling deploys) only skips the index that lost it
+    super::create_lookup_indexes(&self.pool).await?;
+
+    // Persistent email queue
+    #[cfg(feature = "persistent-queue")]
+    self
+      .pool
+      .execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS email_jobs (
+          id TEXT PRIMARY KEY,
+          job_type TEXT NOT NULL,
+          template_id TEXT,
+          recipient TEXT NOT NULL,
+          token TEXT NOT NULL,
+          token_expires_at BIGINT NOT NULL,
+          user_id TEXT NOT NULL,
+          data TEXT NOT NULL,
+          status TEXT NOT NULL,
+          attempts BIGINT NOT NULL DEFAULT 0,
+          max_attempts BIGINT NOT NULL,
+          last_error TEXT,
+          created_at BIGINT NOT NULL,
+          updated_at BIGINT NOT NULL
+        )
+        "#,
+      )
+      .await?;
+
+    #[cfg(feature = "persistent-queue")]
+    super::create_index(
+      &self.pool,
+      "CREATE INDEX IF NOT EXISTS idx_email_jobs_status ON email_jobs(status, created_at)",
+    )
+    .await?;
+
+    Ok(())
+  }
+
   // ==========================================
   // User Operations
   // ==========================================
@@ -179,6 +433,94 @@ impl DatabaseTrait for PostgresDatabase {
     Ok(user)
   }
 
+  async fn find_unverified_users_created_before(&self, cutoff: i64) -> Result<Vec<User>> {
+    let users = sqlx::query(
+      r#"
+      SELECT id, email, name, created_at, updated_at, email_verified, email_verified_at
+      FROM users
+      WHERE email_verified = FALSE AND created_at < $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(cutoff)
+    .map(|row: sqlx::postgres::PgRow| DbUser {
+      id: row.get("id"),
+      email: row.get("email"),
+      name: row.get("name"),
+      created_at: row.get("created_at"),
+      updated_at: row.get("updated_at"),
+      email_verified: row.get("email_verified"),
+      email_verified_at: row.get("email_verified_at"),
+    })
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(users.into_iter().map(Into::into).collect())
+  }
+
+  // ==========================================
+  // Phone Verification Operations (sms feature)
+  // ==========================================
+
+  #[cfg(feature = "sms")]
+  async fn set_user_phone(&self, user_id: &str, phone: &str, updated_at: i64) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET phone = $1, phone_verified = $2, phone_verified_at = NULL, updated_at = $3
+      WHERE id = $4
+      "#,
+    )
+    .bind(phone)
+    .bind(false)
+    .bind(updated_at)
+    .bind(user_id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  #[cfg(feature = "sms")]
+  async fn find_user_phone(&self, user_id: &str) -> Result<Option<DbUserPhone>> {
+    let phone = sqlx::query(
+      r#"
+      SELECT phone, phone_verified, phone_verified_at
+      FROM users
+      WHERE id = $1
+      "#,
+    )
+    .bind(user_id)
+    .map(|row: sqlx::postgres::PgRow| DbUserPhone {
+      phone: row.get("phone"),
+      phone_verified: row.get("phone_verified"),
+      phone_verified_at: row.get("phone_verified_at"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(phone)
+  }
+
+  #[cfg(feature = "sms")]
+  async fn mark_phone_verified(&self, user_id: &str, verified_at: i64) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET phone_verified = $1, phone_verified_at = $2, updated_at = $3
+      WHERE id = $4
+      "#,
+    )
+    .bind(true)
+    .bind(verified_at)
+    .bind(verified_at)
+    .bind(user_id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
   // ==========================================
   // Account Operations
   // ==========================================
@@ -239,9 +581,119 @@ impl DatabaseTrait for PostgresDatabase {
     Ok(account)
   }
 
+  async fn find_accounts_by_user_id(&self, user_id: &str) -> Result<Vec<DbAccount>> {
+    let accounts = sqlx::query(
+      r#"
+      SELECT id, user_id, provider, provider_account_id, password_hash, created_at, updated_at
+      FROM accounts
+      WHERE user_id = $1
+      ORDER BY created_at ASC
+      "#,
+    )
+    .bind(user_id)
+    .map(|row: sqlx::postgres::PgRow| DbAccount {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      provider: row.get("provider"),
+      provider_account_id: row.get("provider_account_id"),
+      password_hash: row.get("password_hash"),
+      created_at: row.get("created_at"),
+      updated_at: row.get("updated_at"),
+    })
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(accounts)
+  }
+
+  async fn update_account_password_hash(
+    &self,
+    account_id: &str,
+    password_hash: &str,
+    updated_at: i64,
+  ) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE accounts
+      SET password_hash = $1, updated_at = $2
+      WHERE id = $3
+      "#,
+    )
+    .bind(password_hash)
+    .bind(updated_at)
+    .bind(account_id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn accept_invitation(
+    &self,
+    user_id: &str,
+    account_id: &str,
+    credential_provider: &str,
+    email: &str,
+    password_hash: &str,
+    verification_id: Option<&str>,
+    now: i64,
+  ) -> Result<bool> {
+    let mut tx = self.pool.begin().await?;
+
+    if let Some(verification_id) = verification_id {
+      let claimed = sqlx::query(
+        r#"
+        UPDATE verification SET used_at = $1
+        WHERE id = $2 AND used_at IS NULL
+        "#,
+      )
+      .bind(now)
+      .bind(verification_id)
+      .execute(&mut *tx)
+      .await?;
+
+      // Dropping the transaction rolls it back
+      if claimed.rows_affected() == 0 {
+        return Ok(false);
+      }
+    }
+
+    sqlx::query(
+      r#"
+      INSERT INTO accounts (id, user_id, provider, provider_account_id, password_hash, created_at, updated_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $6)
+      "#,
+    )
+    .bind(account_id)
+    .bind(user_id)
+    .bind(credential_provider)
+    .bind(email)
+    .bind(password_hash)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET email_verified = TRUE, email_verified_at = $1, updated_at = $1
+      WHERE id = $2
+      "#,
+    )
+    .bind(now)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+  }
+
   async fn find_user_with_credential_account(
     &self,
     email: &str,
+    provider: &str,
   ) -> Result<Option<DbUserWithAccount>> {
     // Query base columns only - email_verified columns are optional (added by email_verification feature)
     let result = sqlx::query(
@@ -253,10 +705,11 @@ impl DatabaseTrait for PostgresDatabase {
         a.created_at as account_created_at, a.updated_at as account_updated_at
       FROM users u
       INNER JOIN accounts a ON u.id = a.user_id
-      WHERE u.email = $1 AND a.provider = 'credential'
+      WHERE u.email = $1 AND a.provider = $2
       "#,
     )
     .bind(email)
+    .bind(provider)
     .map(|row: sqlx::postgres::PgRow| {
       let user = DbUser {
         id: row.get("user_id"),
@@ -287,6 +740,7 @@ impl DatabaseTrait for PostgresDatabase {
   async fn find_user_with_credential_account_with_verification(
     &self,
     email: &str,
+    provider: &str,
   ) -> Result<Option<DbUserWithAccount>> {
     // Queries email_verified columns - requires email_verification feature migration
     let result = sqlx::query(
@@ -298,10 +752,11 @@ impl DatabaseTrait for PostgresDatabase {
         a.created_at as account_created_at, a.updated_at as account_updated_at
       FROM users u
       INNER JOIN accounts a ON u.id = a.user_id
-      WHERE u.email = $1 AND a.provider = 'credential'
+      WHERE u.email = $1 AND a.provider = $2
       "#,
     )
     .bind(email)
+    .bind(provider)
     .map(|row: sqlx::postgres::PgRow| {
       let user = DbUser {
         id: row.get("user_id"),
@@ -330,126 +785,538 @@ impl DatabaseTrait for PostgresDatabase {
   }
 
   // ==========================================
-  // Session Operations
+  // Secondary Email Operations
   // ==========================================
 
-  async fn create_session(
+  async fn create_user_email(
     &self,
     id: &str,
-    token: &str,
     user_id: &str,
-    expires_at: i64,
-    ip_address: Option<&str>,
-    user_agent: Option<&str>,
-  ) -> Result<()> {
-    let created_at = std::time::SystemTime::now()
-      .duration_since(std::time::UNIX_EPOCH)
-      .unwrap()
-      .as_secs() as i64;
-
+    email: &str,
+    created_at: i64,
+  ) -> Result<DbUserEmail> {
     sqlx::query(
       r#"
-      INSERT INTO sessions (id, token, user_id, expires_at, created_at, ip_address, user_agent)
-      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      INSERT INTO user_emails (id, user_id, email, verified, created_at)
+      VALUES ($1, $2, $3, $4, $5)
       "#,
     )
     .bind(id)
-    .bind(token)
     .bind(user_id)
-    .bind(expires_at)
+    .bind(email)
+    .bind(false)
     .bind(created_at)
-    .bind(ip_address)
-    .bind(user_agent)
     .execute(&self.pool)
     .await?;
 
-    Ok(())
+    Ok(DbUserEmail {
+      id: id.to_string(),
+      user_id: user_id.to_string(),
+      email: email.to_string(),
+      verified: false,
+      verified_at: None,
+      created_at,
+    })
   }
 
-  async fn find_session(&self, token: &str) -> Result<Option<DbSession>> {
-    let session = sqlx::query(
+  async fn find_user_email(&self, email: &str) -> Result<Option<DbUserEmail>> {
+    let user_email = sqlx::query(
       r#"
-      SELECT id, token, user_id, expires_at, created_at, ip_address, user_agent
-      FROM sessions
-      WHERE token = $1
+      SELECT id, user_id, email, verified, verified_at, created_at
+      FROM user_emails
+      WHERE email = $1
       "#,
     )
-    .bind(token)
-    .map(|row: sqlx::postgres::PgRow| DbSession {
+    .bind(email)
+    .map(|row: sqlx::postgres::PgRow| DbUserEmail {
       id: row.get("id"),
-      token: row.get("token"),
       user_id: row.get("user_id"),
-      expires_at: row.get("expires_at"),
+      email: row.get("email"),
+      verified: row.get("verified"),
+      verified_at: row.get("verified_at"),
       created_at: row.get("created_at"),
-      ip_address: row.get("ip_address"),
-      user_agent: row.get("user_agent"),
     })
     .fetch_optional(&self.pool)
     .await?;
 
-    Ok(session)
+    Ok(user_email)
   }
 
-  async fn delete_session(&self, token: &str) -> Result<()> {
-    sqlx::query(
+  async fn find_user_emails_by_user_id(&self, user_id: &str) -> Result<Vec<DbUserEmail>> {
+    let user_emails = sqlx::query(
       r#"
-      DELETE FROM sessions
-      WHERE token = $1
+      SELECT id, user_id, email, verified, verified_at, created_at
+      FROM user_emails
+      WHERE user_id = $1
+      ORDER BY created_at ASC
       "#,
     )
-    .bind(token)
-    .execute(&self.pool)
+    .bind(user_id)
+    .map(|row: sqlx::postgres::PgRow| DbUserEmail {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      email: row.get("email"),
+      verified: row.get("verified"),
+      verified_at: row.get("verified_at"),
+      created_at: row.get("created_at"),
+    })
+    .fetch_all(&self.pool)
     .await?;
 
-    Ok(())
+    Ok(user_emails)
   }
 
-  async fn delete_expired_sessions(&self) -> Result<u64> {
-    let now = std::time::SystemTime::now()
-      .duration_since(std::time::UNIX_EPOCH)
-      .unwrap()
-      .as_secs() as i64;
-
-    let result = sqlx::query(
+  async fn mark_user_email_verified(&self, email: &str, verified_at: i64) -> Result<()> {
+    sqlx::query(
       r#"
-      DELETE FROM sessions
-      WHERE expires_at < $1
+      UPDATE user_emails
+      SET verified = $1, verified_at = $2
+      WHERE email = $3
       "#,
     )
-    .bind(now)
+    .bind(true)
+    .bind(verified_at)
+    .bind(email)
     .execute(&self.pool)
     .await?;
 
-    Ok(result.rows_affected())
+    Ok(())
   }
 
-  // ==========================================
-  // Verification Token Operations
-  // ==========================================
-
-  async fn create_verification(
+  async fn promote_user_email(
     &self,
-    id: &str,
-    user_id: Option<&str>,
-    identifier: &str,
-    token_hash: &str,
-    token_type: &str,
-    expires_at: i64,
-    created_at: i64,
+    user_id: &str,
+    email: &str,
+    demoted_id: &str,
+    credential_provider: &str,
+    now: i64,
   ) -> Result<()> {
-    sqlx::query(
+    let mut tx = self.pool.begin().await?;
+
+    let current = sqlx::query(
       r#"
-      INSERT INTO verification (id, user_id, identifier, token_hash, token_type, expires_at, created_at)
-      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      SELECT email, email_verified, email_verified_at
+      FROM users
+      WHERE id = $1
       "#,
     )
-    .bind(id)
     .bind(user_id)
-    .bind(identifier)
-    .bind(token_hash)
-    .bind(token_type)
+    .fetch_one(&mut *tx)
+    .await?;
+    let current_email: String = current.get("email");
+    let current_verified: bool = current.get("email_verified");
+    let current_verified_at: Option<i64> = current.get("email_verified_at");
+
+    let promoted_verified_at: Option<i64> = sqlx::query(
+      r#"
+      DELETE FROM user_emails
+      WHERE email = $1 AND user_id = $2
+      RETURNING verified_at
+      "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?
+    .get("verified_at");
+
+    sqlx::query(
+      r#"
+      INSERT INTO user_emails (id, user_id, email, verified, verified_at, created_at)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      "#,
+    )
+    .bind(demoted_id)
+    .bind(user_id)
+    .bind(&current_email)
+    .bind(current_verified)
+    .bind(current_verified_at)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+      r#"
+      UPDATE users
+      SET email = $1, email_verified = $2, email_verified_at = $3, updated_at = $4
+      WHERE id = $5
+      "#,
+    )
+    .bind(email)
+    .bind(true)
+    .bind(promoted_verified_at)
+    .bind(now)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    // Credential accounts use the login email as their provider account ID
+    sqlx::query(
+      r#"
+      UPDATE accounts
+      SET provider_account_id = $1, updated_at = $2
+      WHERE user_id = $3 AND provider = $4
+      "#,
+    )
+    .bind(email)
+    .bind(now)
+    .bind(user_id)
+    .bind(credential_provider)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+  }
+
+  // ==========================================
+  // Session Operations
+  // ==========================================
+
+  async fn create_session(&self, session: &DbSession) -> Result<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO sessions (
+        id, token, user_id, expires_at, created_at, ip_address, user_agent, remember_me,
+        last_activity_at, token_prefix
+      )
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+      "#,
+    )
+    .bind(&session.id)
+    .bind(&session.token)
+    .bind(&session.user_id)
+    .bind(session.expires_at)
+    .bind(session.created_at)
+    .bind(&session.ip_address)
+    .bind(&session.user_agent)
+    .bind(session.remember_me)
+    .bind(session.last_activity_at)
+    .bind(&session.token_prefix)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn find_session(&self, token: &str) -> Result<Option<DbSession>> {
+    let session = sqlx::query(
+      r#"
+      SELECT id, token, user_id, expires_at, created_at, ip_address, user_agent, remember_me,
+        last_activity_at, token_prefix
+      FROM sessions
+      WHERE token = $1
+      "#,
+    )
+    .bind(token)
+    .map(|row: sqlx::postgres::PgRow| DbSession {
+      id: row.get("id"),
+      token: row.get("token"),
+      user_id: row.get("user_id"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      ip_address: row.get("ip_address"),
+      user_agent: row.get("user_agent"),
+      remember_me: row.get("remember_me"),
+      last_activity_at: row.get("last_activity_at"),
+      token_prefix: row.get("token_prefix"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(session)
+  }
+
+  async fn find_session_with_user(&self, token: &str) -> Result<Option<DbSessionWithUser>> {
+    // Query base columns only - email_verified columns are optional (added by email_verification feature)
+    // Runs on every verify: prepared once per connection and reused from
+    // sqlx's statement cache (see benches/verify.rs)
+    let result = sqlx::query(
+      r#"
+      SELECT
+        s.id as session_id, s.token, s.user_id, s.expires_at,
+        s.created_at as session_created_at, s.ip_address, s.user_agent, s.remember_me,
+        s.last_activity_at, s.token_prefix,
+        u.email, u.name, u.created_at as user_created_at, u.updated_at as user_updated_at
+      FROM sessions s
+      INNER JOIN users u ON u.id = s.user_id
+      WHERE s.token = $1
+      "#,
+    )
+    .bind(token)
+    .map(|row: sqlx::postgres::PgRow| {
+      let session = DbSession {
+        id: row.get("session_id"),
+        token: row.get("token"),
+        user_id: row.get("user_id"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("session_created_at"),
+        ip_address: row.get("ip_address"),
+        user_agent: row.get("user_agent"),
+        remember_me: row.get("remember_me"),
+        last_activity_at: row.get("last_activity_at"),
+        token_prefix: row.get("token_prefix"),
+      };
+      let user = DbUser {
+        id: row.get("user_id"),
+        email: row.get("email"),
+        name: row.get("name"),
+        created_at: row.get("user_created_at"),
+        updated_at: row.get("user_updated_at"),
+        email_verified: None,
+        email_verified_at: None,
+      };
+      DbSessionWithUser { session, user }
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(result)
+  }
+
+  async fn find_session_with_user_with_verification(
+    &self,
+    token: &str,
+  ) -> Result<Option<DbSessionWithUser>> {
+    // Queries email_verified columns - requires email_verification feature migration
+    let result = sqlx::query(
+      r#"
+      SELECT
+        s.id as session_id, s.token, s.user_id, s.expires_at,
+        s.created_at as session_created_at, s.ip_address, s.user_agent, s.remember_me,
+        s.last_activity_at, s.token_prefix,
+        u.email, u.name, u.created_at as user_created_at, u.updated_at as user_updated_at,
+        u.email_verified, u.email_verified_at
+      FROM sessions s
+      INNER JOIN users u ON u.id = s.user_id
+      WHERE s.token = $1
+      "#,
+    )
+    .bind(token)
+    .map(|row: sqlx::postgres::PgRow| {
+      let session = DbSession {
+        id: row.get("session_id"),
+        token: row.get("token"),
+        user_id: row.get("user_id"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("session_created_at"),
+        ip_address: row.get("ip_address"),
+        user_agent: row.get("user_agent"),
+        remember_me: row.get("remember_me"),
+        last_activity_at: row.get("last_activity_at"),
+        token_prefix: row.get("token_prefix"),
+      };
+      let user = DbUser {
+        id: row.get("user_id"),
+        email: row.get("email"),
+        name: row.get("name"),
+        created_at: row.get("user_created_at"),
+        updated_at: row.get("user_updated_at"),
+        email_verified: row.get("email_verified"),
+        email_verified_at: row.get("email_verified_at"),
+      };
+      DbSessionWithUser { session, user }
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(result)
+  }
+
+  async fn scan_sessions(&self, after_id: Option<&str>, limit: u32) -> Result<Vec<DbSession>> {
+    let sessions = sqlx::query(
+      r#"
+      SELECT id, token, user_id, expires_at, created_at, ip_address, user_agent, remember_me,
+        last_activity_at, token_prefix
+      FROM sessions
+      WHERE id > $1
+      ORDER BY id ASC
+      LIMIT $2
+      "#,
+    )
+    .bind(after_id.unwrap_or(""))
+    .bind(i64::from(limit))
+    .map(|row: sqlx::postgres::PgRow| DbSession {
+      id: row.get("id"),
+      token: row.get("token"),
+      user_id: row.get("user_id"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      ip_address: row.get("ip_address"),
+      user_agent: row.get("user_agent"),
+      remember_me: row.get("remember_me"),
+      last_activity_at: row.get("last_activity_at"),
+      token_prefix: row.get("token_prefix"),
+    })
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(sessions)
+  }
+
+  async fn find_sessions_by_user_id(&self, user_id: &str, now: i64) -> Result<Vec<DbSession>> {
+    let sessions = sqlx::query(
+      r#"
+      SELECT id, token, user_id, expires_at, created_at, ip_address, user_agent, remember_me,
+        last_activity_at, token_prefix
+      FROM sessions
+      WHERE user_id = $1 AND expires_at > $2
+      ORDER BY created_at DESC, id ASC
+      "#,
+    )
+    .bind(user_id)
+    .bind(now)
+    .map(|row: sqlx::postgres::PgRow| DbSession {
+      id: row.get("id"),
+      token: row.get("token"),
+      user_id: row.get("user_id"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      ip_address: row.get("ip_address"),
+      user_agent: row.get("user_agent"),
+      remember_me: row.get("remember_me"),
+      last_activity_at: row.get("last_activity_at"),
+      token_prefix: row.get("token_prefix"),
+    })
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(sessions)
+  }
+
+  async fn delete_user_session(&self, user_id: &str, session_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+      r#"
+      DELETE FROM sessions
+      WHERE user_id = $1 AND id = $2
+      "#,
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  async fn touch_session(&self, id: &str, now: i64) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE sessions
+      SET last_activity_at = $1
+      WHERE id = $2
+      "#,
+    )
+    .bind(now)
+    .bind(id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn update_session_expiry(&self, id: &str, expires_at: i64) -> Result<bool> {
+    let result = sqlx::query(
+      r#"
+      UPDATE sessions
+      SET expires_at = $1
+      WHERE id = $2
+      "#,
+    )
     .bind(expires_at)
-    .bind(created_at)
+    .bind(id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  async fn delete_session(&self, token: &str) -> Result<()> {
+    sqlx::query(
+      r#"
+      DELETE FROM sessions
+      WHERE token = $1
+      "#,
+    )
+    .bind(token)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn rotate_session_token(&self, old_token: &str, new_token: &str, now: i64) -> Result<bool> {
+    let result = sqlx::query(
+      r#"
+      UPDATE sessions
+      SET token = $1, token_prefix = substr($1, 1, length(token_prefix))
+      WHERE token = $2 AND expires_at >= $3
+      "#,
+    )
+    .bind(new_token)
+    .bind(old_token)
+    .bind(now)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  async fn delete_expired_sessions(&self) -> Result<u64> {
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    let result = sqlx::query(
+      r#"
+      DELETE FROM sessions
+      WHERE expires_at < $1
+      "#,
+    )
+    .bind(now)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn delete_user_sessions(&self, user_id: &str) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+      .bind(user_id)
+      .execute(&self.pool)
+      .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn delete_all_sessions(&self) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM sessions")
+      .execute(&self.pool)
+      .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  // ==========================================
+  // Verification Token Operations
+  // ==========================================
+
+  async fn create_verification(&self, verification: &DbVerification) -> Result<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO verification (id, user_id, identifier, token_hash, token_type, expires_at, created_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+    )
+    .bind(&verification.id)
+    .bind(&verification.user_id)
+    .bind(&verification.identifier)
+    .bind(&verification.token_hash)
+    .bind(&verification.token_type)
+    .bind(verification.expires_at)
+    .bind(verification.created_at)
     .execute(&self.pool)
     .await?;
 
@@ -486,12 +1353,252 @@ impl DatabaseTrait for PostgresDatabase {
     Ok(token)
   }
 
-  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<()> {
+  async fn find_verification_by_hash(&self, token_hash: &str) -> Result<Option<DbVerification>> {
+    let token = sqlx::query(
+      r#"
+      SELECT id, user_id, identifier, token_hash, token_type, expires_at, created_at, used_at
+      FROM verification
+      WHERE token_hash = $1
+      "#,
+    )
+    .bind(token_hash)
+    .map(|row: sqlx::postgres::PgRow| DbVerification {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      identifier: row.get("identifier"),
+      token_hash: row.get("token_hash"),
+      token_type: row.get("token_type"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      used_at: row.get("used_at"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(token)
+  }
+
+  async fn find_latest_verification(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>> {
+    let token = sqlx::query(
+      r#"
+      SELECT id, user_id, identifier, token_hash, token_type, expires_at, created_at, used_at
+      FROM verification
+      WHERE identifier = $1 AND token_type = $2
+      ORDER BY created_at DESC
+      LIMIT 1
+      "#,
+    )
+    .bind(identifier)
+    .bind(token_type)
+    .map(|row: sqlx::postgres::PgRow| DbVerification {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      identifier: row.get("identifier"),
+      token_hash: row.get("token_hash"),
+      token_type: row.get("token_type"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      used_at: row.get("used_at"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(token)
+  }
+
+  async fn record_verification_send(
+    &self,
+    send: &DbVerificationSend,
+    prune_before: i64,
+  ) -> Result<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO verification_sends (id, user_id, identifier, token_type, sent_at)
+      VALUES ($1, $2, $3, $4, $5)
+      "#,
+    )
+    .bind(&send.id)
+    .bind(&send.user_id)
+    .bind(&send.identifier)
+    .bind(&send.token_type)
+    .bind(send.sent_at)
+    .execute(&self.pool)
+    .await?;
+
     sqlx::query(
+      r#"
+      DELETE FROM verification_sends
+      WHERE user_id = $1 AND sent_at < $2
+      "#,
+    )
+    .bind(&send.user_id)
+    .bind(prune_before)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn find_latest_verification_send(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<i64>> {
+    let sent_at = sqlx::query_scalar(
+      r#"
+      SELECT MAX(sent_at)
+      FROM verification_sends
+      WHERE identifier = $1 AND token_type = $2
+      "#,
+    )
+    .bind(identifier)
+    .bind(token_type)
+    .fetch_one(&self.pool)
+    .await?;
+
+    Ok(sent_at)
+  }
+
+  async fn find_verification_status(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerificationStatus>> {
+    let status = sqlx::query(
+      r#"
+      SELECT u.id, u.email, u.name, u.created_at, u.updated_at, u.email_verified,
+        u.email_verified_at, v.expires_at AS pending_token_expires_at
+      FROM users u
+      LEFT JOIN verification v ON v.id = (
+        SELECT id FROM verification
+        WHERE user_id = u.id AND token_type = $1 AND used_at IS NULL AND expires_at >= $2
+        ORDER BY created_at DESC
+        LIMIT 1
+      )
+      WHERE u.id = $3
+      "#,
+    )
+    .bind(token_type)
+    .bind(now)
+    .bind(user_id)
+    .map(|row: sqlx::postgres::PgRow| DbVerificationStatus {
+      user: DbUser {
+        id: row.get("id"),
+        email: row.get("email"),
+        name: row.get("name"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        email_verified: row.get("email_verified"),
+        email_verified_at: row.get("email_verified_at"),
+      },
+      pending_token_expires_at: row.get("pending_token_expires_at"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(status)
+  }
+
+  #[cfg(feature = "encrypted-tokens")]
+  async fn find_valid_token_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerification>> {
+    let token = sqlx::query(
+      r#"
+      SELECT id, user_id, identifier, token_hash, token_type, expires_at, created_at, used_at
+      FROM verification
+      WHERE user_id = $1 AND token_type = $2 AND used_at IS NULL AND expires_at >= $3
+      ORDER BY created_at DESC
+      LIMIT 1
+      "#,
+    )
+    .bind(user_id)
+    .bind(token_type)
+    .bind(now)
+    .map(|row: sqlx::postgres::PgRow| DbVerification {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      identifier: row.get("identifier"),
+      token_hash: row.get("token_hash"),
+      token_type: row.get("token_type"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      used_at: row.get("used_at"),
+    })
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(token)
+  }
+
+  async fn count_verification_sends_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    since: i64,
+  ) -> Result<u64> {
+    let count: i64 = sqlx::query(
+      r#"
+      SELECT COUNT(*) AS count
+      FROM verification_sends
+      WHERE user_id = $1 AND token_type = $2 AND sent_at >= $3
+      "#,
+    )
+    .bind(user_id)
+    .bind(token_type)
+    .bind(since)
+    .fetch_one(&self.pool)
+    .await?
+    .get("count");
+
+    Ok(count as u64)
+  }
+
+  async fn find_verifications_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<DbVerification>> {
+    let tokens = sqlx::query(
+      r#"
+      SELECT id, user_id, identifier, token_hash, token_type, expires_at, created_at, used_at
+      FROM verification
+      WHERE identifier = $1 AND token_type = $2
+      ORDER BY created_at DESC
+      "#,
+    )
+    .bind(identifier)
+    .bind(token_type)
+    .map(|row: sqlx::postgres::PgRow| DbVerification {
+      id: row.get("id"),
+      user_id: row.get("user_id"),
+      identifier: row.get("identifier"),
+      token_hash: row.get("token_hash"),
+      token_type: row.get("token_type"),
+      expires_at: row.get("expires_at"),
+      created_at: row.get("created_at"),
+      used_at: row.get("used_at"),
+    })
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(tokens)
+  }
+
+  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<bool> {
+    let result = sqlx::query(
       r#"
       UPDATE verification
       SET used_at = $1
-      WHERE token_hash = $2
+      WHERE token_hash = $2 AND used_at IS NULL
       "#,
     )
     .bind(used_at)
@@ -499,7 +1606,7 @@ impl DatabaseTrait for PostgresDatabase {
     .execute(&self.pool)
     .await?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
   }
 
   async fn delete_verification(&self, token_hash: &str) -> Result<()> {
@@ -516,6 +1623,44 @@ impl DatabaseTrait for PostgresDatabase {
     Ok(())
   }
 
+  async fn delete_tokens_for_user(&self, user_id: &str, token_type: &str) -> Result<u64> {
+    let result = sqlx::query(
+      r#"
+      DELETE FROM verification
+      WHERE user_id = $1 AND token_type = $2 AND used_at IS NULL
+      "#,
+    )
+    .bind(user_id)
+    .bind(token_type)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn delete_all_verifications(&self, token_type: Option<&str>) -> Result<u64> {
+    let result = match token_type {
+      Some(token_type) => {
+        sqlx::query(
+          r#"
+          DELETE FROM verification
+          WHERE token_type = $1
+          "#,
+        )
+        .bind(token_type)
+        .execute(&self.pool)
+        .await?
+      }
+      None => {
+        sqlx::query("DELETE FROM verification")
+          .execute(&self.pool)
+          .await?
+      }
+    };
+
+    Ok(result.rows_affected())
+  }
+
   async fn delete_expired_verifications(&self) -> Result<u64> {
     let now = std::time::SystemTime::now()
       .duration_since(std::time::UNIX_EPOCH)
@@ -534,4 +1679,151 @@ impl DatabaseTrait for PostgresDatabase {
 
     Ok(result.rows_affected())
   }
+  #[cfg(feature = "persistent-queue")]
+  async fn insert_email_job(&self, job: &DbEmailJob) -> Result<()> {
+    sqlx::query(
+      r#"
+      INSERT INTO email_jobs (id, job_type, template_id, recipient, token, token_expires_at, user_id, data, status, attempts, max_attempts, last_error, created_at, updated_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+      "#,
+    )
+    .bind(&job.id)
+    .bind(&job.job_type)
+    .bind(&job.template_id)
+    .bind(&job.recipient)
+    .bind(&job.token)
+    .bind(job.token_expires_at)
+    .bind(&job.user_id)
+    .bind(&job.data)
+    .bind(&job.status)
+    .bind(job.attempts)
+    .bind(job.max_attempts)
+    .bind(&job.last_error)
+    .bind(job.created_at)
+    .bind(job.updated_at)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn claim_next_email_job(&self, now: i64) -> Result<Option<DbEmailJob>> {
+    // A single UPDATE claims the job, so two workers can't both take it
+    let job = sqlx::query(
+      r#"
+      UPDATE email_jobs
+      SET status = 'sending', updated_at = $1
+      WHERE status = 'pending' AND id = (
+        SELECT id FROM email_jobs
+        WHERE status = 'pending'
+        ORDER BY created_at, id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+      )
+      RETURNING id, job_type, template_id, recipient, token, token_expires_at, user_id, data, status, attempts, max_attempts, last_error, created_at, updated_at
+      "#,
+    )
+    .bind(now)
+    .map(|row: sqlx::postgres::PgRow| email_job_from_row(&row))
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(job)
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn record_email_job_attempt(
+    &self,
+    id: &str,
+    attempts: i64,
+    last_error: Option<&str>,
+    now: i64,
+  ) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE email_jobs
+      SET attempts = $1, last_error = $2, updated_at = $3
+      WHERE id = $4
+      "#,
+    )
+    .bind(attempts)
+    .bind(last_error)
+    .bind(now)
+    .bind(id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn finish_email_job(&self, id: &str, status: &str, now: i64) -> Result<()> {
+    sqlx::query(
+      r#"
+      UPDATE email_jobs
+      SET status = $1, token = '', data = '{}', updated_at = $2
+      WHERE id = $3
+      "#,
+    )
+    .bind(status)
+    .bind(now)
+    .bind(id)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn requeue_stale_email_jobs(&self, claimed_before: i64, now: i64) -> Result<u64> {
+    let result = sqlx::query(
+      r#"
+      UPDATE email_jobs
+      SET status = 'pending', updated_at = $1
+      WHERE status = 'sending' AND updated_at < $2
+      "#,
+    )
+    .bind(now)
+    .bind(claimed_before)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn delete_finished_email_jobs(&self, finished_before: i64) -> Result<u64> {
+    let result = sqlx::query(
+      r#"
+      DELETE FROM email_jobs
+      WHERE status IN ('sent', 'failed') AND updated_at < $1
+      "#,
+    )
+    .bind(finished_before)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+}
+
+#[cfg(feature = "persistent-queue")]
+fn email_job_from_row(row: &sqlx::postgres::PgRow) -> DbEmailJob {
+  DbEmailJob {
+    id: row.get("id"),
+    job_type: row.get("job_type"),
+    template_id: row.get("template_id"),
+    recipient: row.get("recipient"),
+    token: row.get("token"),
+    token_expires_at: row.get("token_expires_at"),
+    user_id: row.get("user_id"),
+    data: row.get("data"),
+    status: row.get("status"),
+    attempts: row.get("attempts"),
+    max_attempts: row.get("max_attempts"),
+    last_error: row.get("last_error"),
+    created_at: row.get("created_at"),
+    updated_at: row.get("updated_at"),
+  }
 }
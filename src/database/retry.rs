@@ -0,0 +1,602 @@
+#[cfg(feature = "persistent-queue")]
+use super::models::DbEmailJob;
+#[cfg(feature = "sms")]
+use super::models::DbUserPhone;
+use super::models::{
+  DbAccount, DbSession, DbSessionWithUser, DbUser, DbUserEmail, DbUserWithAccount, DbVerification,
+  DbVerificationSend, DbVerificationStatus,
+};
+use super::DatabaseTrait;
+use crate::error::Result;
+use crate::retry::RetryPolicy;
+use crate::types::User;
+use async_trait::async_trait;
+
+/// Database wrapper that retries calls failing with a transient error
+///
+/// Installed by `AuthBuilder::db_retry`. Every call is retried on I/O errors
+/// and pool timeouts per the policy. A write whose connection drops after
+/// the commit may be retried and then fail on a constraint (e.g. a duplicate
+/// id), which surfaces as the usual error.
+pub(crate) struct RetryingDatabase {
+  inner: Box<dyn DatabaseTrait>,
+  policy: RetryPolicy,
+}
+
+impl RetryingDatabase {
+  pub(crate) fn new(inner: Box<dyn DatabaseTrait>, policy: RetryPolicy) -> Self {
+    Self { inner, policy }
+  }
+}
+
+#[async_trait]
+impl DatabaseTrait for RetryingDatabase {
+  async fn migrate(&self) -> Result<()> {
+    self.policy.run(|| self.inner.migrate()).await
+  }
+
+  async fn migrate_email_verification(&self) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.migrate_email_verification())
+      .await
+  }
+
+  async fn ping(&self) -> Result<()> {
+    self.policy.run(|| self.inner.ping()).await
+  }
+
+  async fn find_user_by_email(&self, email: &str) -> Result<Option<DbUser>> {
+    self
+      .policy
+      .run(|| self.inner.find_user_by_email(email))
+      .await
+  }
+
+  async fn find_user_by_id(&self, id: &str) -> Result<Option<User>> {
+    self.policy.run(|| self.inner.find_user_by_id(id)).await
+  }
+
+  async fn create_user(
+    &self,
+    id: &str,
+    email: &str,
+    name: Option<&str>,
+    created_at: i64,
+  ) -> Result<User> {
+    self
+      .policy
+      .run(|| self.inner.create_user(id, email, name, created_at))
+      .await
+  }
+
+  async fn update_email_verified(&self, user_id: &str, verified_at: i64) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.update_email_verified(user_id, verified_at))
+      .await
+  }
+
+  async fn find_user_by_id_with_verification(&self, id: &str) -> Result<Option<User>> {
+    self
+      .policy
+      .run(|| self.inner.find_user_by_id_with_verification(id))
+      .await
+  }
+
+  async fn find_user_by_email_with_verification(&self, email: &str) -> Result<Option<DbUser>> {
+    self
+      .policy
+      .run(|| self.inner.find_user_by_email_with_verification(email))
+      .await
+  }
+
+  async fn find_unverified_users_created_before(&self, cutoff: i64) -> Result<Vec<User>> {
+    self
+      .policy
+      .run(|| self.inner.find_unverified_users_created_before(cutoff))
+      .await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn set_user_phone(&self, user_id: &str, phone: &str, updated_at: i64) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.set_user_phone(user_id, phone, updated_at))
+      .await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn find_user_phone(&self, user_id: &str) -> Result<Option<DbUserPhone>> {
+    self
+      .policy
+      .run(|| self.inner.find_user_phone(user_id))
+      .await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn mark_phone_verified(&self, user_id: &str, verified_at: i64) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.mark_phone_verified(user_id, verified_at))
+      .await
+  }
+
+  async fn create_account(
+    &self,
+    id: &str,
+    user_id: &str,
+    provider: &str,
+    provider_account_id: &str,
+    password_hash: Option<&str>,
+    created_at: i64,
+  ) -> Result<()> {
+    self
+      .policy
+      .run(|| {
+        self.inner.create_account(
+          id,
+          user_id,
+          provider,
+          provider_account_id,
+          password_hash,
+          created_at,
+        )
+      })
+      .await
+  }
+
+  async fn find_account_by_provider(
+    &self,
+    provider: &str,
+    provider_account_id: &str,
+  ) -> Result<Option<DbAccount>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_account_by_provider(provider, provider_account_id)
+      })
+      .await
+  }
+
+  async fn find_accounts_by_user_id(&self, user_id: &str) -> Result<Vec<DbAccount>> {
+    self
+      .policy
+      .run(|| self.inner.find_accounts_by_user_id(user_id))
+      .await
+  }
+
+  async fn update_account_password_hash(
+    &self,
+    account_id: &str,
+    password_hash: &str,
+    updated_at: i64,
+  ) -> Result<()> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .update_account_password_hash(account_id, password_hash, updated_at)
+      })
+      .await
+  }
+
+  async fn accept_invitation(
+    &self,
+    user_id: &str,
+    account_id: &str,
+    credential_provider: &str,
+    email: &str,
+    password_hash: &str,
+    verification_id: Option<&str>,
+    now: i64,
+  ) -> Result<bool> {
+    // Not idempotent: a retried commit would read as an already used token
+    self
+      .policy
+      .run_unsent(|| {
+        self.inner.accept_invitation(
+          user_id,
+          account_id,
+          credential_provider,
+          email,
+          password_hash,
+          verification_id,
+          now,
+        )
+      })
+      .await
+  }
+
+  async fn find_user_with_credential_account(
+    &self,
+    email: &str,
+    provider: &str,
+  ) -> Result<Option<DbUserWithAccount>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_user_with_credential_account(email, provider)
+      })
+      .await
+  }
+
+  async fn find_user_with_credential_account_with_verification(
+    &self,
+    email: &str,
+    provider: &str,
+  ) -> Result<Option<DbUserWithAccount>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_user_with_credential_account_with_verification(email, provider)
+      })
+      .await
+  }
+
+  async fn create_user_email(
+    &self,
+    id: &str,
+    user_id: &str,
+    email: &str,
+    created_at: i64,
+  ) -> Result<DbUserEmail> {
+    self
+      .policy
+      .run(|| self.inner.create_user_email(id, user_id, email, created_at))
+      .await
+  }
+
+  async fn find_user_email(&self, email: &str) -> Result<Option<DbUserEmail>> {
+    self.policy.run(|| self.inner.find_user_email(email)).await
+  }
+
+  async fn find_user_emails_by_user_id(&self, user_id: &str) -> Result<Vec<DbUserEmail>> {
+    self
+      .policy
+      .run(|| self.inner.find_user_emails_by_user_id(user_id))
+      .await
+  }
+
+  async fn mark_user_email_verified(&self, email: &str, verified_at: i64) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.mark_user_email_verified(email, verified_at))
+      .await
+  }
+
+  async fn promote_user_email(
+    &self,
+    user_id: &str,
+    email: &str,
+    demoted_id: &str,
+    credential_provider: &str,
+    now: i64,
+  ) -> Result<()> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .promote_user_email(user_id, email, demoted_id, credential_provider, now)
+      })
+      .await
+  }
+
+  async fn create_session(&self, session: &DbSession) -> Result<()> {
+    self.policy.run(|| self.inner.create_session(session)).await
+  }
+
+  async fn find_session(&self, token: &str) -> Result<Option<DbSession>> {
+    self.policy.run(|| self.inner.find_session(token)).await
+  }
+
+  async fn find_session_with_user(&self, token: &str) -> Result<Option<DbSessionWithUser>> {
+    self
+      .policy
+      .run(|| self.inner.find_session_with_user(token))
+      .await
+  }
+
+  async fn find_session_with_user_with_verification(
+    &self,
+    token: &str,
+  ) -> Result<Option<DbSessionWithUser>> {
+    self
+      .policy
+      .run(|| self.inner.find_session_with_user_with_verification(token))
+      .await
+  }
+
+  async fn scan_sessions(&self, after_id: Option<&str>, limit: u32) -> Result<Vec<DbSession>> {
+    self
+      .policy
+      .run(|| self.inner.scan_sessions(after_id, limit))
+      .await
+  }
+
+  async fn find_sessions_by_user_id(&self, user_id: &str, now: i64) -> Result<Vec<DbSession>> {
+    self
+      .policy
+      .run(|| self.inner.find_sessions_by_user_id(user_id, now))
+      .await
+  }
+
+  async fn delete_user_session(&self, user_id: &str, session_id: &str) -> Result<bool> {
+    self
+      .policy
+      .run(|| self.inner.delete_user_session(user_id, session_id))
+      .await
+  }
+
+  async fn touch_session(&self, id: &str, now: i64) -> Result<()> {
+    self.policy.run(|| self.inner.touch_session(id, now)).await
+  }
+
+  async fn update_session_expiry(&self, id: &str, expires_at: i64) -> Result<bool> {
+    self
+      .policy
+      .run(|| self.inner.update_session_expiry(id, expires_at))
+      .await
+  }
+
+  async fn delete_session(&self, token: &str) -> Result<()> {
+    self.policy.run(|| self.inner.delete_session(token)).await
+  }
+
+  async fn rotate_session_token(&self, old_token: &str, new_token: &str, now: i64) -> Result<bool> {
+    self
+      .policy
+      .run(|| self.inner.rotate_session_token(old_token, new_token, now))
+      .await
+  }
+
+  async fn delete_expired_sessions(&self) -> Result<u64> {
+    self
+      .policy
+      .run(|| self.inner.delete_expired_sessions())
+      .await
+  }
+
+  // Bulk logouts delete the sessions that exist when they run; a replay
+  // after a dropped commit would also delete logins made since
+  async fn delete_user_sessions(&self, user_id: &str) -> Result<u64> {
+    self
+      .policy
+      .run_unsent(|| self.inner.delete_user_sessions(user_id))
+      .await
+  }
+
+  async fn delete_all_sessions(&self) -> Result<u64> {
+    self
+      .policy
+      .run_unsent(|| self.inner.delete_all_sessions())
+      .await
+  }
+
+  async fn create_verification(&self, verification: &DbVerification) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.create_verification(verification))
+      .await
+  }
+
+  async fn find_verification(
+    &self,
+    token_hash: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>> {
+    self
+      .policy
+      .run(|| self.inner.find_verification(token_hash, token_type))
+      .await
+  }
+
+  async fn find_verification_by_hash(&self, token_hash: &str) -> Result<Option<DbVerification>> {
+    self
+      .policy
+      .run(|| self.inner.find_verification_by_hash(token_hash))
+      .await
+  }
+
+  async fn find_latest_verification(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>> {
+    self
+      .policy
+      .run(|| self.inner.find_latest_verification(identifier, token_type))
+      .await
+  }
+
+  async fn record_verification_send(
+    &self,
+    send: &DbVerificationSend,
+    prune_before: i64,
+  ) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.record_verification_send(send, prune_before))
+      .await
+  }
+
+  async fn find_latest_verification_send(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<i64>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_latest_verification_send(identifier, token_type)
+      })
+      .await
+  }
+
+  async fn find_verification_status(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerificationStatus>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_verification_status(user_id, token_type, now)
+      })
+      .await
+  }
+
+  #[cfg(feature = "encrypted-tokens")]
+  async fn find_valid_token_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerification>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_valid_token_for_user(user_id, token_type, now)
+      })
+      .await
+  }
+
+  async fn count_verification_sends_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    since: i64,
+  ) -> Result<u64> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .count_verification_sends_for_user(user_id, token_type, since)
+      })
+      .await
+  }
+
+  async fn find_verifications_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<DbVerification>> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .find_verifications_by_identifier(identifier, token_type)
+      })
+      .await
+  }
+
+  // A replay after a dropped commit would find the token used and report
+  // that this caller lost the claim it actually won
+  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<bool> {
+    self
+      .policy
+      .run_unsent(|| self.inner.mark_verification_used(token_hash, used_at))
+      .await
+  }
+
+  async fn delete_verification(&self, token_hash: &str) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.delete_verification(token_hash))
+      .await
+  }
+
+  async fn delete_tokens_for_user(&self, user_id: &str, token_type: &str) -> Result<u64> {
+    self
+      .policy
+      .run(|| self.inner.delete_tokens_for_user(user_id, token_type))
+      .await
+  }
+
+  // Like bulk logouts: a replay would also delete tokens issued since
+  async fn delete_all_verifications(&self, token_type: Option<&str>) -> Result<u64> {
+    self
+      .policy
+      .run_unsent(|| self.inner.delete_all_verifications(token_type))
+      .await
+  }
+
+  async fn delete_expired_verifications(&self) -> Result<u64> {
+    self
+      .policy
+      .run(|| self.inner.delete_expired_verifications())
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn insert_email_job(&self, job: &DbEmailJob) -> Result<()> {
+    self.policy.run(|| self.inner.insert_email_job(job)).await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn claim_next_email_job(&self, now: i64) -> Result<Option<DbEmailJob>> {
+    self
+      .policy
+      .run(|| self.inner.claim_next_email_job(now))
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn record_email_job_attempt(
+    &self,
+    id: &str,
+    attempts: i64,
+    last_error: Option<&str>,
+    now: i64,
+  ) -> Result<()> {
+    self
+      .policy
+      .run(|| {
+        self
+          .inner
+          .record_email_job_attempt(id, attempts, last_error, now)
+      })
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn finish_email_job(&self, id: &str, status: &str, now: i64) -> Result<()> {
+    self
+      .policy
+      .run(|| self.inner.finish_email_job(id, status, now))
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn requeue_stale_email_jobs(&self, claimed_before: i64, now: i64) -> Result<u64> {
+    self
+      .policy
+      .run(|| self.inner.requeue_stale_email_jobs(claimed_before, now))
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn delete_finished_email_jobs(&self, finished_before: i64) -> Result<u64> {
+    self
+      .policy
+      .run(|| self.inner.delete_finished_email_jobs(finished_before))
+      .await
+  }
+}
@@ -1,5 +1,6 @@
 use crate::error::Result;
 use async_trait::async_trait;
+use std::collections::HashMap;
 
 /// Context provided to email senders containing information about the email to send
 #[derive(Debug, Clone)]
@@ -12,6 +13,53 @@ pub struct EmailContext {
   pub expires_at: i64,
 }
 
+impl EmailContext {
+  /// The link to put in the email; see [`VerificationToken::verification_url`]
+  ///
+  /// [`VerificationToken::verification_url`]: crate::VerificationToken::verification_url
+  pub fn verification_url(&self, template: &str) -> String {
+    verification_url(template, &self.token)
+  }
+}
+
+/// Placeholder replaced with the token in verification URL templates
+const TOKEN_PLACEHOLDER: &str = "{token}";
+
+/// Build a verification link from a template or base URL
+pub(crate) fn verification_url(template: &str, token: &str) -> String {
+  let token = percent_encode(token);
+  if template.contains(TOKEN_PLACEHOLDER) {
+    return template.replace(TOKEN_PLACEHOLDER, &token);
+  }
+
+  let separator = if template.contains('?') { '&' } else { '?' };
+  format!("{}{}token={}", template, separator, token)
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn percent_encode(value: &str) -> String {
+  let mut encoded = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+      encoded.push(char::from(byte));
+    } else {
+      encoded.push_str(&format!("%{:02X}", byte));
+    }
+  }
+  encoded
+}
+
+/// Context provided to email senders for application-defined transactional emails
+#[derive(Debug, Clone)]
+pub struct CustomEmailContext {
+  /// The recipient's email address
+  pub email: String,
+  /// Application-defined template identifier
+  pub template_id: String,
+  /// Template data supplied when the email was queued
+  pub data: HashMap<String, String>,
+}
+
 /// Trait for sending verification emails
 ///
 /// Implement this trait to provide your own email sending logic.
@@ -32,10 +80,8 @@ pub struct EmailContext {
 /// impl EmailSender for MyEmailSender {
 ///     async fn send_verification_email(&self, context: EmailContext) -> Result<()> {
 ///         // Use your email service (SendGrid, AWS SES, SMTP, etc.)
-///         let verification_url = format!(
-///             "https://myapp.com/verify?token={}",
-///             context.token
-///         );
+///         let verification_url =
+///             context.verification_url("https://myapp.com/verify?token={token}");
 ///
 ///         // Send email using your service
 ///         my_email_service::send(
@@ -64,4 +110,12 @@ pub trait EmailSender: Send + Sync {
   /// * `Ok(())` if the email was sent successfully
   /// * `Err(_)` if there was an error sending the email
   async fn send_verification_email(&self, context: EmailContext) -> Result<()>;
+
+  /// Send an application-defined transactional email
+  ///
+  /// Called by the email worker for jobs queued with `EmailQueue::enqueue_custom`.
+  /// The default implementation does nothing, so existing senders keep working.
+  async fn send_custom_email(&self, _context: CustomEmailContext) -> Result<()> {
+    Ok(())
+  }
 }
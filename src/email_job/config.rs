@@ -1,10 +1,31 @@
+use super::error::EmailQueueError;
+use crate::retry::RetryPolicy;
+
 #[derive(Clone, Debug)]
 pub struct EmailWorkerConfig {
   pub channel_buffer_size: usize,
   pub base_retry_delay: std::time::Duration,
   pub max_retry_delay: std::time::Duration,
+  /// Fraction of each retry delay to randomize by, between 0.0 and 1.0
+  pub retry_jitter: f64,
   pub default_max_attempts: u32,
   pub non_blocking: bool,
+  /// Maximum emails sent per second (including retries); `None` is unpaced
+  pub max_send_rate: Option<u32>,
+  /// Persist jobs in the `email_jobs` table so they survive restarts
+  #[cfg(feature = "persistent-queue")]
+  pub persistent: bool,
+  /// How often a persistent worker checks for jobs it wasn't woken for
+  #[cfg(feature = "persistent-queue")]
+  pub poll_interval: std::time::Duration,
+  /// How long a persisted job may stay claimed before another worker
+  /// assumes its worker died and retries it
+  #[cfg(feature = "persistent-queue")]
+  pub stale_claim_timeout: std::time::Duration,
+  /// How long `sent` and `failed` jobs are kept before the worker deletes
+  /// them
+  #[cfg(feature = "persistent-queue")]
+  pub finished_job_retention: std::time::Duration,
 }
 
 impl Default for EmailWorkerConfig {
@@ -13,8 +34,18 @@ impl Default for EmailWorkerConfig {
       channel_buffer_size: 100,
       base_retry_delay: std::time::Duration::from_secs(1),
       max_retry_delay: std::time::Duration::from_secs(60),
+      retry_jitter: 0.1,
       default_max_attempts: 2,
       non_blocking: false,
+      max_send_rate: None,
+      #[cfg(feature = "persistent-queue")]
+      persistent: false,
+      #[cfg(feature = "persistent-queue")]
+      poll_interval: std::time::Duration::from_secs(5),
+      #[cfg(feature = "persistent-queue")]
+      stale_claim_timeout: std::time::Duration::from_secs(5 * 60),
+      #[cfg(feature = "persistent-queue")]
+      finished_job_retention: std::time::Duration::from_secs(7 * 24 * 60 * 60),
     }
   }
 }
@@ -28,8 +59,102 @@ impl EmailWorkerConfig {
     self.base_retry_delay = delay;
     self
   }
+  pub fn with_max_retry_delay(mut self, delay: std::time::Duration) -> Self {
+    self.max_retry_delay = delay;
+    self
+  }
+  /// Take retry delays, jitter and max attempts from a shared `RetryPolicy`
+  pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+    self.base_retry_delay = policy.base;
+    self.max_retry_delay = policy.max;
+    self.retry_jitter = policy.jitter;
+    self.default_max_attempts = policy.max_attempts;
+    self
+  }
+  /// The retry settings as a `RetryPolicy`
+  pub fn retry_policy(&self) -> RetryPolicy {
+    RetryPolicy {
+      max_attempts: self.default_max_attempts,
+      base: self.base_retry_delay,
+      max: self.max_retry_delay,
+      jitter: self.retry_jitter,
+    }
+  }
+  /// Pace the worker to at most `per_second` sends, e.g. to stay under a
+  /// provider's rate limit during bulk sends
+  pub fn with_max_send_rate(mut self, per_second: u32) -> Self {
+    self.max_send_rate = Some(per_second);
+    self
+  }
+  /// Persist jobs in the database so they survive process restarts
+  ///
+  /// `enqueue` inserts a row into `email_jobs` (created by `Auth::migrate`)
+  /// and the worker claims rows one at a time, marking them `sent` or
+  /// `failed` once done. On start, the worker picks up jobs left pending by
+  /// a previous process. Pending rows hold the token until the job
+  /// finishes, encrypted when `AuthBuilder::token_encryption_key` is set;
+  /// finished rows keep no token and are deleted after
+  /// `finished_job_retention`.
+  #[cfg(feature = "persistent-queue")]
+  pub fn persistent(mut self) -> Self {
+    self.persistent = true;
+    self
+  }
   pub fn blocking(mut self) -> Self {
     self.non_blocking = false;
     self
   }
+
+  /// Check the configuration for values that would break the worker
+  ///
+  /// Rejects a zero buffer size (the channel can't be created), a zero base
+  /// retry delay, a max retry delay below the base, jitter outside 0.0..=1.0,
+  /// zero max attempts, and a zero send rate.
+  /// Called by `AuthBuilder::build` when an email queue is configured.
+  pub fn validate(&self) -> Result<(), EmailQueueError> {
+    if self.channel_buffer_size == 0 {
+      return Err(EmailQueueError::ConfigError(
+        "channel_buffer_size must be greater than zero".to_string(),
+      ));
+    }
+    if self.base_retry_delay.is_zero() {
+      return Err(EmailQueueError::ConfigError(
+        "base_retry_delay must be greater than zero".to_string(),
+      ));
+    }
+    if self.max_retry_delay < self.base_retry_delay {
+      return Err(EmailQueueError::ConfigError(
+        "max_retry_delay must be at least base_retry_delay".to_string(),
+      ));
+    }
+    if !(0.0..=1.0).contains(&self.retry_jitter) {
+      return Err(EmailQueueError::ConfigError(
+        "retry_jitter must be between 0.0 and 1.0".to_string(),
+      ));
+    }
+    if self.default_max_attempts == 0 {
+      return Err(EmailQueueError::ConfigError(
+        "default_max_attempts must be at least 1".to_string(),
+      ));
+    }
+    if self.max_send_rate == Some(0) {
+      return Err(EmailQueueError::ConfigError(
+        "max_send_rate must be greater than zero".to_string(),
+      ));
+    }
+    #[cfg(feature = "persistent-queue")]
+    if self.persistent && self.poll_interval.is_zero() {
+      return Err(EmailQueueError::ConfigError(
+        "poll_interval must be greater than zero".to_string(),
+      ));
+    }
+    #[cfg(feature = "persistent-queue")]
+    if self.persistent && self.stale_claim_timeout <= self.max_retry_delay {
+      return Err(EmailQueueError::ConfigError(
+        "stale_claim_timeout must be longer than max_retry_delay".to_string(),
+      ));
+    }
+
+    Ok(())
+  }
 }
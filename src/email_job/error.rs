@@ -17,6 +17,10 @@ pub enum EmailQueueError {
   #[error("Failed to send email after {attempts} attempts: {message}")]
   SendFailed { attempts: u32, message: String },
 
+  /// Reading or writing persisted jobs failed
+  #[error("Email job storage failed: {0}")]
+  StorageError(String),
+
   /// Worker configuration error
   #[error("Invalid worker configuration: {0}")]
   ConfigError(String),
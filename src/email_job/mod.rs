@@ -3,17 +3,22 @@
 mod config;
 mod error;
 mod queue;
+#[cfg(feature = "persistent-queue")]
+mod store;
 mod types;
 mod worker;
 
 pub use config::EmailWorkerConfig;
 pub use error::EmailQueueError;
 pub use queue::EmailQueue;
+use queue::WeakEmailQueue;
+#[cfg(feature = "persistent-queue")]
+pub(crate) use store::JobStore;
 pub use types::{EmailJob, EmailJobType};
 pub use worker::EmailWorker;
 
 use crate::email::EmailSender;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
@@ -28,6 +33,46 @@ pub fn create_email_queue(
 
   (queue, worker)
 }
+
+/// Like [`create_email_queue`], with jobs persisted in the database
+#[cfg(feature = "persistent-queue")]
+pub(crate) fn create_persistent_email_queue(
+  email_sender: Arc<Box<dyn EmailSender>>,
+  config: EmailWorkerConfig,
+  store: store::JobStore,
+) -> (EmailQueue, EmailWorker) {
+  let (queue, worker) = create_email_queue(email_sender, config);
+  (queue.with_store(store.clone()), worker.with_store(store))
+}
+
+/// The queue of the worker most recently started through `Auth`
+///
+/// Only a weak reference is kept, so shutting down or dropping the
+/// `EmailWorkerHandle` still lets the worker drain and stop. The slot only
+/// holds a weak reference, so a lock poisoned by a panic is safe to reuse.
+#[derive(Default)]
+pub(crate) struct WorkerSlot {
+  queue: RwLock<Option<WeakEmailQueue>>,
+}
+
+impl WorkerSlot {
+  pub(crate) fn set(&self, queue: &EmailQueue) {
+    *self.queue.write().unwrap_or_else(PoisonError::into_inner) = Some(queue.downgrade());
+  }
+
+  /// The running worker's queue, or `None` if it has stopped
+  pub(crate) fn queue(&self) -> Option<EmailQueue> {
+    let queue = self
+      .queue
+      .read()
+      .unwrap_or_else(PoisonError::into_inner)
+      .as_ref()?
+      .upgrade()?;
+    // A panicked or aborted worker drops its receiver, closing the channel
+    (!queue.is_closed()).then_some(queue)
+  }
+}
+
 pub struct EmailWorkerHandle {
   handle: JoinHandle<()>,
   queue: EmailQueue,
@@ -1,11 +1,16 @@
 use super::error::EmailQueueError;
+#[cfg(feature = "persistent-queue")]
+use super::store::JobStore;
 use super::types::EmailJob;
+use std::collections::HashMap;
 use tokio::sync::mpsc;
 
 #[derive(Clone)]
 pub struct EmailQueue {
   sender: mpsc::Sender<EmailJob>,
   non_blocking: bool,
+  #[cfg(feature = "persistent-queue")]
+  store: Option<JobStore>,
 }
 
 impl EmailQueue {
@@ -13,9 +18,26 @@ impl EmailQueue {
     Self {
       sender,
       non_blocking,
+      #[cfg(feature = "persistent-queue")]
+      store: None,
     }
   }
+  /// Persist enqueued jobs in `store`
+  #[cfg(feature = "persistent-queue")]
+  pub(crate) fn with_store(mut self, store: JobStore) -> Self {
+    self.store = Some(store);
+    self
+  }
   pub async fn enqueue(&self, job: EmailJob) -> Result<(), EmailQueueError> {
+    // Once the row is stored the job is safe; the message only wakes the
+    // worker, which also polls, so a full or closed channel is fine
+    #[cfg(feature = "persistent-queue")]
+    if let Some(store) = &self.store {
+      store.insert(&job).await?;
+      let _ = self.sender.try_send(job);
+      return Ok(());
+    }
+
     if self.non_blocking {
       self.sender.try_send(job).map_err(|e| match e {
         mpsc::error::TrySendError::Full(_) => EmailQueueError::QueueFull,
@@ -30,6 +52,44 @@ impl EmailQueue {
     }
   }
 
+  /// Queue several jobs in order
+  ///
+  /// Stops at the first job that can't be queued and returns its error;
+  /// jobs before it stay queued. Returns the number of jobs queued.
+  pub async fn enqueue_batch(&self, jobs: Vec<EmailJob>) -> Result<usize, EmailQueueError> {
+    let mut queued = 0;
+    for job in jobs {
+      self.enqueue(job).await?;
+      queued += 1;
+    }
+    Ok(queued)
+  }
+
+  /// Queue an application-defined transactional email
+  ///
+  /// The worker delivers it through `EmailSender::send_custom_email`, with the
+  /// same retry behavior as verification emails.
+  pub async fn enqueue_custom(
+    &self,
+    recipient: impl Into<String>,
+    template_id: impl Into<String>,
+    data: HashMap<String, String>,
+  ) -> Result<(), EmailQueueError> {
+    self
+      .enqueue(EmailJob::custom(recipient.into(), template_id.into(), data))
+      .await
+  }
+
+  /// A handle that doesn't keep the worker's channel open
+  pub(crate) fn downgrade(&self) -> WeakEmailQueue {
+    WeakEmailQueue {
+      sender: self.sender.downgrade(),
+      non_blocking: self.non_blocking,
+      #[cfg(feature = "persistent-queue")]
+      store: self.store.clone(),
+    }
+  }
+
   pub fn is_closed(&self) -> bool {
     self.sender.is_closed()
   }
@@ -38,6 +98,27 @@ impl EmailQueue {
   }
 }
 
+/// Weak counterpart of [`EmailQueue`]
+#[derive(Clone)]
+pub(crate) struct WeakEmailQueue {
+  sender: mpsc::WeakSender<EmailJob>,
+  non_blocking: bool,
+  #[cfg(feature = "persistent-queue")]
+  store: Option<JobStore>,
+}
+
+impl WeakEmailQueue {
+  /// The queue, if some `EmailQueue` still keeps the channel open
+  pub(crate) fn upgrade(&self) -> Option<EmailQueue> {
+    self.sender.upgrade().map(|sender| EmailQueue {
+      sender,
+      non_blocking: self.non_blocking,
+      #[cfg(feature = "persistent-queue")]
+      store: self.store.clone(),
+    })
+  }
+}
+
 impl std::fmt::Debug for EmailQueue {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     f.debug_struct("EmailQueue")
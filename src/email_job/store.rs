@@ -0,0 +1,225 @@
+use super::error::EmailQueueError;
+use super::types::{EmailJob, EmailJobType};
+use crate::database::models::DbEmailJob;
+use crate::database::DatabaseTrait;
+#[cfg(feature = "encrypted-tokens")]
+use crate::security::encryption::TokenCipher;
+use crate::security::tokens::TokenGenerator;
+use std::sync::Arc;
+
+/// Email jobs persisted in the `email_jobs` table
+///
+/// Jobs are inserted as `pending` and claimed one at a time by the worker,
+/// so they survive restarts and are never sent twice by concurrent workers.
+/// A pending job holds its token until it is sent or fails, encrypted when a
+/// cipher is set.
+#[derive(Clone)]
+pub(crate) struct JobStore {
+  db: Arc<Box<dyn DatabaseTrait>>,
+  token_generator: Arc<dyn TokenGenerator>,
+  #[cfg(feature = "encrypted-tokens")]
+  cipher: Option<TokenCipher>,
+}
+
+impl JobStore {
+  pub(crate) fn new(
+    db: Arc<Box<dyn DatabaseTrait>>,
+    token_generator: Arc<dyn TokenGenerator>,
+  ) -> Self {
+    Self {
+      db,
+      token_generator,
+      #[cfg(feature = "encrypted-tokens")]
+      cipher: None,
+    }
+  }
+
+  /// Encrypt job tokens at rest with `cipher`
+  #[cfg(feature = "encrypted-tokens")]
+  pub(crate) fn with_cipher(mut self, cipher: Option<TokenCipher>) -> Self {
+    self.cipher = cipher;
+    self
+  }
+
+  /// The value stored in the `token` column for `token`
+  fn seal(&self, token: &str) -> Result<String, EmailQueueError> {
+    #[cfg(feature = "encrypted-tokens")]
+    if let Some(cipher) = &self.cipher {
+      if !token.is_empty() {
+        return cipher.encrypt(token).map_err(storage_error);
+      }
+    }
+
+    Ok(token.to_string())
+  }
+
+  /// Recover the token from its stored value
+  fn open(&self, stored: String) -> Result<String, String> {
+    #[cfg(feature = "encrypted-tokens")]
+    if let Some(cipher) = &self.cipher {
+      if !stored.is_empty() {
+        return cipher.decrypt(&stored).map_err(|e| e.to_string());
+      }
+    }
+
+    Ok(stored)
+  }
+
+  /// Persist a job as `pending`
+  pub(crate) async fn insert(&self, job: &EmailJob) -> Result<(), EmailQueueError> {
+    let now = unix_now();
+    let template_id = (job.job_type == EmailJobType::Custom).then(|| job.template_id.clone());
+    let data = serde_json::to_string(&job.data).map_err(storage_error)?;
+
+    let row = DbEmailJob {
+      id: self.token_generator.generate_id(),
+      job_type: job.job_type.as_str().to_string(),
+      template_id,
+      recipient: job.recipient.clone(),
+      token: self.seal(&job.token)?,
+      token_expires_at: job.token_expires_at,
+      user_id: job.user_id.clone(),
+      data,
+      status: "pending".to_string(),
+      attempts: job.attempts as i64,
+      max_attempts: job.max_attempts as i64,
+      last_error: None,
+      created_at: job.created_at,
+      updated_at: now,
+    };
+
+    self.db.insert_email_job(&row).await.map_err(storage_error)
+  }
+
+  /// Claim the oldest pending job, if any
+  ///
+  /// Jobs whose token or data can't be read are marked `failed` with the
+  /// reason in `last_error`, and the next job is claimed instead.
+  pub(crate) async fn claim_next(&self) -> Result<Option<EmailJob>, EmailQueueError> {
+    loop {
+      let Some(row) = self
+        .db
+        .claim_next_email_job(unix_now())
+        .await
+        .map_err(storage_error)?
+      else {
+        return Ok(None);
+      };
+
+      let id = row.id.clone();
+      match self.decode(row) {
+        Ok(job) => return Ok(Some(job)),
+        Err(e) => self.fail_unreadable(&id, &e).await,
+      }
+    }
+  }
+
+  /// Rebuild a job from its row
+  fn decode(&self, row: DbEmailJob) -> Result<EmailJob, String> {
+    let job_type = match row.job_type.as_str() {
+      "email_verification" => EmailJobType::EmailVerification,
+      "password_reset" => EmailJobType::PasswordReset,
+      "magic_link" => EmailJobType::MagicLink,
+      "welcome" => EmailJobType::Welcome,
+      _ => EmailJobType::Custom,
+    };
+    let data = serde_json::from_str(&row.data).map_err(|e| format!("Invalid job data: {}", e))?;
+    let token = self
+      .open(row.token)
+      .map_err(|e| format!("Invalid job token: {}", e))?;
+
+    let mut job = EmailJob::new(
+      job_type,
+      row.recipient,
+      token,
+      row.token_expires_at,
+      row.user_id,
+    );
+    job.template_id = row.template_id.unwrap_or_default();
+    job.data = data;
+    job.attempts = row.attempts as u32;
+    job.max_attempts = row.max_attempts as u32;
+    job.created_at = row.created_at;
+    job.store_id = Some(row.id);
+
+    Ok(job)
+  }
+
+  /// Mark a claimed job that can't be decoded as `failed`
+  async fn fail_unreadable(&self, id: &str, error: &str) {
+    log::error!("Failing unreadable email job {}: {}", id, error);
+    let now = unix_now();
+    let result = async {
+      self
+        .db
+        .record_email_job_attempt(id, 0, Some(error), now)
+        .await?;
+      self.db.finish_email_job(id, "failed", now).await
+    }
+    .await;
+    if let Err(e) = result {
+      log::error!("Failed to mark email job {} as failed: {}", id, e);
+    }
+  }
+
+  /// Record that `job` has been attempted `job.attempts` times
+  pub(crate) async fn record_attempt(&self, job: &EmailJob, error: Option<&str>) {
+    let Some(id) = &job.store_id else {
+      return;
+    };
+    if let Err(e) = self
+      .db
+      .record_email_job_attempt(id, job.attempts as i64, error, unix_now())
+      .await
+    {
+      log::error!("Failed to record email job attempt {}: {}", id, e);
+    }
+  }
+
+  /// Mark `job` as finished, `sent` or `failed`
+  pub(crate) async fn finish(&self, job: &EmailJob, sent: bool) {
+    let Some(id) = &job.store_id else {
+      return;
+    };
+    let status = if sent { "sent" } else { "failed" };
+    if let Err(e) = self.db.finish_email_job(id, status, unix_now()).await {
+      log::error!("Failed to mark email job {} as {}: {}", id, status, e);
+    }
+  }
+
+  /// Put jobs claimed longer than `timeout` ago back in the queue
+  pub(crate) async fn requeue_stale(
+    &self,
+    timeout: std::time::Duration,
+  ) -> Result<u64, EmailQueueError> {
+    let now = unix_now();
+    self
+      .db
+      .requeue_stale_email_jobs(now - timeout.as_secs() as i64, now)
+      .await
+      .map_err(storage_error)
+  }
+
+  /// Delete finished jobs older than `retention`
+  pub(crate) async fn prune_finished(
+    &self,
+    retention: std::time::Duration,
+  ) -> Result<u64, EmailQueueError> {
+    self
+      .db
+      .delete_finished_email_jobs(unix_now() - retention.as_secs() as i64)
+      .await
+      .map_err(storage_error)
+  }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> EmailQueueError {
+  EmailQueueError::StorageError(e.to_string())
+}
+
+fn unix_now() -> i64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64
+}
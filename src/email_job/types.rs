@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum EmailJobType {
@@ -6,6 +7,9 @@ pub enum EmailJobType {
   PasswordReset,
   MagicLink,
   Welcome,
+  /// Application-defined transactional email (order confirmations, etc.);
+  /// the template is in `EmailJob::template_id`
+  Custom,
 }
 
 impl EmailJobType {
@@ -15,6 +19,7 @@ impl EmailJobType {
       Self::PasswordReset => "password_reset",
       Self::MagicLink => "magic_link",
       Self::Welcome => "welcome",
+      Self::Custom => "custom",
     }
   }
 }
@@ -25,9 +30,20 @@ pub struct EmailJob {
   pub token: String,
   pub token_expires_at: i64,
   pub user_id: String,
+  /// Template for `EmailJobType::Custom` jobs (empty for auth emails)
+  pub template_id: String,
+  /// Template data for `EmailJobType::Custom` jobs (empty for auth emails)
+  pub data: HashMap<String, String>,
   pub attempts: u32,
   pub max_attempts: u32,
   pub created_at: i64,
+  /// Trace context of the code that created the job, so the worker's send
+  /// span joins the same trace
+  #[cfg(feature = "otel")]
+  pub(crate) otel_cx: opentelemetry::Context,
+  /// Row id in `email_jobs` when the job is persisted
+  #[cfg(feature = "persistent-queue")]
+  pub(crate) store_id: Option<String>,
 }
 
 impl EmailJob {
@@ -48,9 +64,15 @@ impl EmailJob {
       token,
       token_expires_at,
       user_id,
+      template_id: String::new(),
+      data: HashMap::new(),
       attempts: 0,
       max_attempts: 2,
       created_at,
+      #[cfg(feature = "otel")]
+      otel_cx: opentelemetry::Context::current(),
+      #[cfg(feature = "persistent-queue")]
+      store_id: None,
     }
   }
   pub fn verification(
@@ -67,4 +89,20 @@ impl EmailJob {
       user_id,
     )
   }
+  /// Create a job for an application-defined transactional email
+  ///
+  /// Custom jobs carry no token or user; the worker hands the template id and
+  /// data to `EmailSender::send_custom_email`.
+  pub fn custom(recipient: String, template_id: String, data: HashMap<String, String>) -> Self {
+    let mut job = Self::new(
+      EmailJobType::Custom,
+      recipient,
+      String::new(),
+      0,
+      String::new(),
+    );
+    job.template_id = template_id;
+    job.data = data;
+    job
+  }
 }
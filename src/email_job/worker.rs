@@ -1,14 +1,31 @@
 use super::config::EmailWorkerConfig;
+#[cfg(feature = "persistent-queue")]
+use super::store::JobStore;
 use super::types::{EmailJob, EmailJobType};
-use crate::email::{EmailContext, EmailSender};
+use crate::email::{CustomEmailContext, EmailContext, EmailSender};
+use crate::metrics::MetricsRecorder;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How often a persistent worker deletes jobs past `finished_job_retention`
+#[cfg(feature = "persistent-queue")]
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 pub struct EmailWorker {
   receiver: mpsc::Receiver<EmailJob>,
   email_sender: Arc<Box<dyn EmailSender>>,
   config: EmailWorkerConfig,
+  /// Earliest time the next send may start when `max_send_rate` is set
+  next_send_at: Option<Instant>,
+  /// Records `email_send` timings and outcomes, one per attempt
+  metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+  #[cfg(feature = "otel")]
+  telemetry: crate::telemetry::Telemetry,
+  /// Where jobs are claimed from when the queue is persistent
+  #[cfg(feature = "persistent-queue")]
+  store: Option<JobStore>,
 }
 
 impl EmailWorker {
@@ -21,18 +38,108 @@ impl EmailWorker {
       receiver,
       email_sender,
       config,
+      next_send_at: None,
+      metrics_recorder: None,
+      #[cfg(feature = "otel")]
+      telemetry: Default::default(),
+      #[cfg(feature = "persistent-queue")]
+      store: None,
     }
   }
+  /// Claim jobs from `store` instead of taking them off the channel
+  #[cfg(feature = "persistent-queue")]
+  pub(crate) fn with_store(mut self, store: JobStore) -> Self {
+    self.store = Some(store);
+    self
+  }
+  /// Report send attempts to the `Auth` instance's metrics recorder
+  pub(crate) fn with_metrics_recorder(
+    mut self,
+    recorder: Option<Arc<dyn MetricsRecorder>>,
+  ) -> Self {
+    self.metrics_recorder = recorder;
+    self
+  }
+  /// Trace sends with the `Auth` instance's tracer
+  #[cfg(feature = "otel")]
+  pub(crate) fn with_telemetry(mut self, telemetry: crate::telemetry::Telemetry) -> Self {
+    self.telemetry = telemetry;
+    self
+  }
   pub async fn run(mut self) {
     log::info!("Email worker started");
 
+    #[cfg(feature = "persistent-queue")]
+    if let Some(store) = self.store.clone() {
+      self.run_persistent(store).await;
+      return;
+    }
+
     while let Some(job) = self.receiver.recv().await {
       self.process_job(job).await;
     }
 
     log::info!("Email worker stopped (channel closed)");
   }
-  async fn process_job(&self, mut job: EmailJob) {
+  /// Send persisted jobs until the channel closes
+  ///
+  /// Jobs are claimed from the store; channel messages only wake the worker
+  /// early. Jobs left pending or stuck mid-send by a previous process are
+  /// picked up on start, and everything pending is drained before stopping.
+  #[cfg(feature = "persistent-queue")]
+  async fn run_persistent(&mut self, store: JobStore) {
+    let mut open = true;
+    let mut next_prune_at = Instant::now();
+    loop {
+      if Instant::now() >= next_prune_at {
+        match store
+          .prune_finished(self.config.finished_job_retention)
+          .await
+        {
+          Ok(0) => {}
+          Ok(pruned) => log::info!("Deleted {} finished email jobs", pruned),
+          Err(e) => log::error!("Failed to delete finished email jobs: {}", e),
+        }
+        next_prune_at = Instant::now() + PRUNE_INTERVAL;
+      }
+
+      match store.requeue_stale(self.config.stale_claim_timeout).await {
+        Ok(0) => {}
+        Ok(requeued) => log::warn!(
+          "Requeued {} email jobs left claimed by a stopped worker",
+          requeued
+        ),
+        Err(e) => log::error!("Failed to requeue stale email jobs: {}", e),
+      }
+
+      loop {
+        match store.claim_next().await {
+          Ok(Some(job)) => self.process_job(job).await,
+          Ok(None) => break,
+          Err(e) => {
+            log::error!("Failed to claim email job: {}", e);
+            break;
+          }
+        }
+      }
+
+      if !open {
+        break;
+      }
+
+      tokio::select! {
+        message = self.receiver.recv() => {
+          open = message.is_some();
+          // One drain covers every queued wakeup
+          while self.receiver.try_recv().is_ok() {}
+        }
+        _ = tokio::time::sleep(self.config.poll_interval) => {}
+      }
+    }
+
+    log::info!("Email worker stopped (channel closed)");
+  }
+  async fn process_job(&mut self, mut job: EmailJob) {
     log::debug!(
       "Processing email job: type={}, recipient={}, user_id={}",
       job.job_type.as_str(),
@@ -40,10 +147,38 @@ impl EmailWorker {
       job.user_id
     );
 
+    #[cfg(feature = "persistent-queue")]
+    if job.attempts >= job.max_attempts {
+      // Out of attempts before a restart interrupted it
+      if let Some(store) = &self.store {
+        store.finish(&job, false).await;
+      }
+      return;
+    }
+
     loop {
       job.attempts += 1;
 
-      match self.send_email(&job).await {
+      // Count the attempt before sending, so a crash mid-send still uses it up
+      #[cfg(feature = "persistent-queue")]
+      if let Some(store) = &self.store {
+        store.record_attempt(&job, None).await;
+      }
+
+      self.pace().await;
+      let sent = crate::metrics::instrument(
+        self.metrics_recorder.as_ref(),
+        "email_send",
+        self.send_email(&job),
+      );
+      #[cfg(feature = "otel")]
+      let sent = self.telemetry.in_span(
+        "authkit.email.send",
+        &job.otel_cx,
+        send_attributes(&job),
+        sent,
+      );
+      match sent.await {
         Ok(()) => {
           log::info!(
             "Email sent successfully: type={}, recipient={}, attempts={}",
@@ -51,6 +186,10 @@ impl EmailWorker {
             job.recipient,
             job.attempts
           );
+          #[cfg(feature = "persistent-queue")]
+          if let Some(store) = &self.store {
+            store.finish(&job, true).await;
+          }
           return;
         }
         Err(e) => {
@@ -71,9 +210,19 @@ impl EmailWorker {
               job.recipient,
               job.user_id
             );
+            #[cfg(feature = "persistent-queue")]
+            if let Some(store) = &self.store {
+              store.record_attempt(&job, Some(&e.to_string())).await;
+              store.finish(&job, false).await;
+            }
             return;
           }
 
+          #[cfg(feature = "persistent-queue")]
+          if let Some(store) = &self.store {
+            store.record_attempt(&job, Some(&e.to_string())).await;
+          }
+
           // Exponential backoff with jitter
           let delay = self.calculate_backoff(job.attempts);
           log::debug!("Retrying in {:?}...", delay);
@@ -83,7 +232,29 @@ impl EmailWorker {
     }
   }
 
+  /// Wait until the configured send rate allows another send
+  async fn pace(&mut self) {
+    let Some(rate) = self.config.max_send_rate else {
+      return;
+    };
+    let interval = Duration::from_secs(1) / rate.max(1);
+
+    let now = Instant::now();
+    let send_at = self.next_send_at.map_or(now, |next| next.max(now));
+    tokio::time::sleep_until(send_at).await;
+    self.next_send_at = Some(send_at + interval);
+  }
+
   async fn send_email(&self, job: &EmailJob) -> Result<(), crate::error::AuthError> {
+    if job.job_type == EmailJobType::Custom {
+      let context = CustomEmailContext {
+        email: job.recipient.clone(),
+        template_id: job.template_id.clone(),
+        data: job.data.clone(),
+      };
+      return self.email_sender.send_custom_email(context).await;
+    }
+
     let context = EmailContext {
       email: job.recipient.clone(),
       token: job.token.clone(),
@@ -94,27 +265,33 @@ impl EmailWorker {
       EmailJobType::EmailVerification => self.email_sender.send_verification_email(context).await,
       EmailJobType::PasswordReset => self.email_sender.send_verification_email(context).await,
       EmailJobType::MagicLink => self.email_sender.send_verification_email(context).await,
-      EmailJobType::Welcome => Ok(()),
+      EmailJobType::Welcome | EmailJobType::Custom => Ok(()),
     }
   }
 
-  fn calculate_backoff(&self, attempt: u32) -> Duration {
-    let base = self.config.base_retry_delay.as_millis() as u64;
-    let max = self.config.max_retry_delay.as_millis() as u64;
-
-    let exponential = base.saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)));
-
-    let clamped = exponential.min(max);
+  /// Delay before retrying after `attempt` failed attempts
+  ///
+  /// Follows the config's `retry_policy`: doubles from `base_retry_delay`
+  /// with `retry_jitter` (10% by default), never exceeding `max_retry_delay`.
+  pub(crate) fn calculate_backoff(&self, attempt: u32) -> Duration {
+    self.config.retry_policy().delay(attempt)
+  }
+}
 
-    let jitter_range = clamped / 10;
-    let jitter = if jitter_range > 0 {
-      use rand::Rng;
-      let mut rng = rand::rng();
-      rng.random_range(0..jitter_range * 2) as i64 - jitter_range as i64
-    } else {
-      0
-    };
+/// Span attributes describing an email job
+#[cfg(feature = "otel")]
+fn send_attributes(job: &EmailJob) -> Vec<opentelemetry::KeyValue> {
+  use opentelemetry::KeyValue;
 
-    Duration::from_millis((clamped as i64 + jitter).max(0) as u64)
+  let mut attributes = vec![
+    KeyValue::new("authkit.email.type", job.job_type.as_str()),
+    KeyValue::new("authkit.email.attempt", i64::from(job.attempts)),
+  ];
+  if !job.user_id.is_empty() {
+    attributes.push(KeyValue::new(
+      "authkit.user_id_hash",
+      crate::telemetry::hash_user_id(&job.user_id),
+    ));
   }
+  attributes
 }
@@ -17,17 +17,18 @@ pub enum AuthError {
   #[error("Session not found or expired")]
   InvalidSession,
 
+  #[error("Session expired due to inactivity")]
+  SessionExpired,
+
   #[error("Password validation failed: {0}")]
   WeakPassword(String),
 
   #[error("Invalid email format")]
   InvalidEmailFormat,
 
-  #[error("Missing required configuration: Database")]
-  MissingDatabase,
-
-  #[error("Missing required configuration: Password strategy")]
-  MissingPasswordStrategy,
+  /// Invalid or incomplete configuration, reported by `AuthBuilder::build`
+  #[error("Configuration error: {0}")]
+  ConfigError(String),
 
   #[error("Password hasing error: {0}")]
   PasswordHashingError(String),
@@ -56,8 +57,122 @@ pub enum AuthError {
   #[error("Rate limit exceeded: {0}")]
   RateLimitExceeded(String),
 
+  /// An email was sent to the address too recently; retry after
+  /// `seconds_remaining`
+  #[error("Please wait {seconds_remaining} seconds before requesting another email")]
+  Cooldown { seconds_remaining: u64 },
+
+  /// Login needs a verified email; carries the address, e.g. to offer
+  /// `Auth::resend_email_verification`
   #[error("Email Not verified: {0}")]
   EmailNotVerified(String),
+
+  #[error("Email not found: {0}")]
+  EmailNotFound(String),
+
+  #[error("Invalid phone number format")]
+  InvalidPhoneFormat,
+
+  #[error("Phone Already Verified: {0}")]
+  PhoneAlreadyVerified(String),
+
+  #[error("Invalid input: {0}")]
+  InvalidInput(String),
+}
+
+impl AuthError {
+  /// Config error for a builder without a database
+  pub fn missing_database() -> Self {
+    AuthError::ConfigError("a database is required; call AuthBuilder::database(...)".to_string())
+  }
+
+  /// Config error for a builder without a password strategy
+  pub fn missing_password_strategy() -> Self {
+    AuthError::ConfigError(
+      "a password strategy is required; call AuthBuilder::password_strategy(...)".to_string(),
+    )
+  }
+
+  /// Stable, machine-readable error code (e.g. `"invalid_credentials"`)
+  ///
+  /// Unlike the `Display` message, codes are part of the public API and won't
+  /// change between releases, so clients can match on them.
+  pub fn code(&self) -> &'static str {
+    match self {
+      AuthError::DatabaseError(_) => "database_error",
+      AuthError::UserAlreadyExists(_) => "user_already_exists",
+      AuthError::UserNotFound => "user_not_found",
+      AuthError::InvalidCredentials => "invalid_credentials",
+      AuthError::InvalidSession => "invalid_session",
+      AuthError::SessionExpired => "session_expired",
+      AuthError::WeakPassword(_) => "weak_password",
+      AuthError::InvalidEmailFormat => "invalid_email_format",
+      AuthError::ConfigError(_) => "config_error",
+      AuthError::PasswordHashingError(_) => "password_hashing_error",
+      AuthError::TokenGenerationError(_) => "token_generation_error",
+      AuthError::InternalError(_) => "internal_error",
+      AuthError::InvalidToken(_) => "invalid_token",
+      AuthError::TokenAlreadyUsed(_) => "token_already_used",
+      AuthError::EmailAlreadyVerified(_) => "email_already_verified",
+      AuthError::TokenExpired(_) => "token_expired",
+      AuthError::EmailSendFailed(_) => "email_send_failed",
+      AuthError::RateLimitExceeded(_) => "rate_limit_exceeded",
+      AuthError::Cooldown { .. } => "cooldown",
+      AuthError::EmailNotVerified(_) => "email_not_verified",
+      AuthError::EmailNotFound(_) => "email_not_found",
+      AuthError::InvalidPhoneFormat => "invalid_phone_format",
+      AuthError::PhoneAlreadyVerified(_) => "phone_already_verified",
+      AuthError::InvalidInput(_) => "invalid_input",
+    }
+  }
+
+  /// Suggested HTTP status code for this error
+  ///
+  /// A hint for framework integrations; applications are free to map errors
+  /// differently (e.g. to avoid revealing whether a user exists).
+  pub fn http_status_hint(&self) -> u16 {
+    match self {
+      AuthError::WeakPassword(_)
+      | AuthError::InvalidEmailFormat
+      | AuthError::InvalidToken(_)
+      | AuthError::TokenAlreadyUsed(_)
+      | AuthError::TokenExpired(_)
+      | AuthError::InvalidPhoneFormat
+      | AuthError::InvalidInput(_) => 400,
+      AuthError::InvalidCredentials | AuthError::InvalidSession | AuthError::SessionExpired => 401,
+      AuthError::EmailNotVerified(_) => 403,
+      AuthError::UserNotFound | AuthError::EmailNotFound(_) => 404,
+      AuthError::UserAlreadyExists(_)
+      | AuthError::EmailAlreadyVerified(_)
+      | AuthError::PhoneAlreadyVerified(_) => 409,
+      AuthError::RateLimitExceeded(_) | AuthError::Cooldown { .. } => 429,
+      AuthError::DatabaseError(_)
+      | AuthError::ConfigError(_)
+      | AuthError::PasswordHashingError(_)
+      | AuthError::TokenGenerationError(_)
+      | AuthError::InternalError(_)
+      | AuthError::EmailSendFailed(_) => 500,
+    }
+  }
+
+  /// Whether the error is a transient database failure worth retrying
+  ///
+  /// True for I/O errors (e.g. a reset connection) and pool acquire
+  /// timeouts; constraint violations and other query errors are not.
+  pub fn is_transient(&self) -> bool {
+    matches!(
+      self,
+      AuthError::DatabaseError(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)
+    )
+  }
+
+  /// Whether this is a database unique-constraint violation
+  pub fn is_unique_violation(&self) -> bool {
+    matches!(
+      self,
+      AuthError::DatabaseError(sqlx::Error::Database(e)) if e.is_unique_violation()
+    )
+  }
 }
 
 pub type Result<T> = std::result::Result<T, AuthError>;
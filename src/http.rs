@@ -0,0 +1,245 @@
+//! Framework-agnostic HTTP helpers
+//!
+//! Only available with the `http` feature.
+
+use crate::error::AuthError;
+use serde::{Deserialize, Serialize};
+
+/// Canonical JSON error body for AuthKit errors
+///
+/// Serializes as `{"code": "...", "message": "..."}`, plus
+/// `"correlation_id"` when one is set. Pair it with
+/// [`AuthError::http_status_hint`] for the response status.
+///
+/// Messages of server-side errors (status 500) are replaced with a generic
+/// text so database or hashing details don't leak to clients; log the
+/// original error instead.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::http::ErrorBody;
+///
+/// match auth.login(request).await {
+///     Ok(session) => Ok(Json(session)),
+///     Err(e) => {
+///         let status = Status::from_code(e.http_status_hint()).unwrap();
+///         let body = ErrorBody::from_auth_error(&e).with_correlation_id(request_id);
+///         Err(Custom(status, Json(body)))
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+  /// Stable error code from [`AuthError::code`]
+  pub code: String,
+  /// Human-readable message, safe to show to clients
+  pub message: String,
+  /// Request correlation id, for matching client reports to server logs
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub correlation_id: Option<String>,
+}
+
+impl ErrorBody {
+  /// Build the canonical body for an error
+  pub fn from_auth_error(err: &AuthError) -> Self {
+    let message = if err.http_status_hint() >= 500 {
+      "Internal server error".to_string()
+    } else {
+      err.to_string()
+    };
+
+    Self {
+      code: err.code().to_string(),
+      message,
+      correlation_id: None,
+    }
+  }
+
+  /// Attach a request correlation id
+  pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+    self.correlation_id = Some(correlation_id.into());
+    self
+  }
+}
+
+impl From<&AuthError> for ErrorBody {
+  fn from(err: &AuthError) -> Self {
+    Self::from_auth_error(err)
+  }
+}
+
+/// Form field name used by [`csrf_hidden_input`]
+pub const CSRF_FORM_FIELD: &str = "csrf_token";
+
+/// Conventional request header for submitting the CSRF token from scripts
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// `SameSite` attribute of a cookie
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SameSite {
+  Strict,
+  /// Sent on top-level navigations from other sites (default)
+  #[default]
+  Lax,
+  /// Sent on all cross-site requests; browsers require `Secure` with it
+  None,
+}
+
+impl SameSite {
+  fn as_str(self) -> &'static str {
+    match self {
+      SameSite::Strict => "Strict",
+      SameSite::Lax => "Lax",
+      SameSite::None => "None",
+    }
+  }
+}
+
+/// Cookie settings for carrying a session token (or CSRF token)
+///
+/// Builds `Set-Cookie` header values and reads the value back from a
+/// `Cookie` request header. The defaults (`HttpOnly`, `Secure`,
+/// `SameSite=Lax`, path `/`) suit session cookies; [`SessionCookie::csrf`]
+/// gives the script-readable variant for double-submit CSRF tokens.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::http::SessionCookie;
+///
+/// let cookie = SessionCookie::default().with_domain("example.com");
+/// let session = auth.login(request).await?;
+/// response.add_header("Set-Cookie", cookie.set_cookie_for(&session));
+///
+/// // Later, on an incoming request
+/// let token = cookie.value_from(request.header("Cookie").unwrap_or(""));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCookie {
+  pub name: String,
+  pub path: String,
+  pub domain: Option<String>,
+  pub secure: bool,
+  pub http_only: bool,
+  pub same_site: SameSite,
+}
+
+impl Default for SessionCookie {
+  fn default() -> Self {
+    Self {
+      name: "authkit_session".to_string(),
+      path: "/".to_string(),
+      domain: None,
+      secure: true,
+      http_only: true,
+      same_site: SameSite::Lax,
+    }
+  }
+}
+
+impl SessionCookie {
+  /// Default settings under a different cookie name
+  pub fn new(name: impl Into<String>) -> Self {
+    Self {
+      name: name.into(),
+      ..Self::default()
+    }
+  }
+
+  /// Settings for the CSRF cookie of the double-submit pattern
+  ///
+  /// Not `HttpOnly`, so page scripts can copy the token into the
+  /// [`CSRF_HEADER`] header.
+  pub fn csrf() -> Self {
+    Self {
+      name: "authkit_csrf".to_string(),
+      http_only: false,
+      ..Self::default()
+    }
+  }
+
+  pub fn with_path(mut self, path: impl Into<String>) -> Self {
+    self.path = path.into();
+    self
+  }
+
+  pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+    self.domain = Some(domain.into());
+    self
+  }
+
+  /// Allow the cookie over plain HTTP (local development only)
+  pub fn with_secure(mut self, secure: bool) -> Self {
+    self.secure = secure;
+    self
+  }
+
+  pub fn with_http_only(mut self, http_only: bool) -> Self {
+    self.http_only = http_only;
+    self
+  }
+
+  pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+    self.same_site = same_site;
+    self
+  }
+
+  /// `Set-Cookie` header value storing `value` for `max_age_secs` seconds
+  pub fn set_cookie(&self, value: &str, max_age_secs: i64) -> String {
+    let mut cookie = format!(
+      "{}={}; Path={}; Max-Age={}",
+      self.name,
+      value,
+      self.path,
+      max_age_secs.max(0)
+    );
+    if let Some(domain) = &self.domain {
+      cookie.push_str("; Domain=");
+      cookie.push_str(domain);
+    }
+    if self.secure {
+      cookie.push_str("; Secure");
+    }
+    if self.http_only {
+      cookie.push_str("; HttpOnly");
+    }
+    cookie.push_str("; SameSite=");
+    cookie.push_str(self.same_site.as_str());
+    cookie
+  }
+
+  /// `Set-Cookie` header value for a session, expiring with it
+  pub fn set_cookie_for(&self, session: &crate::types::Session) -> String {
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    self.set_cookie(&session.token, session.expires_at - now)
+  }
+
+  /// `Set-Cookie` header value that removes the cookie, e.g. on logout
+  pub fn clear_cookie(&self) -> String {
+    self.set_cookie("", 0)
+  }
+
+  /// Find this cookie's value in a `Cookie` request header
+  pub fn value_from<'a>(&self, cookie_header: &'a str) -> Option<&'a str> {
+    cookie_header
+      .split(';')
+      .filter_map(|pair| pair.trim().split_once('='))
+      .find(|(name, _)| *name == self.name)
+      .map(|(_, value)| value)
+  }
+}
+
+/// Hidden form field carrying a CSRF token from [`Auth::csrf_token`](crate::Auth::csrf_token)
+///
+/// CSRF tokens are hex, so the value needs no HTML escaping.
+pub fn csrf_hidden_input(csrf_token: &str) -> String {
+  format!(
+    r#"<input type="hidden" name="{}" value="{}">"#,
+    CSRF_FORM_FIELD, csrf_token
+  )
+}
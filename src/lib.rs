@@ -5,24 +5,60 @@ mod email;
 #[cfg(feature = "email-queue")]
 mod email_job;
 mod error;
+mod metrics;
 mod operations;
+mod rate_limit;
+mod retry;
 mod security;
+#[cfg(feature = "sms")]
+mod sms;
 mod strategies;
+#[cfg(feature = "otel")]
+mod telemetry;
+mod token_store;
 mod types;
 mod validation;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "http")]
+pub mod http;
 pub mod prelude;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub use auth::Auth;
 pub use builder::AuthBuilder;
-pub use email::{EmailContext, EmailSender};
+pub use email::{CustomEmailContext, EmailContext, EmailSender};
 pub use error::{AuthError, Result};
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsCrateRecorder;
+pub use metrics::MetricsRecorder;
 pub use operations::{
-  Login, Logout, Register, ResendEmailVerification, SendEmailVerification, Verify, VerifyEmail,
+  AcceptInvitation, AddEmail, BatchVerificationResult, ChangePassword, ConsumeVerification,
+  Invitation, InviteUser, IssueVerification, Login, LoginRef, Logout, LogoutScope, Register,
+  RegisterOutcome, RegisterRef, RegisterWithHash, ResendEmailVerification, RevokeSession,
+  RotateSession, SendEmailVerification, SendStatus, SendVerificationOutcome, SessionPage,
+  SetPrimaryEmail, SourceVerification, TokenKind, TokenSource, VerificationOnRegister,
+  VerificationStatusReport, VerifiedIdentifier, VerifiedSession, Verify, VerifyEmail, VerifyRef,
+  VerifySecondaryEmail,
 };
-pub use types::{Account, Database, Session, User, VerificationToken};
+pub use rate_limit::{InMemoryRateLimitStore, RateLimitStore};
+pub use retry::RetryPolicy;
+pub use security::tokens::{OsTokenGenerator, TokenGenerator, TokenHashAlgo};
+pub use strategies::token::TokenType;
+pub use token_store::{StoredToken, TokenStore};
+pub use types::{
+  Account, Database, IdentifierKind, Session, SessionInfo, UnverifiedIdentifier, User, UserEmail,
+  VerificationToken,
+};
+pub use validation::email::EmailValidator;
+pub use validation::limits::InputLimits;
+pub use validation::password::PasswordPolicy;
+
+// SQLite exports (only available with sqlite feature)
+#[cfg(feature = "sqlite")]
+pub use database::sqlite::SqliteConfig;
 
 // Email queue exports (only available with email-queue feature)
 #[cfg(feature = "email-queue")]
@@ -30,3 +66,9 @@ pub use email_job::{
   EmailJob, EmailJobType, EmailQueue, EmailQueueError, EmailWorker, EmailWorkerConfig,
   EmailWorkerHandle,
 };
+
+// SMS exports (only available with sms feature)
+#[cfg(feature = "sms")]
+pub use operations::{SendPhoneVerification, VerifiedPhone, VerifyPhone};
+#[cfg(feature = "sms")]
+pub use sms::{SmsContext, SmsSender};
@@ -0,0 +1,155 @@
+use crate::error::Result;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Trait for recording operation metrics
+///
+/// Implement this to feed AuthKit timings and counters into Prometheus,
+/// StatsD, or any other metrics backend. Install it with
+/// `AuthBuilder::metrics_recorder`; when no recorder is set, operations are
+/// not timed at all.
+///
+/// Operations are named `register`, `login`, `verify`,
+/// `send_email_verification`, `send_email_verification_code`,
+/// `resend_email_verification`, `verify_email` and
+/// `verify_email_with_identifier`. After each one, `{op}_success` or
+/// `{op}_failure` is incremented. Each send attempt of the email worker is
+/// recorded the same way as `email_send`.
+///
+/// With the `metrics` feature, [`MetricsCrateRecorder`] reports all of these
+/// through the `metrics` crate.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::MetricsRecorder;
+/// use std::time::Duration;
+///
+/// struct PrometheusRecorder;
+///
+/// impl MetricsRecorder for PrometheusRecorder {
+///     fn record_duration(&self, op: &str, duration: Duration) {
+///         histogram!("authkit_operation_seconds", "op" => op.to_string())
+///             .record(duration.as_secs_f64());
+///     }
+///
+///     fn increment(&self, counter: &str) {
+///         counter!("authkit_events_total", "event" => counter.to_string()).increment(1);
+///     }
+/// }
+/// ```
+pub trait MetricsRecorder: Send + Sync {
+  /// Record how long an operation took, whether it succeeded or not
+  fn record_duration(&self, op: &str, duration: Duration);
+
+  /// Increment a named counter by one
+  fn increment(&self, counter: &str);
+}
+
+/// Operation names passed to [`MetricsRecorder`]
+#[cfg(feature = "metrics")]
+const OPERATIONS: &[&str] = &[
+  "register",
+  "login",
+  "verify",
+  "send_email_verification",
+  "send_email_verification_code",
+  "resend_email_verification",
+  "verify_email",
+  "verify_email_with_identifier",
+  "email_send",
+];
+
+#[cfg(feature = "metrics")]
+const DURATION_HISTOGRAM: &str = "authkit_operation_duration_seconds";
+
+/// [`MetricsRecorder`] backed by the `metrics` crate
+///
+/// Durations go to the `authkit_operation_duration_seconds` histogram,
+/// labelled with `op`, and each counter to `authkit_{counter}_total`, e.g.
+/// `authkit_login_success_total`. Install any `metrics` exporter, such as
+/// `metrics-exporter-prometheus`, to scrape them.
+///
+/// Requires the `metrics` feature.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// PrometheusBuilder::new().install()?;
+///
+/// let auth = Auth::builder()
+///     .database(Database::sqlite("auth.db").await?)
+///     .metrics_recorder(MetricsCrateRecorder::new())
+///     .build()?;
+/// ```
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy)]
+pub struct MetricsCrateRecorder;
+
+#[cfg(feature = "metrics")]
+impl MetricsCrateRecorder {
+  /// Create the recorder, describing its metrics to the installed exporter
+  ///
+  /// Install the exporter first, or the descriptions are lost.
+  pub fn new() -> Self {
+    ::metrics::describe_histogram!(
+      DURATION_HISTOGRAM,
+      ::metrics::Unit::Seconds,
+      "Duration of AuthKit operations and email sends"
+    );
+    for op in OPERATIONS {
+      for outcome in ["success", "failure"] {
+        ::metrics::describe_counter!(
+          counter_name(&format!("{}_{}", op, outcome)),
+          format!("Count of {} operations ending in {}", op, outcome)
+        );
+      }
+    }
+    Self
+  }
+}
+
+#[cfg(feature = "metrics")]
+impl Default for MetricsCrateRecorder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsCrateRecorder {
+  fn record_duration(&self, op: &str, duration: Duration) {
+    ::metrics::histogram!(DURATION_HISTOGRAM, "op" => op.to_string())
+      .record(duration.as_secs_f64());
+  }
+
+  fn increment(&self, counter: &str) {
+    ::metrics::counter!(counter_name(counter)).increment(1);
+  }
+}
+
+#[cfg(feature = "metrics")]
+fn counter_name(counter: &str) -> String {
+  format!("authkit_{}_total", counter)
+}
+
+/// Run an operation, recording its duration and outcome if a recorder is set
+pub(crate) async fn instrument<T>(
+  recorder: Option<&Arc<dyn MetricsRecorder>>,
+  op: &str,
+  operation: impl Future<Output = Result<T>>,
+) -> Result<T> {
+  let Some(recorder) = recorder else {
+    return operation.await;
+  };
+
+  let start = Instant::now();
+  let result = operation.await;
+  recorder.record_duration(op, start.elapsed());
+
+  let outcome = if result.is_ok() { "success" } else { "failure" };
+  recorder.increment(&format!("{}_{}", op, outcome));
+
+  result
+}
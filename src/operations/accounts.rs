@@ -0,0 +1,44 @@
+use crate::auth::Auth;
+use crate::error::Result;
+use crate::types::Account;
+use crate::validation::provider;
+
+/// List the authentication providers linked to a user
+///
+/// Returns an empty list for unknown user IDs.
+pub(crate) async fn list(auth: &Auth, user_id: &str) -> Result<Vec<Account>> {
+  let accounts = auth.inner.db.find_accounts_by_user_id(user_id).await?;
+
+  Ok(accounts.into_iter().map(Account::from).collect())
+}
+
+/// Link an account to a user
+///
+/// The provider and its account ID are validated before touching the
+/// database, and the provider is stored lowercased so `Google` and `google`
+/// can't become separate accounts.
+pub(crate) async fn create(
+  auth: &Auth,
+  user_id: &str,
+  provider: &str,
+  provider_account_id: &str,
+  password_hash: Option<&str>,
+  created_at: i64,
+) -> Result<()> {
+  let provider = provider::normalize(provider)?;
+  provider::validate_account_id(provider_account_id)?;
+
+  let id = auth.inner.token_generator.generate_id();
+  auth
+    .inner
+    .db
+    .create_account(
+      &id,
+      user_id,
+      &provider,
+      provider_account_id,
+      password_hash,
+      created_at,
+    )
+    .await
+}
@@ -0,0 +1,81 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+
+/// Change a user's password after confirming the current one
+///
+/// The new password must satisfy the configured `PasswordPolicy`. Unless
+/// disabled with `AuthBuilder::invalidate_sessions_on_password_change`, all
+/// of the user's sessions are deleted afterwards.
+#[derive(Debug, Clone)]
+pub struct ChangePassword {
+  pub user_id: String,
+  pub current_password: String,
+  pub new_password: String,
+}
+
+pub(crate) async fn execute(auth: &Auth, request: ChangePassword) -> Result<()> {
+  auth
+    .inner
+    .input_limits
+    .check_password(&request.current_password)?;
+  auth
+    .inner
+    .input_limits
+    .check_password(&request.new_password)?;
+
+  if !crate::operations::login::verify_password(auth, &request.user_id, &request.current_password)
+    .await?
+  {
+    return Err(AuthError::InvalidCredentials);
+  }
+
+  auth.inner.password_policy.check(&request.new_password)?;
+
+  let accounts = auth
+    .inner
+    .db
+    .find_accounts_by_user_id(&request.user_id)
+    .await?;
+  let account = accounts
+    .iter()
+    .find(|account| account.provider == auth.inner.credential_provider)
+    .ok_or(AuthError::InvalidCredentials)?;
+
+  let password_hash = auth
+    .inner
+    .password_strategy
+    .hash_password(&request.new_password)
+    .await?;
+
+  set_password_hash(auth, &request.user_id, &account.id, &password_hash).await
+}
+
+/// Store a new password hash on a credential account
+///
+/// Every operation that replaces a password goes through here, so that
+/// existing sessions are invalidated (when configured) no matter how the
+/// password changed.
+pub(crate) async fn set_password_hash(
+  auth: &Auth,
+  user_id: &str,
+  account_id: &str,
+  password_hash: &str,
+) -> Result<()> {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  auth
+    .inner
+    .db
+    .update_account_password_hash(account_id, password_hash, now)
+    .await?;
+
+  // A stolen session must not outlive the password it was created with
+  if auth.inner.invalidate_sessions_on_password_change {
+    auth.inner.db.delete_user_sessions(user_id).await?;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::auth::Auth;
+use crate::error::Result;
+
+/// What an opaque token turned out to be, from [`Auth::classify_token`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+  /// A session token
+  Session,
+  /// A verification token; holds its stored type (see `TokenType::as_str`),
+  /// e.g. `"email_verification"` or `"password_reset"`
+  Verification(String),
+  /// Not a token this instance issued
+  Unknown,
+}
+
+pub(crate) async fn execute(auth: &Auth, token: &str) -> Result<TokenKind> {
+  auth.inner.input_limits.check_token(token)?;
+
+  let db = auth.inner.db.as_ref().as_ref();
+
+  if auth
+    .inner
+    .session_strategy
+    .find_session(db, token)
+    .await?
+    .is_some()
+  {
+    return Ok(TokenKind::Session);
+  }
+
+  if let Some(token_type) = auth.inner.token_strategy.find_token_type(db, token).await? {
+    return Ok(TokenKind::Verification(token_type));
+  }
+
+  Ok(TokenKind::Unknown)
+}
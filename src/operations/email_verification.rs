@@ -1,6 +1,9 @@
 use crate::auth::Auth;
+use crate::database::models::DbVerificationSend;
 use crate::email::EmailContext;
 use crate::error::{AuthError, Result};
+#[cfg(feature = "encrypted-tokens")]
+use crate::strategies::token::Token;
 use crate::strategies::token::TokenType;
 use crate::types::{User, VerificationToken};
 
@@ -16,12 +19,55 @@ pub struct SendEmailVerification {
   pub user_id: String,
 }
 
+/// How a verification email was delivered, from
+/// [`Auth::send_email_verification_detailed`](crate::Auth::send_email_verification_detailed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendStatus {
+  /// Sent synchronously through the email sender
+  Sent,
+  /// Accepted by the background email queue; delivery happens later
+  Queued,
+  /// No email sender is configured; the application must deliver the token
+  TokenOnly,
+  /// The email sender failed; the token is valid and can be resent
+  Failed,
+}
+
+/// An issued verification token and how its email was delivered
+#[derive(Debug, Clone)]
+pub struct SendVerificationOutcome {
+  pub verification: VerificationToken,
+  pub status: SendStatus,
+}
+
+/// Per-user result of
+/// [`Auth::send_verification_emails`](crate::Auth::send_verification_emails)
+#[derive(Debug)]
+pub struct BatchVerificationResult {
+  pub user_id: String,
+  pub result: Result<SendVerificationOutcome>,
+}
+
 /// Request to verify an email using a token
 #[derive(Debug, Clone)]
 pub struct VerifyEmail {
   pub token: String,
 }
 
+/// Everything about a user's email verification, from
+/// [`Auth::verification_status`](crate::Auth::verification_status)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationStatusReport {
+  pub user_id: String,
+  pub email: String,
+  pub email_verified: bool,
+  pub email_verified_at: Option<i64>,
+  /// Whether an unused, unexpired verification token is outstanding
+  pub has_pending_token: bool,
+  /// Expiry of the newest outstanding token (Unix timestamp)
+  pub pending_token_expires_at: Option<i64>,
+}
+
 /// Request to resend email verification
 #[derive(Debug, Clone)]
 pub struct ResendEmailVerification {
@@ -41,6 +87,92 @@ pub struct ResendEmailVerification {
 pub(crate) async fn send_email_verification(
   auth: &Auth,
   request: SendEmailVerification,
+) -> Result<VerificationToken> {
+  let verification = issue_email_verification(auth, &request).await?;
+  deliver_verification(auth, &request.user_id, &verification).await?;
+
+  Ok(verification)
+}
+
+/// Like [`send_email_verification`], also reporting how the email went out
+///
+/// A failed synchronous send is logged and reported as `SendStatus::Failed`
+/// rather than returned as an error; the token is still valid.
+pub(crate) async fn send_email_verification_detailed(
+  auth: &Auth,
+  request: SendEmailVerification,
+) -> Result<SendVerificationOutcome> {
+  let verification = issue_email_verification(auth, &request).await?;
+  let status = delivery_status(auth, &request.user_id, &verification).await;
+
+  Ok(SendVerificationOutcome {
+    verification,
+    status,
+  })
+}
+
+/// Issue verification tokens for several users, then deliver the emails
+///
+/// Every token is issued before any email goes out, so a slow sender doesn't
+/// hold up token creation. Users that can't be sent a verification (unknown,
+/// already verified, in cooldown) get an error in their entry; the rest of
+/// the batch carries on.
+pub(crate) async fn send_verification_emails(
+  auth: &Auth,
+  user_ids: &[String],
+) -> Vec<BatchVerificationResult> {
+  let mut issued = Vec::with_capacity(user_ids.len());
+  for user_id in user_ids {
+    let request = SendEmailVerification {
+      user_id: user_id.clone(),
+    };
+    issued.push((user_id, issue_email_verification(auth, &request).await));
+  }
+
+  let mut results = Vec::with_capacity(issued.len());
+  for (user_id, verification) in issued {
+    let result = match verification {
+      Ok(verification) => {
+        let status = delivery_status(auth, user_id, &verification).await;
+        Ok(SendVerificationOutcome {
+          verification,
+          status,
+        })
+      }
+      Err(e) => Err(e),
+    };
+    results.push(BatchVerificationResult {
+      user_id: user_id.clone(),
+      result,
+    });
+  }
+
+  results
+}
+
+/// Deliver a verification email, logging a failed send as `SendStatus::Failed`
+async fn delivery_status(
+  auth: &Auth,
+  user_id: &str,
+  verification: &VerificationToken,
+) -> SendStatus {
+  match deliver_verification(auth, user_id, verification).await {
+    Ok(status) => status,
+    Err(e) => {
+      log::warn!(
+        "Failed to send verification email to {}: {}",
+        verification.identifier,
+        e
+      );
+      SendStatus::Failed
+    }
+  }
+}
+
+/// Check the user can be sent a verification email and issue its token
+async fn issue_email_verification(
+  auth: &Auth,
+  request: &SendEmailVerification,
 ) -> Result<VerificationToken> {
   // Find the user by ID with email verification status
   // Uses _with_verification method that queries email_verified columns
@@ -58,6 +190,8 @@ pub(crate) async fn send_email_verification(
     ));
   }
 
+  check_send_cooldown(auth, &user.email, TokenType::EmailVerification).await?;
+
   // Generate token (24 hours expiry)
   const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
   let token = auth
@@ -65,32 +199,43 @@ pub(crate) async fn send_email_verification(
     .token_strategy
     .generate_token(
       auth.inner.db.as_ref().as_ref(),
-      &request.user_id,
+      Some(&request.user_id),
       &user.email,
       TokenType::EmailVerification,
       TWENTY_FOUR_HOURS,
     )
     .await?;
+  record_send(auth, &user.id, &user.email, TokenType::EmailVerification).await?;
+
+  Ok(VerificationToken {
+    token: token.token,
+    identifier: user.email,
+    expires_at: token.expires_at,
+  })
+}
 
+/// Hand a verification email to the queue or the sender
+///
+/// Falls back to a synchronous send when the queue rejects the job. A
+/// synchronous send failure is returned as the sender's error.
+async fn deliver_verification(
+  auth: &Auth,
+  user_id: &str,
+  verification: &VerificationToken,
+) -> Result<SendStatus> {
   // Send verification email (queue or sync based on configuration)
   #[cfg(feature = "email-queue")]
   {
-    if let Some(queue) = &auth.inner.email_queue {
+    if let Some(queue) = auth.email_queue() {
       let job = EmailJob::verification(
-        user.email.clone(),
-        token.token.clone(),
-        token.expires_at,
-        user.id.clone(),
+        verification.identifier.clone(),
+        verification.token.clone(),
+        verification.expires_at,
+        user_id.to_string(),
       );
 
       match queue.enqueue(job).await {
-        Ok(()) => {
-          return Ok(VerificationToken {
-            token: token.token,
-            identifier: user.email,
-            expires_at: token.expires_at,
-          });
-        }
+        Ok(()) => return Ok(SendStatus::Queued),
         Err(e) => {
           log::warn!("Email queue error, sending synchronously: {}", e);
           // Fall through to sync send
@@ -98,23 +243,22 @@ pub(crate) async fn send_email_verification(
       }
     }
   }
+  #[cfg(not(feature = "email-queue"))]
+  let _ = user_id;
 
   // Synchronous send (fallback or when queue not enabled)
-  if let Some(email_sender) = &auth.inner.email_sender {
-    let context = EmailContext {
-      email: user.email.clone(),
-      token: token.token.clone(),
-      expires_at: token.expires_at,
-    };
+  let Some(email_sender) = &auth.inner.email_sender else {
+    return Ok(SendStatus::TokenOnly);
+  };
 
-    email_sender.send_verification_email(context).await?;
-  }
+  let context = EmailContext {
+    email: verification.identifier.clone(),
+    token: verification.token.clone(),
+    expires_at: verification.expires_at,
+  };
+  email_sender.send_verification_email(context).await?;
 
-  Ok(VerificationToken {
-    token: token.token,
-    identifier: user.email,
-    expires_at: token.expires_at,
-  })
+  Ok(SendStatus::Sent)
 }
 
 /// Execute email verification operation
@@ -122,19 +266,39 @@ pub(crate) async fn send_email_verification(
 /// This verifies the provided token and marks the user's email as verified
 /// if the token is valid, not expired, and not already used.
 ///
+/// Each failure maps to a distinct error so callers can pick an outcome
+/// (e.g. a redirect) without inspecting messages:
+/// - `InvalidToken` - the token doesn't exist (or has no associated user)
+/// - `TokenAlreadyUsed` - the token was already consumed
+/// - `TokenExpired` - the token is past its expiry
+/// - `EmailAlreadyVerified` - the token is valid but the email is already verified
+///
 /// **Requires:** email_verification feature columns in the database schema.
 /// Run `authkit migrate` with email_verification feature enabled.
 pub(crate) async fn verify_email(auth: &Auth, request: VerifyEmail) -> Result<User> {
+  let token = auth.inner.token_input(&request.token);
+  auth.inner.input_limits.check_token(token)?;
+
   // Verify the token
-  let verified_token = auth
+  let verified_token = match auth
     .inner
     .token_strategy
     .verify_token(
       auth.inner.db.as_ref().as_ref(),
-      &request.token,
+      token,
       TokenType::EmailVerification,
     )
-    .await?;
+    .await
+  {
+    Err(AuthError::TokenAlreadyUsed(message)) if auth.inner.idempotent_email_verify => {
+      // A repeated click on a link that already did its job
+      return match already_verified_user(auth, token).await? {
+        Some(user) => Ok(user),
+        None => Err(AuthError::TokenAlreadyUsed(message)),
+      };
+    }
+    result => result?,
+  };
 
   // Get the user ID from the token
   let user_id = verified_token
@@ -153,20 +317,112 @@ pub(crate) async fn verify_email(auth: &Auth, request: VerifyEmail) -> Result<Us
     .ok_or(AuthError::UserNotFound)?;
 
   // Check if already verified
+  if user.email_verified {
+    if auth.inner.idempotent_email_verify {
+      return Ok(user);
+    }
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  // Claim the token; of several concurrent verifies only one gets past this
+  auth
+    .inner
+    .token_strategy
+    .mark_token_as_used(auth.inner.db.as_ref().as_ref(), token)
+    .await?;
+
+  complete_verification(auth, user_id).await
+}
+
+/// Issue a one-time code for a verification link
+///
+/// The code verifies the user's email exactly once, within
+/// `verification_link_code_ttl`, through `exchange_verification_code`.
+pub(crate) async fn issue_verification_link_code(
+  auth: &Auth,
+  user_id: &str,
+) -> Result<VerificationToken> {
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  if user.email_verified {
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  let code = auth
+    .inner
+    .token_strategy
+    .generate_token(
+      auth.inner.db.as_ref().as_ref(),
+      Some(&user.id),
+      &user.email,
+      TokenType::VerificationLinkCode,
+      auth.inner.verification_link_code_ttl.as_secs() as i64,
+    )
+    .await?;
+
+  Ok(VerificationToken {
+    token: code.token,
+    identifier: code.identifier,
+    expires_at: code.expires_at,
+  })
+}
+
+/// Exchange a verification link code for a verified email
+///
+/// Fails like `verify_email`; a second exchange of the same code fails with
+/// `TokenAlreadyUsed`.
+pub(crate) async fn exchange_verification_code(auth: &Auth, code: &str) -> Result<User> {
+  let code = auth.inner.token_input(code);
+  auth.inner.input_limits.check_token(code)?;
+
+  let verified_code = auth
+    .inner
+    .token_strategy
+    .verify_token(
+      auth.inner.db.as_ref().as_ref(),
+      code,
+      TokenType::VerificationLinkCode,
+    )
+    .await?;
+
+  let user_id = verified_code.user_id.ok_or(AuthError::InvalidToken(
+    "Token does not have an associated user".to_string(),
+  ))?;
+
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
   if user.email_verified {
     return Err(AuthError::EmailAlreadyVerified(
       "Email is already verified".to_string(),
     ));
   }
 
-  // Mark token as used
+  // Claim the code; of several concurrent exchanges only one gets past this
   auth
     .inner
     .token_strategy
-    .mark_token_as_used(auth.inner.db.as_ref().as_ref(), &request.token)
+    .mark_token_as_used(auth.inner.db.as_ref().as_ref(), code)
     .await?;
 
-  // Update user's email_verified status
+  complete_verification(auth, &user_id).await
+}
+
+/// Mark a user's email verified once a token or link code was claimed
+async fn complete_verification(auth: &Auth, user_id: &str) -> Result<User> {
   let now = std::time::SystemTime::now()
     .duration_since(std::time::UNIX_EPOCH)
     .unwrap()
@@ -174,15 +430,50 @@ pub(crate) async fn verify_email(auth: &Auth, request: VerifyEmail) -> Result<Us
 
   auth.inner.db.update_email_verified(user_id, now).await?;
 
+  // Any other outstanding verification links are now pointless; drop them
+  for token_type in [
+    TokenType::EmailVerification,
+    TokenType::VerificationLinkCode,
+  ] {
+    auth
+      .inner
+      .db
+      .delete_tokens_for_user(user_id, token_type.as_str())
+      .await?;
+  }
+
   // Return updated user with verification status
-  let updated_user = auth
+  auth
     .inner
     .db
     .find_user_by_id_with_verification(user_id)
     .await?
-    .ok_or(AuthError::UserNotFound)?;
+    .ok_or(AuthError::UserNotFound)
+}
+
+/// The user a used verification token belongs to, if their email is verified
+async fn already_verified_user(auth: &Auth, token: &str) -> Result<Option<User>> {
+  let Some(user_id) = auth
+    .inner
+    .token_strategy
+    .find_token_user_id(
+      auth.inner.db.as_ref().as_ref(),
+      token,
+      TokenType::EmailVerification,
+    )
+    .await?
+  else {
+    return Ok(None);
+  };
 
-  Ok(updated_user)
+  Ok(
+    auth
+      .inner
+      .db
+      .find_user_by_id_with_verification(&user_id)
+      .await?
+      .filter(|user| user.email_verified),
+  )
 }
 
 /// Execute resend email verification operation
@@ -196,6 +487,8 @@ pub(crate) async fn resend_email_verification(
   auth: &Auth,
   request: ResendEmailVerification,
 ) -> Result<VerificationToken> {
+  auth.inner.input_limits.check_email(&request.email)?;
+
   // Find the user by email with email verification status
   let db_user = auth
     .inner
@@ -212,24 +505,44 @@ pub(crate) async fn resend_email_verification(
     ));
   }
 
-  // Generate new token (24 hours expiry)
+  check_send_cooldown(auth, &db_user.email, TokenType::EmailVerification).await?;
+  check_resend_limit(auth, &db_user.id, TokenType::EmailVerification).await?;
+
+  #[cfg(feature = "encrypted-tokens")]
+  let reused = reusable_token(auth, &db_user.id).await?;
+  #[cfg(not(feature = "encrypted-tokens"))]
+  let reused = None;
+
+  // Generate new token (24 hours expiry) unless a valid one can be resent
   const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
-  let token = auth
-    .inner
-    .token_strategy
-    .generate_token(
-      auth.inner.db.as_ref().as_ref(),
-      &db_user.id,
-      &db_user.email,
-      TokenType::EmailVerification,
-      TWENTY_FOUR_HOURS,
-    )
-    .await?;
+  let token = match reused {
+    Some(token) => token,
+    None => {
+      auth
+        .inner
+        .token_strategy
+        .generate_token(
+          auth.inner.db.as_ref().as_ref(),
+          Some(&db_user.id),
+          &db_user.email,
+          TokenType::EmailVerification,
+          TWENTY_FOUR_HOURS,
+        )
+        .await?
+    }
+  };
+  record_send(
+    auth,
+    &db_user.id,
+    &db_user.email,
+    TokenType::EmailVerification,
+  )
+  .await?;
 
   // Send verification email (queue or sync based on configuration)
   #[cfg(feature = "email-queue")]
   {
-    if let Some(queue) = &auth.inner.email_queue {
+    if let Some(queue) = auth.email_queue() {
       let job = EmailJob::verification(
         db_user.email.clone(),
         token.token.clone(),
@@ -270,3 +583,372 @@ pub(crate) async fn resend_email_verification(
     expires_at: token.expires_at,
   })
 }
+
+/// Find a still-valid verification token for the user that can be resent
+///
+/// Only applies when `reuse_valid_verification_tokens` is enabled and the
+/// stored token can be decrypted; hashed tokens can't be recovered.
+#[cfg(feature = "encrypted-tokens")]
+async fn reusable_token(auth: &Auth, user_id: &str) -> Result<Option<Token>> {
+  if !auth.inner.reuse_valid_verification_tokens {
+    return Ok(None);
+  }
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let Some(existing) = auth
+    .inner
+    .db
+    .find_valid_token_for_user(user_id, TokenType::EmailVerification.as_str(), now)
+    .await?
+  else {
+    return Ok(None);
+  };
+
+  let Ok(token) = auth
+    .inner
+    .token_strategy
+    .decrypt_stored_token(&existing.token_hash)
+  else {
+    return Ok(None);
+  };
+
+  Ok(Some(Token {
+    id: existing.id,
+    token,
+    token_hash: existing.token_hash,
+    user_id: existing.user_id,
+    identifier: existing.identifier,
+    token_type: TokenType::EmailVerification,
+    expires_at: existing.expires_at,
+    created_at: existing.created_at,
+  }))
+}
+
+/// Enforce the per-user cap on verification resends, if configured
+///
+/// Counts every `token_type` email logged for the user in the last 24 hours,
+/// including the first one and resends of a reused token, so `max` resends
+/// allow `max + 1` sends.
+pub(crate) async fn check_resend_limit(
+  auth: &Auth,
+  user_id: &str,
+  token_type: TokenType,
+) -> Result<()> {
+  let Some(max) = auth.inner.max_verification_resends else {
+    return Ok(());
+  };
+
+  const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let sent = auth
+    .inner
+    .db
+    .count_verification_sends_for_user(user_id, token_type.as_str(), now - TWENTY_FOUR_HOURS)
+    .await?;
+
+  if sent > u64::from(max) {
+    return Err(AuthError::RateLimitExceeded(
+      "Too many verification emails requested, please try again later".to_string(),
+    ));
+  }
+
+  Ok(())
+}
+
+/// Log a `token_type` email sent to `email`, for the cooldown and resend cap
+///
+/// Every send is logged, including resends of a reused token, so neither
+/// limit depends on how many tokens exist or which store holds them.
+pub(crate) async fn record_send(
+  auth: &Auth,
+  user_id: &str,
+  email: &str,
+  token_type: TokenType,
+) -> Result<()> {
+  const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  // Keep as much history as the longer of the two limits looks at
+  let cooldown = auth
+    .inner
+    .email_send_cooldown
+    .map_or(0, |cooldown| cooldown.as_secs() as i64);
+  let retention = TWENTY_FOUR_HOURS.max(cooldown);
+
+  auth
+    .inner
+    .db
+    .record_verification_send(
+      &DbVerificationSend {
+        id: auth.inner.token_generator.generate_id(),
+        user_id: user_id.to_string(),
+        identifier: email.to_string(),
+        token_type: token_type.as_str().to_string(),
+        sent_at: now,
+      },
+      now - retention,
+    )
+    .await
+}
+
+/// Enforce the per-address email send cooldown, if configured
+///
+/// Only earlier sends of `token_type` count. The last send time is taken
+/// from the send log, so the limit holds across `Auth` clones and processes
+/// sharing the database, and for resends of a reused token.
+pub(crate) async fn check_send_cooldown(
+  auth: &Auth,
+  email: &str,
+  token_type: TokenType,
+) -> Result<()> {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  check_send_cooldown_at(auth, email, token_type, now).await
+}
+
+/// Enforce the send cooldown as of `now` (Unix seconds)
+///
+/// Fails with `AuthError::Cooldown` carrying the seconds left until the
+/// last send plus the cooldown.
+pub(crate) async fn check_send_cooldown_at(
+  auth: &Auth,
+  email: &str,
+  token_type: TokenType,
+  now: i64,
+) -> Result<()> {
+  let Some(cooldown) = auth.inner.email_send_cooldown else {
+    return Ok(());
+  };
+
+  let Some(sent_at) = auth
+    .inner
+    .db
+    .find_latest_verification_send(email, token_type.as_str())
+    .await?
+  else {
+    return Ok(());
+  };
+
+  let available_at = sent_at + cooldown.as_secs() as i64;
+  if now < available_at {
+    return Err(AuthError::Cooldown {
+      seconds_remaining: (available_at - now) as u64,
+    });
+  }
+
+  Ok(())
+}
+
+/// Users who registered before `cutoff` and still haven't verified their email
+pub(crate) async fn find_unverified_users_registered_before(
+  auth: &Auth,
+  cutoff: i64,
+) -> Result<Vec<User>> {
+  auth
+    .inner
+    .db
+    .find_unverified_users_created_before(cutoff)
+    .await
+}
+
+/// A user's verification status and newest outstanding token, in one query
+pub(crate) async fn verification_status(
+  auth: &Auth,
+  user_id: &str,
+) -> Result<VerificationStatusReport> {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let status = auth
+    .inner
+    .db
+    .find_verification_status(user_id, TokenType::EmailVerification.as_str(), now)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  Ok(VerificationStatusReport {
+    user_id: status.user.id,
+    email: status.user.email,
+    email_verified: status.user.email_verified.unwrap_or(false),
+    email_verified_at: status.user.email_verified_at,
+    has_pending_token: status.pending_token_expires_at.is_some(),
+    pending_token_expires_at: status.pending_token_expires_at,
+  })
+}
+
+/// Find the user an email verification token was issued to
+///
+/// Read-only: the token isn't checked for expiry or use, nor consumed, so
+/// support can look up the owner of a link that no longer works.
+pub(crate) async fn user_for_verification_token(auth: &Auth, token: &str) -> Result<Option<User>> {
+  auth.inner.input_limits.check_token(token)?;
+
+  let Some(user_id) = auth
+    .inner
+    .token_strategy
+    .find_token_user_id(
+      auth.inner.db.as_ref().as_ref(),
+      token,
+      TokenType::EmailVerification,
+    )
+    .await?
+  else {
+    return Ok(None);
+  };
+
+  auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&user_id)
+    .await
+}
+
+/// Length of email verification codes
+const CODE_DIGITS: usize = 6;
+
+/// Email verification codes expire after 10 minutes
+const TEN_MINUTES: i64 = 10 * 60;
+
+/// Issue a numeric email verification code
+///
+/// The OTP counterpart of `send_email_verification`: the code is stored
+/// hashed together with the email, sent through the email sender if one is
+/// configured (as `EmailContext::token`), and returned. Confirm it with
+/// `verify_email_with_identifier`.
+pub(crate) async fn send_email_verification_code(
+  auth: &Auth,
+  request: SendEmailVerification,
+) -> Result<VerificationToken> {
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  if user.email_verified {
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  check_send_cooldown(auth, &user.email, TokenType::EmailVerification).await?;
+  check_resend_limit(auth, &user.id, TokenType::EmailVerification).await?;
+
+  let code = auth
+    .inner
+    .token_strategy
+    .generate_code(
+      auth.inner.db.as_ref().as_ref(),
+      &user.id,
+      &user.email,
+      TokenType::EmailVerification,
+      CODE_DIGITS,
+      TEN_MINUTES,
+    )
+    .await?;
+  record_send(auth, &user.id, &user.email, TokenType::EmailVerification).await?;
+
+  if let Some(email_sender) = &auth.inner.email_sender {
+    let context = EmailContext {
+      email: user.email.clone(),
+      token: code.token.clone(),
+      expires_at: code.expires_at,
+    };
+
+    email_sender.send_verification_email(context).await?;
+  }
+
+  Ok(VerificationToken {
+    token: code.token,
+    identifier: user.email,
+    expires_at: code.expires_at,
+  })
+}
+
+/// Verify an email address with a code issued for it
+///
+/// Short codes are only unique per address, so the lookup is scoped to
+/// `email` and the code compared in constant time; a code issued to one
+/// address never verifies another. Fails like `verify_email`, plus
+/// `InvalidToken` if the user's email has changed since the code was issued.
+pub(crate) async fn verify_email_with_identifier(
+  auth: &Auth,
+  email: &str,
+  code: &str,
+) -> Result<User> {
+  auth.inner.input_limits.check_email(email)?;
+  auth.inner.input_limits.check_token(code)?;
+
+  let db = auth.inner.db.as_ref().as_ref();
+  let verified_code = auth
+    .inner
+    .token_strategy
+    .verify_code(db, email, code, TokenType::EmailVerification)
+    .await?;
+
+  let user_id = verified_code.user_id.ok_or(AuthError::InvalidToken(
+    "Token does not have an associated user".to_string(),
+  ))?;
+
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  if user.email != email {
+    return Err(AuthError::InvalidToken(
+      "Token not found or invalid".to_string(),
+    ));
+  }
+
+  if user.email_verified {
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  // Claim the code; of several concurrent verifies only one gets past this
+  auth
+    .inner
+    .token_strategy
+    .mark_code_as_used(db, email, code)
+    .await?;
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  auth.inner.db.update_email_verified(&user_id, now).await?;
+  auth
+    .inner
+    .db
+    .delete_tokens_for_user(&user_id, TokenType::EmailVerification.as_str())
+    .await?;
+
+  auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)
+}
@@ -0,0 +1,91 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+use crate::strategies::token::TokenType;
+use crate::types::VerificationToken;
+use std::time::Duration;
+
+/// Request to issue a verification token for an arbitrary identifier
+///
+/// The identifier can be anything the application wants to prove ownership
+/// of: an email address, a phone number, etc. Delivering the token (e.g. by
+/// SMS) is up to the application.
+#[derive(Debug, Clone)]
+pub struct IssueVerification {
+  pub identifier: String,
+  pub token_type: TokenType,
+  /// User the identifier belongs to, if any
+  pub user_id: Option<String>,
+  pub expires_in: Duration,
+}
+
+/// Request to verify and consume a token issued by `issue_verification`
+#[derive(Debug, Clone)]
+pub struct ConsumeVerification {
+  pub token: String,
+  pub token_type: TokenType,
+}
+
+/// Result of consuming a verification token
+#[derive(Debug, Clone)]
+pub struct VerifiedIdentifier {
+  pub identifier: String,
+  pub user_id: Option<String>,
+  pub token_type: TokenType,
+}
+
+pub(crate) async fn issue(auth: &Auth, request: IssueVerification) -> Result<VerificationToken> {
+  if request.identifier.trim().is_empty() {
+    return Err(AuthError::InvalidInput(
+      "Identifier must not be empty".to_string(),
+    ));
+  }
+  request.token_type.validate()?;
+
+  let token = auth
+    .inner
+    .token_strategy
+    .generate_token(
+      auth.inner.db.as_ref().as_ref(),
+      request.user_id.as_deref(),
+      &request.identifier,
+      request.token_type,
+      request.expires_in.as_secs() as i64,
+    )
+    .await?;
+
+  Ok(VerificationToken {
+    token: token.token,
+    identifier: token.identifier,
+    expires_at: token.expires_at,
+  })
+}
+
+pub(crate) async fn consume(
+  auth: &Auth,
+  request: ConsumeVerification,
+) -> Result<VerifiedIdentifier> {
+  auth.inner.input_limits.check_token(&request.token)?;
+  request.token_type.validate()?;
+
+  let verified_token = auth
+    .inner
+    .token_strategy
+    .verify_token(
+      auth.inner.db.as_ref().as_ref(),
+      &request.token,
+      request.token_type,
+    )
+    .await?;
+
+  auth
+    .inner
+    .token_strategy
+    .mark_token_as_used(auth.inner.db.as_ref().as_ref(), &request.token)
+    .await?;
+
+  Ok(VerifiedIdentifier {
+    identifier: verified_token.identifier,
+    user_id: verified_token.user_id,
+    token_type: verified_token.token_type,
+  })
+}
@@ -0,0 +1,36 @@
+use crate::auth::Auth;
+use crate::error::Result;
+use crate::strategies::token::TokenType;
+
+/// Delete every session of every user
+///
+/// For incident response, e.g. after a session signing secret leaked.
+pub(crate) async fn logout_all_users(auth: &Auth) -> Result<u64> {
+  let deleted = auth.inner.db.delete_all_sessions().await?;
+
+  log::warn!("Logged out all users: deleted {} sessions", deleted);
+
+  Ok(deleted)
+}
+
+/// Delete every outstanding verification token, optionally of one type
+///
+/// For incident response: links and codes already sent stop working.
+pub(crate) async fn invalidate_all_tokens(
+  auth: &Auth,
+  token_type: Option<TokenType>,
+) -> Result<u64> {
+  let deleted = auth
+    .inner
+    .db
+    .delete_all_verifications(token_type.as_ref().map(TokenType::as_str))
+    .await?;
+
+  log::warn!(
+    "Invalidated {} verification tokens (type: {})",
+    deleted,
+    token_type.as_ref().map_or("all", TokenType::as_str)
+  );
+
+  Ok(deleted)
+}
@@ -0,0 +1,169 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+use crate::strategies::token::TokenType;
+use crate::types::{User, VerificationToken};
+
+/// How long an invitation link stays valid
+const INVITATION_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Request to invite a user by email
+///
+/// The user is created without a password; they set one when accepting the
+/// invitation. Delivering the invitation token is up to the application.
+#[derive(Debug, Clone)]
+pub struct InviteUser {
+  pub email: String,
+  pub name: Option<String>,
+}
+
+/// An invited user and the token they accept the invitation with, from
+/// [`Auth::invite_user`](crate::Auth::invite_user)
+#[derive(Debug, Clone)]
+pub struct Invitation {
+  pub user: User,
+  pub verification: VerificationToken,
+}
+
+/// Request to accept an invitation and choose a password
+#[derive(Debug, Clone)]
+pub struct AcceptInvitation {
+  pub token: String,
+  pub password: String,
+}
+
+pub(crate) async fn invite(auth: &Auth, request: InviteUser) -> Result<Invitation> {
+  let limits = &auth.inner.input_limits;
+  limits.check_email(&request.email)?;
+  limits.check_name(request.name.as_deref())?;
+
+  auth.inner.email_validator.validate(&request.email)?;
+
+  crate::operations::register::ensure_email_available(auth, &request.email).await?;
+
+  let user_id = auth.inner.token_generator.generate_id();
+  let created_at = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  // No credential account yet, so the user can't log in until they accept
+  let user = auth
+    .inner
+    .db
+    .create_user(
+      &user_id,
+      &request.email,
+      request.name.as_deref(),
+      created_at,
+    )
+    .await?;
+
+  let token = auth
+    .inner
+    .token_strategy
+    .generate_token(
+      auth.inner.db.as_ref().as_ref(),
+      Some(&user.id),
+      &user.email,
+      TokenType::Invitation,
+      INVITATION_TTL_SECS,
+    )
+    .await?;
+
+  Ok(Invitation {
+    user,
+    verification: VerificationToken {
+      token: token.token,
+      identifier: token.identifier,
+      expires_at: token.expires_at,
+    },
+  })
+}
+
+/// Accept an invitation: verify the email and create the credential account
+///
+/// The token proves ownership of the invited address, so the email is
+/// marked verified together with the account creation.
+pub(crate) async fn accept(auth: &Auth, request: AcceptInvitation) -> Result<User> {
+  let token = auth.inner.token_input(&request.token);
+  let limits = &auth.inner.input_limits;
+  limits.check_token(token)?;
+  limits.check_password(&request.password)?;
+
+  let verified_token = auth
+    .inner
+    .token_strategy
+    .verify_token(
+      auth.inner.db.as_ref().as_ref(),
+      token,
+      TokenType::Invitation,
+    )
+    .await?;
+
+  let user_id = verified_token.user_id.ok_or(AuthError::InvalidToken(
+    "Token does not have an associated user".to_string(),
+  ))?;
+
+  auth.inner.password_policy.check(&request.password)?;
+
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id(&user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  // Hash before claiming, so a hashing failure doesn't burn the invitation
+  let password_hash = auth
+    .inner
+    .password_strategy
+    .hash_password(&request.password)
+    .await?;
+
+  // Claim the token; of several concurrent accepts only one gets past this.
+  // A token in the verification table is claimed in the same transaction
+  // as the account, so a failed insert doesn't burn the invitation; an
+  // external store can't join it and is claimed up front.
+  let verification_id = if auth.inner.token_strategy.uses_verification_table() {
+    Some(verified_token.id.as_str())
+  } else {
+    auth
+      .inner
+      .token_strategy
+      .mark_token_as_used(auth.inner.db.as_ref().as_ref(), token)
+      .await?;
+    None
+  };
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+  let account_id = auth.inner.token_generator.generate_id();
+
+  let claimed = auth
+    .inner
+    .db
+    .accept_invitation(
+      &user.id,
+      &account_id,
+      &auth.inner.credential_provider,
+      &user.email,
+      &password_hash,
+      verification_id,
+      now,
+    )
+    .await?;
+  if !claimed {
+    return Err(AuthError::TokenAlreadyUsed(
+      "This token has already been used".to_string(),
+    ));
+  }
+
+  auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&user.id)
+    .await?
+    .ok_or(AuthError::UserNotFound)
+}
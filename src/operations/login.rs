@@ -1,5 +1,7 @@
 use crate::auth::Auth;
+use crate::database::models::DbUserWithAccount;
 use crate::error::{AuthError, Result};
+use crate::strategies::session::CreateSessionOpts;
 use crate::types::Session;
 
 #[derive(Debug, Clone)]
@@ -10,9 +12,138 @@ pub struct Login {
   pub ip_address: Option<String>,
   /// Optional user agent for session tracking
   pub user_agent: Option<String>,
+  /// Create a long-lived session (see `AuthBuilder::remember_me_duration`)
+  pub remember_me: bool,
 }
 
-pub(crate) async fn execute(auth: &Auth, request: Login) -> Result<Session> {
+impl Login {
+  pub fn new(email: impl Into<String>, password: impl Into<String>) -> Self {
+    Self {
+      email: email.into(),
+      password: password.into(),
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+    }
+  }
+
+  /// Borrow this request as a [`LoginRef`]
+  pub fn borrowed(&self) -> LoginRef<'_> {
+    LoginRef {
+      email: &self.email,
+      password: &self.password,
+      ip_address: self.ip_address.as_deref(),
+      user_agent: self.user_agent.as_deref(),
+      remember_me: self.remember_me,
+    }
+  }
+}
+
+/// Borrowed form of [`Login`], for [`Auth::login_ref`]
+///
+/// Lets handlers pass fields of a deserialized request body straight
+/// through instead of cloning them into owned `String`s.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginRef<'a> {
+  pub email: &'a str,
+  pub password: &'a str,
+  /// Optional IP address for session tracking
+  pub ip_address: Option<&'a str>,
+  /// Optional user agent for session tracking
+  pub user_agent: Option<&'a str>,
+  /// Create a long-lived session (see `AuthBuilder::remember_me_duration`)
+  pub remember_me: bool,
+}
+
+impl<'a> LoginRef<'a> {
+  pub fn new(email: &'a str, password: &'a str) -> Self {
+    Self {
+      email,
+      password,
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+    }
+  }
+}
+
+impl<'a> From<&'a Login> for LoginRef<'a> {
+  fn from(request: &'a Login) -> Self {
+    request.borrowed()
+  }
+}
+
+pub(crate) async fn execute(auth: &Auth, request: LoginRef<'_>) -> Result<Session> {
+  let limits = &auth.inner.input_limits;
+  limits.check_email(request.email)?;
+  limits.check_password(request.password)?;
+
+  let user_with_account = authenticate(auth, &request).await?;
+  let password_hash = user_with_account
+    .password_hash()
+    .ok_or(AuthError::InvalidCredentials)?;
+
+  // Upgrade hashes made with a different variant or cost parameters
+  if auth.inner.password_strategy.needs_rehash(password_hash) {
+    rehash_password(auth, &user_with_account.account.id, request.password).await;
+  }
+
+  let user = user_with_account.user;
+
+  // Only check email verification if configured to require it
+  if auth.inner.require_email_verification {
+    let email_verified = user.email_verified.unwrap_or(false);
+    let exempt = auth
+      .inner
+      .verification_exempt_emails
+      .contains(&user.email.to_ascii_lowercase());
+    if !email_verified && !exempt {
+      if auth.inner.resend_verification_on_login {
+        resend_verification(auth, &user.id, &user.email).await;
+      }
+      return Err(AuthError::EmailNotVerified(user.email.clone()));
+    }
+  }
+
+  create_session(
+    auth,
+    &user.id,
+    request.ip_address,
+    request.user_agent,
+    request.remember_me,
+  )
+  .await
+}
+
+/// Check the credentials, enforcing `login_attempt_limit` if configured
+async fn authenticate(auth: &Auth, request: &LoginRef<'_>) -> Result<DbUserWithAccount> {
+  let Some((max, window)) = auth.inner.login_attempt_limit else {
+    return check_credentials(auth, request).await;
+  };
+
+  let attempts = &auth.inner.rate_limit_store;
+  let key = format!("login:{}", request.email.to_ascii_lowercase());
+  if attempts.get(&key).await? >= u64::from(max) {
+    return Err(AuthError::RateLimitExceeded(
+      "Too many failed logins, please try again later".to_string(),
+    ));
+  }
+
+  match check_credentials(auth, request).await {
+    Ok(user_with_account) => {
+      attempts.reset(&key).await?;
+      Ok(user_with_account)
+    }
+    Err(AuthError::InvalidCredentials) => {
+      attempts.incr(&key, window).await?;
+      Err(AuthError::InvalidCredentials)
+    }
+    Err(e) => Err(e),
+  }
+}
+
+/// Find the credential account for `request.email` and check the password
+async fn check_credentials(auth: &Auth, request: &LoginRef<'_>) -> Result<DbUserWithAccount> {
   // Find user with their credential account (email/password)
   // Use the verification-aware query if email verification is required
   let user_with_account = if auth.inner.require_email_verification {
@@ -20,7 +151,10 @@ pub(crate) async fn execute(auth: &Auth, request: Login) -> Result<Session> {
     auth
       .inner
       .db
-      .find_user_with_credential_account_with_verification(&request.email)
+      .find_user_with_credential_account_with_verification(
+        request.email,
+        &auth.inner.credential_provider,
+      )
       .await?
       .ok_or(AuthError::InvalidCredentials)?
   } else {
@@ -28,7 +162,7 @@ pub(crate) async fn execute(auth: &Auth, request: Login) -> Result<Session> {
     auth
       .inner
       .db
-      .find_user_with_credential_account(&request.email)
+      .find_user_with_credential_account(request.email, &auth.inner.credential_provider)
       .await?
       .ok_or(AuthError::InvalidCredentials)?
   };
@@ -39,60 +173,164 @@ pub(crate) async fn execute(auth: &Auth, request: Login) -> Result<Session> {
     .ok_or(AuthError::InvalidCredentials)?;
 
   // Verify password
-  let is_valid = auth
-    .inner
-    .password_strategy
-    .verify_password(&request.password, password_hash)
-    .await?;
+  let is_valid = check_password(
+    auth,
+    &user_with_account.account.id,
+    request.password,
+    password_hash,
+  )
+  .await?;
 
   if !is_valid {
     return Err(AuthError::InvalidCredentials);
   }
 
-  let user = user_with_account.user;
+  Ok(user_with_account)
+}
 
-  // Only check email verification if configured to require it
-  if auth.inner.require_email_verification {
-    let email_verified = user.email_verified.unwrap_or(false);
-    if !email_verified {
-      return Err(AuthError::EmailNotVerified(user.email.clone()));
-    }
+/// Send a fresh verification email after an unverified login, best effort
+async fn resend_verification(auth: &Auth, user_id: &str, email: &str) {
+  let request = crate::operations::SendEmailVerification {
+    user_id: user_id.to_string(),
+  };
+  if let Err(e) =
+    crate::operations::email_verification::send_email_verification(auth, request).await
+  {
+    log::info!("Verification email not resent to {} on login: {}", email, e);
   }
+}
 
-  // Generate session ID and token
-  let session_id = crate::security::tokens::generate_id();
-  let token = crate::security::tokens::generate_token();
-
+/// Create a session for a user whose credentials have been checked
+pub(crate) async fn create_session(
+  auth: &Auth,
+  user_id: &str,
+  ip_address: Option<&str>,
+  user_agent: Option<&str>,
+  remember_me: bool,
+) -> Result<Session> {
   let now = std::time::SystemTime::now()
     .duration_since(std::time::UNIX_EPOCH)
     .unwrap()
     .as_secs() as i64;
 
-  // Session expires in 24 hours by default
-  let expires_at = now + 86400;
+  // Session expires in 24 hours by default, or later when remembered
+  let expires_at = if remember_me {
+    now + auth.inner.remember_me_duration.as_secs() as i64
+  } else {
+    now + 86400
+  };
+
+  // A taken token (vanishingly unlikely) gets a fresh one, a few times at most
+  let mut retries = auth.inner.session_collision_retries;
+  let session = loop {
+    let session_id = auth.inner.token_generator.generate_id();
+    let token = auth.inner.token_generator.generate_token();
+
+    let created = auth
+      .inner
+      .session_strategy
+      .create_session(
+        auth.inner.db.as_ref().as_ref(),
+        CreateSessionOpts {
+          id: &session_id,
+          token: &token,
+          user_id,
+          expires_at,
+          created_at: now,
+          ip_address,
+          user_agent,
+          remember_me,
+        },
+      )
+      .await;
+
+    match created {
+      Err(e) if retries > 0 && e.is_unique_violation() => {
+        log::warn!("Session token collision, retrying with a fresh token");
+        retries -= 1;
+      }
+      result => break result?,
+    }
+  };
+
+  Ok(session.into())
+}
+
+/// Check a user's password without creating a session
+///
+/// Intended for "confirm your password" prompts before sensitive actions.
+/// Returns `AuthError::InvalidCredentials` if the user has no credential
+/// account.
+pub(crate) async fn verify_password(auth: &Auth, user_id: &str, password: &str) -> Result<bool> {
+  auth.inner.input_limits.check_password(password)?;
+
+  let accounts = auth.inner.db.find_accounts_by_user_id(user_id).await?;
+
+  let account = accounts
+    .iter()
+    .find(|account| account.provider == auth.inner.credential_provider)
+    .ok_or(AuthError::InvalidCredentials)?;
+  let password_hash = account
+    .password_hash
+    .as_deref()
+    .ok_or(AuthError::InvalidCredentials)?;
 
-  // Create the session
-  auth
+  check_password(auth, &account.id, password, password_hash).await
+}
+
+/// Verify a password against a stored hash, failing closed on corruption
+///
+/// A stored hash that can't be parsed is logged and reported as
+/// `InvalidCredentials` rather than surfacing as a hashing error.
+async fn check_password(
+  auth: &Auth,
+  account_id: &str,
+  password: &str,
+  password_hash: &str,
+) -> Result<bool> {
+  match auth
     .inner
-    .session_strategy
-    .create_session(
-      auth.inner.db.as_ref().as_ref(),
-      &session_id,
-      &token,
-      &user.id,
-      expires_at,
-      request.ip_address.as_deref(),
-      request.user_agent.as_deref(),
-    )
-    .await?;
-
-  Ok(Session {
-    id: session_id,
-    token,
-    user_id: user.id,
-    expires_at,
-    created_at: now,
-    ip_address: request.ip_address,
-    user_agent: request.user_agent,
-  })
+    .password_strategy
+    .verify_password(password, password_hash)
+    .await
+  {
+    Err(AuthError::PasswordHashingError(e)) => {
+      log::error!(
+        "Stored password hash for account {} is malformed: {}",
+        account_id,
+        e
+      );
+      Err(AuthError::InvalidCredentials)
+    }
+    result => result,
+  }
+}
+
+/// Replace a stored hash with one using the current password settings
+///
+/// Failures are logged rather than returned; the old hash still verifies.
+async fn rehash_password(auth: &Auth, account_id: &str, password: &str) {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let result = match auth.inner.password_strategy.hash_password(password).await {
+    Ok(new_hash) => {
+      auth
+        .inner
+        .db
+        .update_account_password_hash(account_id, &new_hash, now)
+        .await
+    }
+    Err(e) => Err(e),
+  };
+
+  if let Err(e) = result {
+    log::warn!(
+      "Failed to upgrade password hash for account {}: {}",
+      account_id,
+      e
+    );
+  }
 }
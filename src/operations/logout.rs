@@ -1,6 +1,20 @@
 use crate::auth::Auth;
 use crate::error::Result;
 
+/// Which sessions `Auth::logout` deletes, set with
+/// `AuthBuilder::logout_scope`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogoutScope {
+  /// Only the session whose token is given
+  #[default]
+  SingleSession,
+  /// Every session of the token's user ("log out everywhere")
+  ///
+  /// Sessions are deleted in one statement, all or nothing, as of the
+  /// logout; a login that completes afterwards is a new session and stays.
+  AllSessions,
+}
+
 #[derive(Debug, Clone)]
 pub struct Logout {
   pub token: String,
@@ -21,10 +35,34 @@ impl From<&str> for Logout {
 }
 
 pub(crate) async fn execute(auth: &Auth, request: Logout) -> Result<()> {
+  let token = auth.inner.token_input(&request.token);
+  auth.inner.input_limits.check_token(token)?;
+
+  let db = auth.inner.db.as_ref().as_ref();
+
+  if auth.inner.logout_scope == LogoutScope::AllSessions {
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    // Only a live session may log its user out everywhere
+    if let Some(session) = auth
+      .inner
+      .session_strategy
+      .find_session(db, token)
+      .await?
+      .filter(|session| session.expires_at >= now)
+    {
+      auth.inner.db.delete_user_sessions(&session.user_id).await?;
+      return Ok(());
+    }
+  }
+
   auth
     .inner
     .session_strategy
-    .delete_session(auth.inner.db.as_ref().as_ref(), &request.token)
+    .delete_session(db, token)
     .await?;
 
   Ok(())
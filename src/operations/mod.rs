@@ -1,11 +1,39 @@
+pub mod accounts;
+pub mod change_password;
+pub mod classify_token;
 pub mod email_verification;
+pub mod identifier_verification;
+pub mod incident;
+pub mod invitation;
 pub mod login;
 pub mod logout;
+#[cfg(feature = "sms")]
+pub mod phone_verification;
 pub mod register;
+pub mod rotate_session;
+pub mod scan_sessions;
+pub mod self_test;
+pub mod sessions;
+pub mod user_emails;
 pub mod verify;
 
-pub use email_verification::{ResendEmailVerification, SendEmailVerification, VerifyEmail};
-pub use login::Login;
-pub use logout::Logout;
-pub use register::Register;
-pub use verify::Verify;
+pub use change_password::ChangePassword;
+pub use classify_token::TokenKind;
+pub use email_verification::{
+  BatchVerificationResult, ResendEmailVerification, SendEmailVerification, SendStatus,
+  SendVerificationOutcome, VerificationStatusReport, VerifyEmail,
+};
+pub use identifier_verification::{ConsumeVerification, IssueVerification, VerifiedIdentifier};
+pub use invitation::{AcceptInvitation, Invitation, InviteUser};
+pub use login::{Login, LoginRef};
+pub use logout::{Logout, LogoutScope};
+#[cfg(feature = "sms")]
+pub use phone_verification::{SendPhoneVerification, VerifiedPhone, VerifyPhone};
+pub use register::{
+  Register, RegisterOutcome, RegisterRef, RegisterWithHash, VerificationOnRegister,
+};
+pub use rotate_session::RotateSession;
+pub use scan_sessions::SessionPage;
+pub use sessions::RevokeSession;
+pub use user_emails::{AddEmail, SetPrimaryEmail, VerifySecondaryEmail};
+pub use verify::{SourceVerification, TokenSource, VerifiedSession, Verify, VerifyRef};
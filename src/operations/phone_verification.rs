@@ -0,0 +1,173 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+use crate::sms::SmsContext;
+use crate::strategies::token::TokenType;
+use crate::types::VerificationToken;
+use crate::validation;
+
+/// Request to set a user's phone number and send it a verification code
+#[derive(Debug, Clone)]
+pub struct SendPhoneVerification {
+  pub user_id: String,
+  /// Phone number in E.164 format, e.g. `+15555550123`
+  pub phone: String,
+}
+
+/// Request to verify a user's phone number with the code sent by SMS
+#[derive(Debug, Clone)]
+pub struct VerifyPhone {
+  pub user_id: String,
+  pub code: String,
+}
+
+/// A verified phone number
+#[derive(Debug, Clone)]
+pub struct VerifiedPhone {
+  pub user_id: String,
+  pub phone: String,
+  pub verified_at: i64,
+}
+
+/// Length of phone verification codes
+const CODE_DIGITS: usize = 6;
+
+/// Phone verification codes expire after 10 minutes
+const TEN_MINUTES: i64 = 10 * 60;
+
+/// Execute phone verification send operation
+///
+/// Stores the phone number on the user (unverified) when it changed, issues
+/// a numeric code and sends it through the SMS sender, if configured. The
+/// code is also returned so applications without a sender can deliver it;
+/// a failed send is logged rather than returned, for the same reason.
+pub(crate) async fn send_phone_verification(
+  auth: &Auth,
+  request: SendPhoneVerification,
+) -> Result<VerificationToken> {
+  validation::phone::validate(&request.phone)?;
+
+  let current = auth
+    .inner
+    .db
+    .find_user_phone(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  if current.phone.as_deref() == Some(request.phone.as_str()) {
+    if current.phone_verified {
+      return Err(AuthError::PhoneAlreadyVerified(
+        "Phone number is already verified".to_string(),
+      ));
+    }
+  } else {
+    auth
+      .inner
+      .db
+      .set_user_phone(&request.user_id, &request.phone, now)
+      .await?;
+  }
+
+  let code = auth
+    .inner
+    .token_strategy
+    .generate_code(
+      auth.inner.db.as_ref().as_ref(),
+      &request.user_id,
+      &request.phone,
+      TokenType::PhoneVerification,
+      CODE_DIGITS,
+      TEN_MINUTES,
+    )
+    .await?;
+
+  if let Some(sms_sender) = &auth.inner.sms_sender {
+    let context = SmsContext {
+      phone: request.phone.clone(),
+      code: code.token.clone(),
+      expires_at: code.expires_at,
+    };
+
+    // The number and code are already stored; the caller still gets the
+    // code back and can deliver it or ask for another one
+    if let Err(e) = sms_sender.send_verification_sms(context).await {
+      log::warn!("Failed to send phone verification SMS: {}", e);
+    }
+  }
+
+  Ok(VerificationToken {
+    token: code.token,
+    identifier: request.phone,
+    expires_at: code.expires_at,
+  })
+}
+
+/// Execute phone verification operation
+///
+/// Checks the code against the user's current phone number and marks the
+/// number as verified.
+pub(crate) async fn verify_phone(auth: &Auth, request: VerifyPhone) -> Result<VerifiedPhone> {
+  auth.inner.input_limits.check_token(&request.code)?;
+
+  let current = auth
+    .inner
+    .db
+    .find_user_phone(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  let phone = current.phone.ok_or(AuthError::InvalidToken(
+    "No phone number pending verification".to_string(),
+  ))?;
+
+  if current.phone_verified {
+    return Err(AuthError::PhoneAlreadyVerified(
+      "Phone number is already verified".to_string(),
+    ));
+  }
+
+  let verified_code = auth
+    .inner
+    .token_strategy
+    .verify_code(
+      auth.inner.db.as_ref().as_ref(),
+      &phone,
+      &request.code,
+      TokenType::PhoneVerification,
+    )
+    .await?;
+
+  // Phone numbers aren't unique, so make sure the code was issued to this user
+  if verified_code.user_id.as_deref() != Some(request.user_id.as_str()) {
+    return Err(AuthError::InvalidToken(
+      "Token not found or invalid".to_string(),
+    ));
+  }
+
+  auth
+    .inner
+    .token_strategy
+    .mark_code_as_used(auth.inner.db.as_ref().as_ref(), &phone, &request.code)
+    .await?;
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  auth
+    .inner
+    .db
+    .mark_phone_verified(&request.user_id, now)
+    .await?;
+
+  Ok(VerifiedPhone {
+    user_id: request.user_id,
+    phone,
+    verified_at: now,
+  })
+}
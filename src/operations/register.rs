@@ -1,13 +1,37 @@
 use crate::auth::Auth;
 use crate::email::EmailContext;
 use crate::error::{AuthError, Result};
-use crate::strategies::token::TokenType;
-use crate::types::User;
-use crate::validation;
+use crate::strategies::token::{Token, TokenType};
+use crate::types::{Session, User, VerificationToken};
 
 #[cfg(feature = "email-queue")]
 use crate::email_job::EmailJob;
 
+/// When `register` sends a verification email
+///
+/// Set with `AuthBuilder::send_verification_on_register`; a `bool` converts
+/// to `IfSenderPresent` (`true`) or `Never` (`false`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerificationOnRegister {
+  /// Never send automatically (default)
+  #[default]
+  Never,
+  /// Send when an email sender is configured, otherwise skip
+  IfSenderPresent,
+  /// Always send; `build()` fails if no email sender is configured
+  Always,
+}
+
+impl From<bool> for VerificationOnRegister {
+  fn from(enabled: bool) -> Self {
+    if enabled {
+      VerificationOnRegister::IfSenderPresent
+    } else {
+      VerificationOnRegister::Never
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct Register {
   pub email: String,
@@ -15,87 +39,183 @@ pub struct Register {
   pub name: Option<String>,
 }
 
-pub(crate) async fn execute(auth: &Auth, request: Register) -> Result<User> {
-  validation::email::validate(&request.email)?;
+impl Register {
+  pub fn new(email: impl Into<String>, password: impl Into<String>) -> Self {
+    Self {
+      email: email.into(),
+      password: password.into(),
+      name: None,
+    }
+  }
+
+  /// Borrow this request as a [`RegisterRef`]
+  pub fn borrowed(&self) -> RegisterRef<'_> {
+    RegisterRef {
+      email: &self.email,
+      password: &self.password,
+      name: self.name.as_deref(),
+    }
+  }
+}
 
-  validation::password::validate(&request.password)?;
+/// Borrowed form of [`Register`], for [`Auth::register_ref`]
+#[derive(Debug, Clone, Copy)]
+pub struct RegisterRef<'a> {
+  pub email: &'a str,
+  pub password: &'a str,
+  pub name: Option<&'a str>,
+}
 
-  // Check if user already exists
-  if let Some(_existing) = auth.inner.db.find_user_by_email(&request.email).await? {
-    return Err(AuthError::UserAlreadyExists(request.email));
+impl<'a> RegisterRef<'a> {
+  pub fn new(email: &'a str, password: &'a str) -> Self {
+    Self {
+      email,
+      password,
+      name: None,
+    }
+  }
+}
+
+impl<'a> From<&'a Register> for RegisterRef<'a> {
+  fn from(request: &'a Register) -> Self {
+    request.borrowed()
   }
+}
+
+/// Registration with a password hash computed elsewhere
+///
+/// For trusted callers only, e.g. incremental migrations or an external
+/// hashing service. `password_hash` is stored as-is: the caller is
+/// responsible for it being a strong hash (a PHC string the configured
+/// password strategy can verify) of a password that met their own policy.
+#[derive(Debug, Clone)]
+pub struct RegisterWithHash {
+  pub email: String,
+  pub password_hash: String,
+  pub name: Option<String>,
+}
+
+/// A newly registered user, from [`Auth::register_detailed`]
+#[derive(Debug, Clone)]
+pub struct RegisterOutcome {
+  pub user: User,
+  /// The email verification token, when `register` issued one for the
+  /// application to deliver itself (see
+  /// `AuthBuilder::return_verification_token_on_register`)
+  pub verification: Option<VerificationToken>,
+  /// A session for the new user (see `AuthBuilder::session_on_register`)
+  pub session: Option<Session>,
+}
+
+pub(crate) async fn execute(auth: &Auth, request: RegisterRef<'_>) -> Result<User> {
+  Ok(register(auth, request, false).await?.user)
+}
+
+pub(crate) async fn execute_detailed(
+  auth: &Auth,
+  request: RegisterRef<'_>,
+) -> Result<RegisterOutcome> {
+  register(auth, request, true).await
+}
+
+async fn register(
+  auth: &Auth,
+  request: RegisterRef<'_>,
+  detailed: bool,
+) -> Result<RegisterOutcome> {
+  let limits = &auth.inner.input_limits;
+  limits.check_email(request.email)?;
+  limits.check_password(request.password)?;
+  limits.check_name(request.name)?;
+
+  auth.inner.email_validator.validate(request.email)?;
+
+  auth.inner.password_policy.check(request.password)?;
+
+  ensure_email_available(auth, request.email).await?;
 
   // Hash the password
   let password_hash = auth
     .inner
     .password_strategy
-    .hash_password(&request.password)
+    .hash_password(request.password)
     .await?;
 
-  let user_id = crate::security::tokens::generate_id();
-  let account_id = crate::security::tokens::generate_id();
-
-  let created_at = std::time::SystemTime::now()
-    .duration_since(std::time::UNIX_EPOCH)
-    .unwrap()
-    .as_secs() as i64;
-
-  // Create the user
-  let user = auth
-    .inner
-    .db
-    .create_user(
-      &user_id,
-      &request.email,
-      request.name.as_deref(),
-      created_at,
-    )
-    .await?;
+  let user = create_credential_user(auth, request.email, request.name, &password_hash).await?;
 
-  // Create the credential account (links user to email/password provider)
-  auth
-    .inner
-    .db
-    .create_account(
-      &account_id,
-      &user_id,
-      "credential",   // provider type for email/password
-      &request.email, // provider_account_id is the email for credentials
-      Some(&password_hash),
-      created_at,
-    )
-    .await?;
+  let verification = if auth.inner.email_sender.is_none() {
+    // Without an email sender, the application may deliver the token itself;
+    // only the detailed outcome can hand it back, so plain `register` skips it
+    if detailed && auth.inner.return_verification_token_on_register {
+      let token = generate_verification_token(auth, &user).await?;
+      Some(VerificationToken {
+        token: token.token,
+        identifier: token.identifier,
+        expires_at: token.expires_at,
+      })
+    } else {
+      None
+    }
+  } else {
+    // Check if we should send verification email on registration
+    if auth.inner.send_verification_on_register != VerificationOnRegister::Never {
+      send_verification_email(auth, &user).await?;
+    }
+    None
+  };
 
-  // Check if we should send verification email on registration
-  if !auth.inner.send_verification_on_register {
-    // User opted out of automatic verification emails
-    return Ok(user);
-  }
+  // Only the detailed outcome can hand a session back. Created last, so a
+  // registration failed by the email step leaves no live session behind.
+  let session =
+    if detailed && auth.inner.session_on_register && !auth.inner.require_email_verification {
+      Some(crate::operations::login::create_session(auth, &user.id, None, None, false).await?)
+    } else {
+      None
+    };
 
-  // Check if email sender is configured
-  if auth.inner.email_sender.is_none() {
-    // No email sender configured, skip sending verification email
-    return Ok(user);
-  }
+  Ok(RegisterOutcome {
+    user,
+    verification,
+    session,
+  })
+}
 
-  // Generate verification token
+/// Issue a 24-hour email verification token for a new user
+async fn generate_verification_token(auth: &Auth, user: &User) -> Result<Token> {
   const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
   let token = auth
     .inner
     .token_strategy
     .generate_token(
       auth.inner.db.as_ref().as_ref(),
-      &user_id,
-      &request.email,
+      Some(&user.id),
+      &user.email,
       TokenType::EmailVerification,
       TWENTY_FOUR_HOURS,
     )
     .await?;
+  crate::operations::email_verification::record_send(
+    auth,
+    &user.id,
+    &user.email,
+    TokenType::EmailVerification,
+  )
+  .await?;
+
+  Ok(token)
+}
+
+/// Issue a verification token and email it to a new user
+///
+/// Delivery failures are logged, unless `fail_registration_on_email_error`
+/// makes them fail registration.
+async fn send_verification_email(auth: &Auth, user: &User) -> Result<()> {
+  let token = generate_verification_token(auth, user).await?;
 
   // Try to send verification email via queue (if email-queue feature enabled)
   #[cfg(feature = "email-queue")]
   {
-    if let Some(queue) = &auth.inner.email_queue {
+    if let Some(queue) = auth.email_queue() {
       // Queue for async processing (fast path)
       let job = EmailJob::verification(
         user.email.clone(),
@@ -108,7 +228,7 @@ pub(crate) async fn execute(auth: &Auth, request: Register) -> Result<User> {
       match queue.enqueue(job).await {
         Ok(()) => {
           // Successfully queued, return immediately
-          return Ok(user);
+          return Ok(());
         }
         Err(e) => {
           log::warn!("Email queue error, sending synchronously: {}", e);
@@ -126,8 +246,108 @@ pub(crate) async fn execute(auth: &Auth, request: Register) -> Result<User> {
       expires_at: token.expires_at,
     };
 
-    email_sender.send_verification_email(context).await?;
+    if let Err(e) = email_sender.send_verification_email(context).await {
+      if auth.inner.fail_registration_on_email_error {
+        return Err(e);
+      }
+      log::warn!(
+        "Failed to send verification email to {}, registration kept: {}",
+        user.email,
+        e
+      );
+    }
+  }
+
+  Ok(())
+}
+
+pub(crate) async fn execute_with_hash(auth: &Auth, request: RegisterWithHash) -> Result<User> {
+  let limits = &auth.inner.input_limits;
+  limits.check_email(&request.email)?;
+  limits.check_password(&request.password_hash)?;
+  limits.check_name(request.name.as_deref())?;
+
+  auth.inner.email_validator.validate(&request.email)?;
+
+  if request.password_hash.is_empty() {
+    return Err(AuthError::InvalidInput(
+      "password_hash must not be empty".to_string(),
+    ));
+  }
+
+  ensure_email_available(auth, &request.email).await?;
+
+  create_credential_user(
+    auth,
+    &request.email,
+    request.name.as_deref(),
+    &request.password_hash,
+  )
+  .await
+}
+
+/// Fail with `UserAlreadyExists` if the email belongs to any user
+pub(crate) async fn ensure_email_available(auth: &Auth, email: &str) -> Result<()> {
+  if auth.inner.db.find_user_by_email(email).await?.is_some() {
+    return Err(AuthError::UserAlreadyExists(email.to_string()));
+  }
+
+  // Secondary addresses of other users can't be registered either
+  if auth.inner.db.find_user_email(email).await?.is_some() {
+    return Err(AuthError::UserAlreadyExists(email.to_string()));
   }
 
+  Ok(())
+}
+
+/// Create a user and its credential account holding `password_hash`
+async fn create_credential_user(
+  auth: &Auth,
+  email: &str,
+  name: Option<&str>,
+  password_hash: &str,
+) -> Result<User> {
+  let user_id = auth.inner.token_generator.generate_id();
+
+  let created_at = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  // Create the user
+  let user = auth
+    .inner
+    .db
+    .create_user(&user_id, email, name, created_at)
+    .await?;
+
+  // Create the credential account (links user to email/password provider)
+  crate::operations::accounts::create(
+    auth,
+    &user_id,
+    &auth.inner.credential_provider,
+    email, // provider_account_id is the email for credentials
+    Some(password_hash),
+    created_at,
+  )
+  .await?;
+
   Ok(user)
 }
+
+/// Check whether an email could be registered right now
+///
+/// Applies the same format validation and uniqueness check as `register`.
+/// Note that exposing this publicly lets anyone probe which emails have
+/// accounts; only wire it to signup forms where that trade-off is acceptable.
+pub(crate) async fn is_email_available(auth: &Auth, email: &str) -> Result<bool> {
+  auth.inner.input_limits.check_email(email)?;
+
+  auth.inner.email_validator.validate(email)?;
+
+  match ensure_email_available(auth, email).await {
+    Ok(()) => Ok(true),
+    Err(AuthError::UserAlreadyExists(_)) => Ok(false),
+    Err(e) => Err(e),
+  }
+}
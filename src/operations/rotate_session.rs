@@ -0,0 +1,42 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+use crate::types::Session;
+
+/// Rotate a session token after a privilege change
+///
+/// Issues a fresh token for the same session (same id, user and expiry) and
+/// invalidates the old token in a single update. Call this after
+/// re-authentication or 2FA completion to prevent session fixation.
+#[derive(Debug, Clone)]
+pub struct RotateSession {
+  pub token: String,
+}
+
+impl RotateSession {
+  pub fn new(token: impl Into<String>) -> Self {
+    Self {
+      token: token.into(),
+    }
+  }
+}
+
+impl From<&str> for RotateSession {
+  fn from(token: &str) -> Self {
+    Self::new(token)
+  }
+}
+
+pub(crate) async fn execute(auth: &Auth, request: RotateSession) -> Result<Session> {
+  auth.inner.input_limits.check_token(&request.token)?;
+
+  let new_token = auth.inner.token_generator.generate_token();
+
+  let session = auth
+    .inner
+    .session_strategy
+    .rotate_session(auth.inner.db.as_ref().as_ref(), &request.token, &new_token)
+    .await?
+    .ok_or(AuthError::InvalidSession)?;
+
+  Ok(session.into())
+}
@@ -0,0 +1,35 @@
+use crate::auth::Auth;
+use crate::error::Result;
+use crate::types::Session;
+
+/// One page of sessions from [`Auth::scan_sessions`]
+#[derive(Debug, Clone)]
+pub struct SessionPage {
+  /// Sessions ordered by id
+  pub sessions: Vec<Session>,
+  /// Pass as `after` to fetch the next page; `None` once the scan is done
+  pub next_cursor: Option<String>,
+}
+
+pub(crate) async fn execute(auth: &Auth, after: Option<&str>, limit: u32) -> Result<SessionPage> {
+  let sessions: Vec<Session> = auth
+    .inner
+    .db
+    .scan_sessions(after, limit)
+    .await?
+    .into_iter()
+    .map(Into::into)
+    .collect();
+
+  // A short page means there's nothing left to fetch
+  let next_cursor = if limit > 0 && sessions.len() == limit as usize {
+    sessions.last().map(|session| session.id.clone())
+  } else {
+    None
+  };
+
+  Ok(SessionPage {
+    sessions,
+    next_cursor,
+  })
+}
@@ -0,0 +1,49 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+
+/// Exercise the configured strategies and the database once
+///
+/// Nothing is written: the password, token and hash are thrown away.
+pub(crate) async fn execute(auth: &Auth) -> Result<()> {
+  let password = auth.inner.token_generator.generate_token();
+  let hash = auth
+    .inner
+    .password_strategy
+    .hash_password(&password)
+    .await?;
+  if !auth
+    .inner
+    .password_strategy
+    .verify_password(&password, &hash)
+    .await?
+  {
+    return Err(AuthError::InternalError(
+      "self-test: password hash did not verify".to_string(),
+    ));
+  }
+
+  let token = auth.inner.token_generator.generate_token();
+  if token.is_empty() || !auth.inner.session_strategy.accepts_token(&token) {
+    return Err(AuthError::InternalError(
+      "self-test: generated token was rejected".to_string(),
+    ));
+  }
+
+  // Tokens are looked up by their stored form, so it must be stable
+  let session = &auth.inner.session_strategy;
+  let stored = session.stored_token(&token);
+  if stored.is_empty() || stored != session.stored_token(&token) {
+    return Err(AuthError::InternalError(
+      "self-test: session token has no stable stored form".to_string(),
+    ));
+  }
+  let verification = &auth.inner.token_strategy;
+  let stored = verification.stored_token(&token)?;
+  if stored.is_empty() || stored != verification.stored_token(&token)? {
+    return Err(AuthError::InternalError(
+      "self-test: verification token has no stable stored form".to_string(),
+    ));
+  }
+
+  auth.inner.db.ping().await
+}
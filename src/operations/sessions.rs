@@ -0,0 +1,86 @@
+use crate::auth::Auth;
+use crate::error::{AuthError, Result};
+use crate::types::{Session, SessionInfo};
+
+/// Request to revoke one of a user's sessions by its id
+#[derive(Debug, Clone)]
+pub struct RevokeSession {
+  pub user_id: String,
+  /// The session's `id`, as returned by `Auth::list_sessions`
+  pub session_id: String,
+}
+
+/// List a user's active sessions, newest first
+///
+/// Ties on `created_at` are broken by `id`, so the order is stable. Tokens
+/// are never included. Returns an empty list for unknown user IDs.
+pub(crate) async fn list(auth: &Auth, user_id: &str) -> Result<Vec<SessionInfo>> {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let sessions = auth.inner.db.find_sessions_by_user_id(user_id, now).await?;
+
+  Ok(sessions.into_iter().map(SessionInfo::from).collect())
+}
+
+/// Delete one of the user's sessions
+///
+/// Returns `AuthError::InvalidSession` if the user has no session with that
+/// id, so one user can't revoke another's sessions.
+pub(crate) async fn revoke(auth: &Auth, request: RevokeSession) -> Result<()> {
+  if !auth
+    .inner
+    .db
+    .delete_user_session(&request.user_id, &request.session_id)
+    .await?
+  {
+    return Err(AuthError::InvalidSession);
+  }
+
+  Ok(())
+}
+
+/// Set a live session's expiry, within the configured maximum extension
+pub(crate) async fn extend(auth: &Auth, token: &str, new_expires_at: i64) -> Result<Session> {
+  auth.inner.input_limits.check_token(token)?;
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  if new_expires_at <= now {
+    return Err(AuthError::InvalidInput(
+      "new session expiry must be in the future".to_string(),
+    ));
+  }
+  let max_expires_at = now + auth.inner.max_session_extension.as_secs() as i64;
+  if new_expires_at > max_expires_at {
+    return Err(AuthError::InvalidInput(format!(
+      "new session expiry may be at most {} seconds from now",
+      auth.inner.max_session_extension.as_secs()
+    )));
+  }
+
+  let mut session = auth
+    .inner
+    .session_strategy
+    .find_session(auth.inner.db.as_ref().as_ref(), token)
+    .await?
+    .filter(|session| session.expires_at >= now)
+    .ok_or(AuthError::InvalidSession)?;
+
+  if !auth
+    .inner
+    .db
+    .update_session_expiry(&session.id, new_expires_at)
+    .await?
+  {
+    return Err(AuthError::InvalidSession);
+  }
+
+  session.expires_at = new_expires_at;
+  Ok(session.into())
+}
@@ -0,0 +1,324 @@
+use crate::auth::Auth;
+use crate::email::EmailContext;
+use crate::error::{AuthError, Result};
+use crate::operations::email_verification::{check_resend_limit, check_send_cooldown, record_send};
+use crate::strategies::token::TokenType;
+use crate::types::{IdentifierKind, UnverifiedIdentifier, User, UserEmail, VerificationToken};
+
+/// Request to add a secondary email address to a user
+#[derive(Debug, Clone)]
+pub struct AddEmail {
+  pub user_id: String,
+  pub email: String,
+}
+
+/// Request to verify a secondary email address using a token
+#[derive(Debug, Clone)]
+pub struct VerifySecondaryEmail {
+  pub token: String,
+}
+
+/// Request to make a verified secondary email the user's primary email
+#[derive(Debug, Clone)]
+pub struct SetPrimaryEmail {
+  pub user_id: String,
+  pub email: String,
+}
+
+/// Add an unverified secondary email and issue a verification token for it
+///
+/// The address must not be in use by any user, as either a primary or a
+/// secondary email. If an email sender is configured the token is sent to the
+/// new address; a failed send is logged and the token is still returned.
+pub(crate) async fn add_email(auth: &Auth, request: AddEmail) -> Result<VerificationToken> {
+  auth.inner.input_limits.check_email(&request.email)?;
+
+  auth.inner.email_validator.validate(&request.email)?;
+
+  auth
+    .inner
+    .db
+    .find_user_by_id(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  if auth
+    .inner
+    .db
+    .find_user_by_email(&request.email)
+    .await?
+    .is_some()
+    || auth
+      .inner
+      .db
+      .find_user_email(&request.email)
+      .await?
+      .is_some()
+  {
+    return Err(AuthError::UserAlreadyExists(request.email));
+  }
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let id = auth.inner.token_generator.generate_id();
+  auth
+    .inner
+    .db
+    .create_user_email(&id, &request.user_id, &request.email, now)
+    .await?;
+
+  issue_secondary_verification(auth, &request.user_id, request.email).await
+}
+
+/// Issue a fresh verification token for an unverified secondary email
+///
+/// Earlier tokens for the address stay valid until they expire. Subject to
+/// `email_send_cooldown` and `max_verification_resends`, counted separately
+/// from primary email verification.
+pub(crate) async fn resend_secondary_email_verification(
+  auth: &Auth,
+  user_id: &str,
+  email: &str,
+) -> Result<VerificationToken> {
+  auth.inner.input_limits.check_email(email)?;
+
+  let user_email = auth
+    .inner
+    .db
+    .find_user_email(email)
+    .await?
+    .filter(|user_email| user_email.user_id == user_id)
+    .ok_or_else(|| AuthError::EmailNotFound(email.to_string()))?;
+
+  if user_email.verified {
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  check_send_cooldown(
+    auth,
+    &user_email.email,
+    TokenType::SecondaryEmailVerification,
+  )
+  .await?;
+  check_resend_limit(auth, user_id, TokenType::SecondaryEmailVerification).await?;
+
+  issue_secondary_verification(auth, user_id, user_email.email).await
+}
+
+/// Generate a secondary email verification token and send it, if a sender
+/// is configured
+///
+/// Send failures are logged rather than returned, since the address and token
+/// already exist by then.
+async fn issue_secondary_verification(
+  auth: &Auth,
+  user_id: &str,
+  email: String,
+) -> Result<VerificationToken> {
+  // Generate token (24 hours expiry)
+  const TWENTY_FOUR_HOURS: i64 = 24 * 60 * 60;
+  let token = auth
+    .inner
+    .token_strategy
+    .generate_token(
+      auth.inner.db.as_ref().as_ref(),
+      Some(user_id),
+      &email,
+      TokenType::SecondaryEmailVerification,
+      TWENTY_FOUR_HOURS,
+    )
+    .await?;
+  record_send(auth, user_id, &email, TokenType::SecondaryEmailVerification).await?;
+
+  if let Some(email_sender) = &auth.inner.email_sender {
+    let context = EmailContext {
+      email: email.clone(),
+      token: token.token.clone(),
+      expires_at: token.expires_at,
+    };
+
+    // The caller still gets the token back and can deliver it or resend
+    if let Err(e) = email_sender.send_verification_email(context).await {
+      log::warn!("Failed to send secondary email verification: {}", e);
+    }
+  }
+
+  Ok(VerificationToken {
+    token: token.token,
+    identifier: email,
+    expires_at: token.expires_at,
+  })
+}
+
+/// Verify a secondary email address with a token from `add_email`
+pub(crate) async fn verify_secondary_email(
+  auth: &Auth,
+  request: VerifySecondaryEmail,
+) -> Result<UserEmail> {
+  auth.inner.input_limits.check_token(&request.token)?;
+
+  let verified_token = auth
+    .inner
+    .token_strategy
+    .verify_token(
+      auth.inner.db.as_ref().as_ref(),
+      &request.token,
+      TokenType::SecondaryEmailVerification,
+    )
+    .await?;
+
+  // The address may have been promoted or removed since the token was issued
+  let user_email = auth
+    .inner
+    .db
+    .find_user_email(&verified_token.identifier)
+    .await?
+    .ok_or(AuthError::InvalidToken(
+      "Email is no longer linked to an account".to_string(),
+    ))?;
+
+  if user_email.verified {
+    return Err(AuthError::EmailAlreadyVerified(
+      "Email is already verified".to_string(),
+    ));
+  }
+
+  auth
+    .inner
+    .token_strategy
+    .mark_token_as_used(auth.inner.db.as_ref().as_ref(), &request.token)
+    .await?;
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  auth
+    .inner
+    .db
+    .mark_user_email_verified(&user_email.email, now)
+    .await?;
+
+  Ok(UserEmail {
+    verified: true,
+    verified_at: Some(now),
+    ..user_email.into()
+  })
+}
+
+/// Swap a verified secondary email with the user's primary email
+///
+/// The previous primary is kept as a secondary address. Login switches to the
+/// new primary immediately.
+pub(crate) async fn set_primary_email(auth: &Auth, request: SetPrimaryEmail) -> Result<User> {
+  auth.inner.input_limits.check_email(&request.email)?;
+
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  if user.email == request.email {
+    return Ok(user);
+  }
+
+  let user_email = auth
+    .inner
+    .db
+    .find_user_email(&request.email)
+    .await?
+    .filter(|user_email| user_email.user_id == request.user_id)
+    .ok_or_else(|| AuthError::EmailNotFound(request.email.clone()))?;
+
+  if !user_email.verified {
+    return Err(AuthError::EmailNotVerified(request.email));
+  }
+
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let demoted_id = auth.inner.token_generator.generate_id();
+  auth
+    .inner
+    .db
+    .promote_user_email(
+      &request.user_id,
+      &request.email,
+      &demoted_id,
+      &auth.inner.credential_provider,
+      now,
+    )
+    .await?;
+
+  auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(&request.user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)
+}
+
+/// List all email addresses of a user, primary first
+pub(crate) async fn list_emails(auth: &Auth, user_id: &str) -> Result<Vec<UserEmail>> {
+  let user = auth
+    .inner
+    .db
+    .find_user_by_id_with_verification(user_id)
+    .await?
+    .ok_or(AuthError::UserNotFound)?;
+
+  let mut emails = vec![UserEmail {
+    email: user.email,
+    primary: true,
+    verified: user.email_verified,
+    verified_at: user.email_verified_at,
+    created_at: user.created_at,
+  }];
+
+  let secondary = auth.inner.db.find_user_emails_by_user_id(user_id).await?;
+  emails.extend(secondary.into_iter().map(UserEmail::from));
+
+  Ok(emails)
+}
+
+/// List a user's email addresses and phone number that aren't verified yet
+///
+/// Emails come first, primary before secondary, then the phone number (only
+/// with the `sms` feature).
+pub(crate) async fn unverified_identifiers(
+  auth: &Auth,
+  user_id: &str,
+) -> Result<Vec<UnverifiedIdentifier>> {
+  #[cfg_attr(not(feature = "sms"), allow(unused_mut))]
+  let mut identifiers: Vec<_> = list_emails(auth, user_id)
+    .await?
+    .into_iter()
+    .filter(|email| !email.verified)
+    .map(|email| UnverifiedIdentifier {
+      kind: IdentifierKind::Email,
+      value: email.email,
+    })
+    .collect();
+
+  #[cfg(feature = "sms")]
+  if let Some(phone) = auth.inner.db.find_user_phone(user_id).await? {
+    if let (Some(value), false) = (phone.phone, phone.phone_verified) {
+      identifiers.push(UnverifiedIdentifier {
+        kind: IdentifierKind::Phone,
+        value,
+      });
+    }
+  }
+
+  Ok(identifiers)
+}
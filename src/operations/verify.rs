@@ -1,4 +1,5 @@
 use crate::auth::Auth;
+use crate::database::models::DbSession;
 use crate::error::{AuthError, Result};
 use crate::types::User;
 
@@ -20,44 +21,206 @@ impl Verify {
   }
 }
 
+impl Verify {
+  /// Borrow this request as a [`VerifyRef`]
+  pub fn borrowed(&self) -> VerifyRef<'_> {
+    VerifyRef { token: &self.token }
+  }
+}
+
 impl From<&str> for Verify {
   fn from(token: &str) -> Self {
     Self::new(token)
   }
 }
 
-pub(crate) async fn execute(auth: &Auth, request: Verify) -> Result<User> {
-  let session = auth
-    .inner
-    .session_strategy
-    .find_session(auth.inner.db.as_ref().as_ref(), &request.token)
-    .await?
-    .ok_or(AuthError::InvalidSession)?;
+/// Borrowed form of [`Verify`], for [`Auth::verify_ref`]
+#[derive(Debug, Clone, Copy)]
+pub struct VerifyRef<'a> {
+  pub token: &'a str,
+}
+
+impl<'a> VerifyRef<'a> {
+  pub fn new(token: &'a str) -> Self {
+    Self { token }
+  }
+}
+
+impl<'a> From<&'a str> for VerifyRef<'a> {
+  fn from(token: &'a str) -> Self {
+    Self::new(token)
+  }
+}
+
+impl<'a> From<&'a Verify> for VerifyRef<'a> {
+  fn from(request: &'a Verify) -> Self {
+    request.borrowed()
+  }
+}
+
+/// Where a verified session token was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenSource {
+  /// `Authorization: Bearer <token>` header
+  #[default]
+  Header,
+  /// Session cookie
+  Cookie,
+}
 
+/// Result of verifying a token taken from a request header or cookie
+#[derive(Debug, Clone)]
+pub struct SourceVerification {
+  pub user: User,
+  /// The source whose token authenticated the request
+  pub source: TokenSource,
+}
+
+/// A verified session with its remaining lifetime, from [`Auth::verify_full`]
+#[derive(Debug, Clone)]
+pub struct VerifiedSession {
+  pub user: User,
+  /// When the session expires (Unix seconds)
+  pub session_expires_at: i64,
+  /// Seconds until the session expires, as of verification
+  pub seconds_remaining: i64,
+}
+
+pub(crate) async fn execute(auth: &Auth, request: VerifyRef<'_>) -> Result<User> {
+  Ok(execute_full(auth, request).await?.user)
+}
+
+pub(crate) async fn execute_full(auth: &Auth, request: VerifyRef<'_>) -> Result<VerifiedSession> {
   let now = std::time::SystemTime::now()
     .duration_since(std::time::UNIX_EPOCH)
     .unwrap()
     .as_secs() as i64;
 
-  if session.expires_at < now {
+  execute_full_at(auth, request, now).await
+}
+
+/// Verify a session as of `now` (Unix seconds)
+pub(crate) async fn execute_full_at(
+  auth: &Auth,
+  request: VerifyRef<'_>,
+  now: i64,
+) -> Result<VerifiedSession> {
+  let token = auth.inner.token_input(request.token);
+  auth.inner.input_limits.check_token(token)?;
+
+  if !auth.inner.session_strategy.accepts_token(token) {
     return Err(AuthError::InvalidSession);
   }
 
-  // Use the verification-aware query if email verification feature is used
-  // This ensures we return accurate email_verified status
-  if auth.inner.require_email_verification {
+  // Session and user come back from a single JOIN on this hot path
+  let stored_token = auth.inner.session_strategy.stored_token(token);
+  // Use the verification-aware query only if email verification is required
+  let lookup = if auth.inner.require_email_verification {
     auth
       .inner
       .db
-      .find_user_by_id_with_verification(&session.user_id)
-      .await?
-      .ok_or(AuthError::UserNotFound)
+      .find_session_with_user_with_verification(&stored_token)
+      .await
   } else {
-    auth
-      .inner
-      .db
-      .find_user_by_id(&session.user_id)
-      .await?
-      .ok_or(AuthError::UserNotFound)
+    auth.inner.db.find_session_with_user(&stored_token).await
+  };
+  let found = match lookup {
+    Ok(found) => found,
+    Err(e) => {
+      // Fail closed: a lookup that errored never authenticates anyone
+      log::error!("Session lookup failed, rejecting token: {}", e);
+      None
+    }
   }
+  .ok_or(AuthError::InvalidSession)?;
+
+  check_live(auth, &found.session, now).await?;
+
+  Ok(VerifiedSession {
+    user: found.user.into(),
+    session_expires_at: found.session.expires_at,
+    seconds_remaining: found.session.expires_at - now,
+  })
+}
+
+/// The user ID behind a session token, without loading the user
+///
+/// Applies the same checks as `verify` (expiry, inactivity timeout) but
+/// reads only the session row.
+pub(crate) async fn resolve_user_id(auth: &Auth, token: &str) -> Result<String> {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64;
+
+  let token = auth.inner.token_input(token);
+  auth.inner.input_limits.check_token(token)?;
+
+  if !auth.inner.session_strategy.accepts_token(token) {
+    return Err(AuthError::InvalidSession);
+  }
+
+  let stored_token = auth.inner.session_strategy.stored_token(token);
+  let session = match auth.inner.db.find_session(&stored_token).await {
+    Ok(session) => session,
+    Err(e) => {
+      // Fail closed, as in `verify`
+      log::error!("Session lookup failed, rejecting token: {}", e);
+      None
+    }
+  }
+  .ok_or(AuthError::InvalidSession)?;
+
+  check_live(auth, &session, now).await?;
+
+  Ok(session.user_id)
+}
+
+/// Reject expired and idle sessions, recording activity on live ones
+async fn check_live(auth: &Auth, session: &DbSession, now: i64) -> Result<()> {
+  if session.expires_at < now {
+    return Err(AuthError::InvalidSession);
+  }
+
+  // Idle sessions need re-authentication even before they expire
+  if let Some(timeout) = auth.inner.inactivity_timeout {
+    if now - session.last_activity_at > timeout.as_secs() as i64 {
+      return Err(AuthError::SessionExpired);
+    }
+    auth.inner.db.touch_session(&session.id, now).await?;
+  }
+
+  Ok(())
+}
+
+/// Verify a token from a header and/or cookie value
+///
+/// The preferred source (configured via `AuthBuilder::preferred_token_source`)
+/// is tried first; if it is absent or invalid the other source is tried.
+/// A `Bearer ` prefix on the header value is stripped.
+pub(crate) async fn execute_from_sources(
+  auth: &Auth,
+  header: Option<&str>,
+  cookie: Option<&str>,
+) -> Result<SourceVerification> {
+  let header = header.map(|h| h.strip_prefix("Bearer ").unwrap_or(h));
+
+  let candidates = match auth.inner.preferred_token_source {
+    TokenSource::Header => [(TokenSource::Header, header), (TokenSource::Cookie, cookie)],
+    TokenSource::Cookie => [(TokenSource::Cookie, cookie), (TokenSource::Header, header)],
+  };
+
+  for (source, token) in candidates {
+    let Some(token) = token else {
+      continue;
+    };
+
+    match execute(auth, VerifyRef::new(token)).await {
+      Ok(user) => return Ok(SourceVerification { user, source }),
+      Err(AuthError::InvalidSession | AuthError::InvalidInput(_)) => continue,
+      Err(e) => return Err(e),
+    }
+  }
+
+  Err(AuthError::InvalidSession)
 }
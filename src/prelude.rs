@@ -1,15 +1,40 @@
 pub use crate::auth::Auth;
 pub use crate::builder::AuthBuilder;
-pub use crate::email::{EmailContext, EmailSender};
+pub use crate::email::{CustomEmailContext, EmailContext, EmailSender};
 pub use crate::error::{AuthError, Result};
 pub use crate::operations::{
-  Login, Logout, Register, ResendEmailVerification, SendEmailVerification, Verify, VerifyEmail,
+  AcceptInvitation, AddEmail, BatchVerificationResult, ChangePassword, ConsumeVerification,
+  Invitation, InviteUser, IssueVerification, Login, LoginRef, Logout, LogoutScope, Register,
+  RegisterOutcome, RegisterRef, RegisterWithHash, ResendEmailVerification, RevokeSession,
+  RotateSession, SendEmailVerification, SendStatus, SendVerificationOutcome, SessionPage,
+  SetPrimaryEmail, SourceVerification, TokenKind, TokenSource, VerificationOnRegister,
+  VerificationStatusReport, VerifiedIdentifier, VerifiedSession, Verify, VerifyEmail, VerifyRef,
+  VerifySecondaryEmail,
 };
-pub use crate::types::{Database, Session, User, VerificationToken};
+pub use crate::rate_limit::{InMemoryRateLimitStore, RateLimitStore};
+pub use crate::retry::RetryPolicy;
+pub use crate::strategies::token::TokenType;
+pub use crate::token_store::{StoredToken, TokenStore};
+pub use crate::types::{
+  Account, Database, IdentifierKind, Session, SessionInfo, UnverifiedIdentifier, User, UserEmail,
+  VerificationToken,
+};
+pub use crate::validation::email::EmailValidator;
+pub use crate::validation::limits::InputLimits;
+pub use crate::validation::password::PasswordPolicy;
 
 // Email queue exports (only available with email-queue feature)
+#[cfg(feature = "sqlite")]
+pub use crate::database::sqlite::SqliteConfig;
+
 #[cfg(feature = "email-queue")]
 pub use crate::email_job::{
   EmailJob, EmailJobType, EmailQueue, EmailQueueError, EmailWorker, EmailWorkerConfig,
   EmailWorkerHandle,
 };
+
+// SMS exports (only available with sms feature)
+#[cfg(feature = "sms")]
+pub use crate::operations::{SendPhoneVerification, VerifiedPhone, VerifyPhone};
+#[cfg(feature = "sms")]
+pub use crate::sms::{SmsContext, SmsSender};
@@ -0,0 +1,170 @@
+use crate::error::{AuthError, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Storage for rate-limit counters
+///
+/// Rate limits count hits per key (e.g. `login:alice@example.com`) in a fixed
+/// window. The default [`InMemoryRateLimitStore`] keeps counters in process,
+/// so each app instance limits independently. To share limits across a
+/// cluster, implement this trait over a shared store such as Redis and
+/// install it with `AuthBuilder::rate_limit_store`.
+///
+/// Implementations must satisfy:
+///
+/// - `incr` atomically adds one to the counter for `key` and returns the new
+///   count. The first hit on an absent or expired key starts a new window of
+///   length `window` with a count of 1. Later hits inside the window do not
+///   extend it.
+/// - `get` returns the current count for `key`, or 0 if it is absent or its
+///   window has elapsed. It never modifies the counter.
+/// - `reset` removes the counter for `key`, so the next `incr` starts a fresh
+///   window. Resetting an absent key is not an error.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::{RateLimitStore, Result};
+/// use async_trait::async_trait;
+/// use std::time::Duration;
+///
+/// struct RedisRateLimitStore {
+///     client: redis::Client,
+/// }
+///
+/// #[async_trait]
+/// impl RateLimitStore for RedisRateLimitStore {
+///     async fn incr(&self, key: &str, window: Duration) -> Result<u64> {
+///         let mut conn = self.client.get_multiplexed_async_connection().await?;
+///         // INCR then start the window only if the key has no TTL yet
+///         let (count,): (u64,) = redis::pipe()
+///             .atomic()
+///             .incr(key, 1)
+///             .cmd("PEXPIRE").arg(key).arg(window.as_millis() as u64).arg("NX").ignore()
+///             .query_async(&mut conn)
+///             .await?;
+///         Ok(count)
+///     }
+///
+///     async fn get(&self, key: &str) -> Result<u64> {
+///         let mut conn = self.client.get_multiplexed_async_connection().await?;
+///         Ok(conn.get::<_, Option<u64>>(key).await?.unwrap_or(0))
+///     }
+///
+///     async fn reset(&self, key: &str) -> Result<()> {
+///         let mut conn = self.client.get_multiplexed_async_connection().await?;
+///         conn.del::<_, ()>(key).await?;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+  /// Count a hit on `key`, returning the number of hits in the current window
+  async fn incr(&self, key: &str, window: Duration) -> Result<u64>;
+
+  /// Number of hits on `key` in the current window
+  async fn get(&self, key: &str) -> Result<u64>;
+
+  /// Clear the counter for `key`
+  async fn reset(&self, key: &str) -> Result<()>;
+}
+
+/// In-process [`RateLimitStore`], the default
+///
+/// Counters live in this process only. Expired entries are swept out
+/// whenever the number of keys doubles since the last sweep, so memory stays
+/// proportional to the keys hit within one window.
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+  state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  counters: HashMap<String, Counter>,
+  /// Key count that triggers the next sweep of expired entries
+  sweep_at: usize,
+}
+
+#[derive(Debug)]
+struct Counter {
+  count: u64,
+  window_ends: Instant,
+}
+
+/// Smallest key count that triggers a sweep
+const MIN_SWEEP_AT: usize = 64;
+
+impl InMemoryRateLimitStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn state(&self) -> Result<MutexGuard<'_, State>> {
+    self
+      .state
+      .lock()
+      .map_err(|_| AuthError::InternalError("Rate limit store lock poisoned".to_string()))
+  }
+
+  #[cfg(test)]
+  pub(crate) fn key_count(&self) -> usize {
+    self.state().unwrap().counters.len()
+  }
+}
+
+impl State {
+  /// Drop expired counters once the map has doubled since the last sweep
+  fn sweep_if_due(&mut self, now: Instant) {
+    if self.counters.len() < self.sweep_at.max(MIN_SWEEP_AT) {
+      return;
+    }
+    self.counters.retain(|_, counter| counter.window_ends > now);
+    self.sweep_at = self.counters.len() * 2;
+  }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+  async fn incr(&self, key: &str, window: Duration) -> Result<u64> {
+    let now = Instant::now();
+    let mut state = self.state()?;
+    if !state.counters.contains_key(key) {
+      state.sweep_if_due(now);
+    }
+    let counter = state.counters.entry(key.to_string()).or_insert(Counter {
+      count: 0,
+      window_ends: now + window,
+    });
+
+    if counter.window_ends <= now {
+      counter.count = 0;
+      counter.window_ends = now + window;
+    }
+    counter.count += 1;
+
+    Ok(counter.count)
+  }
+
+  async fn get(&self, key: &str) -> Result<u64> {
+    let now = Instant::now();
+    let state = self.state()?;
+
+    Ok(
+      state
+        .counters
+        .get(key)
+        .filter(|counter| counter.window_ends > now)
+        .map_or(0, |counter| counter.count),
+    )
+  }
+
+  async fn reset(&self, key: &str) -> Result<()> {
+    self.state()?.counters.remove(key);
+    Ok(())
+  }
+}
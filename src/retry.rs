@@ -0,0 +1,150 @@
+//! Retry policy shared by the email worker and database operations
+
+use crate::error::{AuthError, Result};
+use std::future::Future;
+use std::time::Duration;
+
+/// Exponential backoff with jitter
+///
+/// Attempt `n` (counting from 1) that fails waits `base * 2^(n-1)`, capped at
+/// `max` and randomized by +/-`jitter` of the delay, before the next attempt.
+/// At most `max_attempts` attempts are made in total.
+///
+/// Used by the email worker (`EmailWorkerConfig::with_retry_policy`) and, when
+/// configured with `AuthBuilder::db_retry`, for database calls that fail with
+/// a transient error.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let policy = RetryPolicy {
+///     max_attempts: 4,
+///     base: Duration::from_millis(50),
+///     max: Duration::from_secs(1),
+///     jitter: 0.2,
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+  /// Total attempts, including the first
+  pub max_attempts: u32,
+  /// Delay after the first failed attempt
+  pub base: Duration,
+  /// Upper bound on any delay
+  pub max: Duration,
+  /// Fraction of the delay to randomize by, between 0.0 and 1.0
+  pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      base: Duration::from_millis(100),
+      max: Duration::from_secs(2),
+      jitter: 0.1,
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Delay before retrying after `attempt` failed attempts
+  pub fn delay(&self, attempt: u32) -> Duration {
+    let base = self.base.as_millis() as u64;
+    let max = self.max.as_millis() as u64;
+
+    let exponential = base.saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)));
+
+    let clamped = exponential.min(max);
+
+    let jitter_range = (clamped as f64 * self.jitter) as u64;
+    let jitter = if jitter_range > 0 {
+      use rand::Rng;
+      let mut rng = rand::rng();
+      rng.random_range(0..jitter_range * 2) as i64 - jitter_range as i64
+    } else {
+      0
+    };
+
+    Duration::from_millis(((clamped as i64 + jitter).max(0) as u64).min(max))
+  }
+
+  /// Describe why the policy is unusable, if it is
+  ///
+  /// Rejects zero attempts, a zero base delay, a max delay below the base,
+  /// and jitter outside 0.0..=1.0.
+  pub(crate) fn check(&self) -> std::result::Result<(), &'static str> {
+    if self.max_attempts == 0 {
+      return Err("max_attempts must be at least 1");
+    }
+    if self.base.is_zero() {
+      return Err("base delay must be greater than zero");
+    }
+    if self.max < self.base {
+      return Err("max delay must be at least the base delay");
+    }
+    if !(0.0..=1.0).contains(&self.jitter) {
+      return Err("jitter must be between 0.0 and 1.0");
+    }
+    Ok(())
+  }
+
+  /// Run `operation`, retrying it while it fails with a transient error
+  ///
+  /// Other errors, and the error from the last allowed attempt, are returned
+  /// as-is.
+  pub(crate) async fn run<T, F, Fut>(&self, operation: F) -> Result<T>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    self.run_while(operation, AuthError::is_transient).await
+  }
+
+  /// Like [`run`](Self::run), but only retries failures to get a
+  /// connection, which never reached the database
+  ///
+  /// For writes that must not be replayed: an I/O error can arrive after
+  /// the commit, and running the statement again would act on whatever
+  /// happened since.
+  pub(crate) async fn run_unsent<T, F, Fut>(&self, operation: F) -> Result<T>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    self
+      .run_while(operation, |e| {
+        matches!(e, AuthError::DatabaseError(sqlx::Error::PoolTimedOut))
+      })
+      .await
+  }
+
+  async fn run_while<T, F, Fut>(
+    &self,
+    mut operation: F,
+    retryable: impl Fn(&AuthError) -> bool,
+  ) -> Result<T>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+  {
+    let mut attempt = 1;
+    loop {
+      match operation().await {
+        Err(e) if attempt < self.max_attempts && retryable(&e) => {
+          let delay = self.delay(attempt);
+          log::warn!(
+            "Transient database error (attempt {}/{}), retrying in {:?}: {}",
+            attempt,
+            self.max_attempts,
+            delay,
+            e
+          );
+          tokio::time::sleep(delay).await;
+          attempt += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+}
@@ -0,0 +1,18 @@
+use crate::security::signing::TokenSigner;
+
+/// Domain separation, so a CSRF token is never a valid session signature
+const CONTEXT: &str = "authkit-csrf:";
+
+/// CSRF tokens for the double-submit pattern
+///
+/// The token is an HMAC of the session token under a server secret, so it
+/// needs no storage: any request carrying the session can be checked by
+/// recomputing it.
+pub(crate) fn derive(signer: &TokenSigner, session_token: &str) -> String {
+  signer.signature(&format!("{}{}", CONTEXT, session_token))
+}
+
+/// Whether `csrf_token` belongs to `session_token` (constant time)
+pub(crate) fn verify(signer: &TokenSigner, session_token: &str, csrf_token: &str) -> bool {
+  signer.verify_signature(&format!("{}{}", CONTEXT, session_token), csrf_token)
+}
@@ -0,0 +1,75 @@
+use crate::error::{AuthError, Result};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use sha2::{Digest, Sha256};
+
+const NONCE_LENGTH: usize = 12;
+
+/// Reversible at-rest protection for verification tokens (AES-256-GCM)
+///
+/// Encryption is deterministic: the nonce is derived from the key and the
+/// token, so the same token always produces the same ciphertext and can be
+/// looked up by its stored value. Tokens are high-entropy and unique, so the
+/// only thing this reveals is equality of identical tokens.
+#[derive(Clone)]
+pub(crate) struct TokenCipher {
+  cipher: Aes256Gcm,
+  key: [u8; 32],
+}
+
+impl TokenCipher {
+  pub(crate) fn new(key: [u8; 32]) -> Self {
+    Self {
+      cipher: Aes256Gcm::new(&key.into()),
+      key,
+    }
+  }
+
+  fn nonce_for(&self, token: &str) -> [u8; NONCE_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.update(self.key);
+    hasher.update(token.as_bytes());
+    let digest = hasher.finalize();
+
+    let mut nonce = [0u8; NONCE_LENGTH];
+    nonce.copy_from_slice(&digest[..NONCE_LENGTH]);
+    nonce
+  }
+
+  /// Encrypt a token, returning hex-encoded `nonce || ciphertext`
+  pub(crate) fn encrypt(&self, token: &str) -> Result<String> {
+    let nonce = self.nonce_for(token);
+    let ciphertext = self
+      .cipher
+      .encrypt(Nonce::from_slice(&nonce), token.as_bytes())
+      .map_err(|_| AuthError::TokenGenerationError("Failed to encrypt token".to_string()))?;
+
+    let mut stored = nonce.to_vec();
+    stored.extend_from_slice(&ciphertext);
+    Ok(hex::encode(stored))
+  }
+
+  /// Decrypt a value produced by `encrypt`
+  pub(crate) fn decrypt(&self, stored: &str) -> Result<String> {
+    let invalid = || AuthError::InvalidToken("Token could not be decrypted".to_string());
+
+    let bytes = hex::decode(stored).map_err(|_| invalid())?;
+    if bytes.len() <= NONCE_LENGTH {
+      return Err(invalid());
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+    let plaintext = self
+      .cipher
+      .decrypt(Nonce::from_slice(nonce), ciphertext)
+      .map_err(|_| invalid())?;
+
+    String::from_utf8(plaintext).map_err(|_| invalid())
+  }
+}
+
+impl std::fmt::Debug for TokenCipher {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("TokenCipher").finish_non_exhaustive()
+  }
+}
@@ -1,4 +1,8 @@
 //! Security utilities
 
+pub(crate) mod csrf;
+#[cfg(feature = "encrypted-tokens")]
+pub(crate) mod encryption;
+pub(crate) mod signing;
 pub mod timing;
 pub mod tokens;
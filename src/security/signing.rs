@@ -0,0 +1,78 @@
+use crate::error::{AuthError, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Shortest accepted signing secret, matching the HMAC-SHA256 block strength
+pub(crate) const MIN_SECRET_LENGTH: usize = 32;
+
+/// HMAC-SHA256 signatures on session tokens, with secret rotation
+///
+/// Signed tokens look like `{token}.{hex signature}`. New tokens are always
+/// signed with the current secret; tokens signed with any previous secret
+/// keep verifying until that secret is dropped from the list, so rotating
+/// the secret doesn't log everyone out.
+#[derive(Clone)]
+pub(crate) struct TokenSigner {
+  current: Vec<u8>,
+  previous: Vec<Vec<u8>>,
+}
+
+impl TokenSigner {
+  pub(crate) fn new(current: Vec<u8>, previous: Vec<Vec<u8>>) -> Self {
+    Self { current, previous }
+  }
+
+  /// Reject secrets too short to resist brute force; `what` names the
+  /// option in the error
+  pub(crate) fn validate(&self, what: &str) -> Result<()> {
+    let too_short = std::iter::once(&self.current)
+      .chain(&self.previous)
+      .any(|secret| secret.len() < MIN_SECRET_LENGTH);
+    if too_short {
+      return Err(AuthError::ConfigError(format!(
+        "{} must be at least {} bytes",
+        what, MIN_SECRET_LENGTH
+      )));
+    }
+    Ok(())
+  }
+
+  fn mac(secret: &[u8], token: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(token.as_bytes());
+    mac
+  }
+
+  /// Append a signature made with the current secret
+  pub(crate) fn sign(&self, token: &str) -> String {
+    format!("{}.{}", token, self.signature(token))
+  }
+
+  /// Whether `signed` carries a valid signature from the current or a
+  /// previous secret (compared in constant time)
+  pub(crate) fn verify(&self, signed: &str) -> bool {
+    let Some((token, signature)) = signed.rsplit_once('.') else {
+      return false;
+    };
+    self.verify_signature(token, signature)
+  }
+
+  /// Hex signature of `data` made with the current secret
+  pub(crate) fn signature(&self, data: &str) -> String {
+    hex::encode(Self::mac(&self.current, data).finalize().into_bytes())
+  }
+
+  /// Whether `signature` is valid for `data` under the current or a previous
+  /// secret (compared in constant time)
+  pub(crate) fn verify_signature(&self, data: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+      return false;
+    };
+
+    std::iter::once(&self.current)
+      .chain(&self.previous)
+      .any(|secret| Self::mac(secret, data).verify_slice(&signature).is_ok())
+  }
+}
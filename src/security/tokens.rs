@@ -3,18 +3,132 @@ use rand::RngCore;
 const TOKEN_LENGTH: usize = 32;
 const ID_LENGTH: usize = 16;
 
-/// Generate a secure random token for sessions
-pub fn generate_token() -> String {
-  let mut rng = rand::rng();
-  let mut bytes = vec![0u8; TOKEN_LENGTH];
-  rng.fill_bytes(&mut bytes);
-  hex::encode(bytes)
+/// Source of randomness for session tokens, verification tokens and ids
+///
+/// AuthKit uses `OsTokenGenerator` (the operating system's secure RNG) unless
+/// another generator is installed with `AuthBuilder::token_generator`. Custom
+/// generators are intended for tests that need reproducible tokens; production
+/// code should keep the default.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::TokenGenerator;
+/// use rand::{rngs::StdRng, RngCore, SeedableRng};
+/// use std::sync::Mutex;
+///
+/// struct SeededGenerator(Mutex<StdRng>);
+///
+/// impl TokenGenerator for SeededGenerator {
+///     fn fill_bytes(&self, dest: &mut [u8]) {
+///         self.0.lock().unwrap().fill_bytes(dest);
+///     }
+/// }
+/// ```
+pub trait TokenGenerator: Send + Sync {
+  /// Fill `dest` with random bytes
+  fn fill_bytes(&self, dest: &mut [u8]);
+
+  /// Generate a secure random token for sessions
+  fn generate_token(&self) -> String {
+    let mut bytes = [0u8; TOKEN_LENGTH];
+    self.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+  }
+
+  /// Generate a unique ID for a new user, session, account or token row
+  ///
+  /// IDs are opaque strings that AuthKit only stores and compares; nothing
+  /// depends on their format or length. Override this to match an existing
+  /// schema's ids, e.g. numeric strings from a sequence, as long as every
+  /// call returns a fresh value.
+  fn generate_id(&self) -> String {
+    let mut bytes = [0u8; ID_LENGTH];
+    self.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+  }
+
+  /// Generate a random numeric one-time code (e.g. "042917")
+  ///
+  /// Codes are short and low-entropy, so they must always be scoped to an
+  /// identifier and compared in constant time (see `TokenStrategy::verify_code`).
+  fn generate_numeric_code(&self, digits: usize) -> String {
+    let mut code = String::with_capacity(digits);
+    let mut byte = [0u8; 1];
+    while code.len() < digits {
+      self.fill_bytes(&mut byte);
+      // Reject 250..=255 so every digit is equally likely
+      if byte[0] < 250 {
+        code.push(char::from(b'0' + byte[0] % 10));
+      }
+    }
+    code
+  }
+}
+
+/// Default token generator backed by the operating system's secure RNG
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsTokenGenerator;
+
+impl TokenGenerator for OsTokenGenerator {
+  fn fill_bytes(&self, dest: &mut [u8]) {
+    rand::rng().fill_bytes(dest);
+  }
+}
+
+/// SHA-256 hex digest of a token, for storing tokens at rest
+pub(crate) fn hash_token(token: &str) -> String {
+  TokenHashAlgo::Sha256.hash(token)
+}
+
+/// How verification tokens are hashed before they are stored
+///
+/// Selected with `AuthBuilder::token_hash`. Changing the algorithm
+/// invalidates every outstanding verification token, since stored hashes no
+/// longer match.
+///
+/// `HmacSha256` keys the hash with a secret kept outside the database, so
+/// hashes stolen from a dump can't be checked against guessed tokens or
+/// codes without the key as well.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum TokenHashAlgo {
+  /// SHA-256 (the default)
+  #[default]
+  Sha256,
+  /// SHA-512
+  Sha512,
+  /// HMAC-SHA256 keyed with `secret` (at least 32 bytes)
+  HmacSha256 { secret: Vec<u8> },
+}
+
+impl TokenHashAlgo {
+  /// Hex digest of `token` under this algorithm
+  pub(crate) fn hash(&self, token: &str) -> String {
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256, Sha512};
+
+    match self {
+      TokenHashAlgo::Sha256 => hex::encode(Sha256::digest(token.as_bytes())),
+      TokenHashAlgo::Sha512 => hex::encode(Sha512::digest(token.as_bytes())),
+      TokenHashAlgo::HmacSha256 { secret } => {
+        let mut mac =
+          Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(token.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+      }
+    }
+  }
 }
 
-/// Generate a secure random ID for users
-pub fn generate_id() -> String {
-  let mut rng = rand::rng();
-  let mut bytes = vec![0u8; ID_LENGTH];
-  rng.fill_bytes(&mut bytes);
-  hex::encode(bytes)
+impl std::fmt::Debug for TokenHashAlgo {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TokenHashAlgo::Sha256 => f.write_str("Sha256"),
+      TokenHashAlgo::Sha512 => f.write_str("Sha512"),
+      TokenHashAlgo::HmacSha256 { .. } => f
+        .debug_struct("HmacSha256")
+        .field("secret", &"<redacted>")
+        .finish(),
+    }
+  }
 }
@@ -0,0 +1,41 @@
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Context provided to SMS senders containing the verification code to send
+#[derive(Debug, Clone)]
+pub struct SmsContext {
+  /// The recipient's phone number (E.164, e.g. `+15555550123`)
+  pub phone: String,
+  /// The numeric verification code (plaintext)
+  pub code: String,
+  /// When the code expires (Unix timestamp)
+  pub expires_at: i64,
+}
+
+/// Trait for sending verification codes by SMS
+///
+/// Implement this trait to deliver phone verification codes through your SMS
+/// provider. AuthKit calls it from `send_phone_verification` after issuing
+/// the code.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::{Result, SmsContext, SmsSender};
+/// use async_trait::async_trait;
+///
+/// struct TwilioSender;
+///
+/// #[async_trait]
+/// impl SmsSender for TwilioSender {
+///     async fn send_verification_sms(&self, context: SmsContext) -> Result<()> {
+///         twilio::send(&context.phone, &format!("Your code is {}", context.code)).await?;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+  /// Send a verification code to a phone number
+  async fn send_verification_sms(&self, context: SmsContext) -> Result<()>;
+}
@@ -3,11 +3,15 @@ use crate::error::{AuthError, Result};
 use crate::strategies::password::PasswordStrategy;
 use argon2::{
   password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-  Argon2,
+  Algorithm, Argon2, Params, Version,
 };
 use async_trait::async_trait;
 
 /// Argon2id password hashing strategy
+///
+/// Hashing and verification are CPU-bound (tens of milliseconds each with
+/// the default parameters), so both run on tokio's blocking thread pool via
+/// `spawn_blocking` instead of stalling the async executor.
 #[derive(Default)]
 pub(crate) struct Argon2Strategy {
   argon2: Argon2<'static>,
@@ -16,30 +20,74 @@ pub(crate) struct Argon2Strategy {
 #[async_trait]
 impl PasswordStrategy for Argon2Strategy {
   async fn hash_password(&self, password: &str) -> Result<String> {
-    // Generate salt
-    let salt = SaltString::generate(&mut OsRng);
+    let argon2 = self.argon2.clone();
+    let password = password.to_owned();
 
-    // Hash password
-    let password_hash = self
-      .argon2
-      .hash_password(password.as_bytes(), &salt)
-      .map_err(|e| AuthError::PasswordHashingError(e.to_string()))?;
+    run_blocking(move || {
+      // Generate salt
+      let salt = SaltString::generate(&mut OsRng);
 
-    Ok(password_hash.to_string())
+      // Hash password
+      let password_hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| AuthError::PasswordHashingError(e.to_string()))?;
+
+      Ok(password_hash.to_string())
+    })
+    .await
   }
 
   async fn verify_password(&self, password: &str, hash: &str) -> Result<bool> {
-    // Parse stored hash
-    let parsed_hash =
-      PasswordHash::new(hash).map_err(|e| AuthError::PasswordHashingError(e.to_string()))?;
-
-    // Verify password (timing-safe comparison built-in)
-    match self
-      .argon2
-      .verify_password(password.as_bytes(), &parsed_hash)
-    {
-      Ok(()) => Ok(true),
-      Err(_) => Ok(false),
-    }
+    let argon2 = self.argon2.clone();
+    let password = password.to_owned();
+    let hash = hash.to_owned();
+
+    run_blocking(move || {
+      // Parse stored hash
+      let parsed_hash =
+        PasswordHash::new(&hash).map_err(|e| AuthError::PasswordHashingError(e.to_string()))?;
+
+      // Verify password (timing-safe comparison built-in). The variant,
+      // version, and cost parameters come from the PHC string, so argon2i
+      // and argon2d hashes verify too.
+      match argon2.verify_password(password.as_bytes(), &parsed_hash) {
+        Ok(()) => Ok(true),
+        Err(_) => Ok(false),
+      }
+    })
+    .await
   }
+
+  fn needs_rehash(&self, hash: &str) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+      return false;
+    };
+
+    let algorithm = Algorithm::try_from(parsed_hash.algorithm).ok();
+    let version = parsed_hash.version.and_then(|v| Version::try_from(v).ok());
+    let params = Params::try_from(&parsed_hash).ok();
+    let current = self.argon2.params();
+
+    algorithm != Some(Algorithm::Argon2id)
+      || version != Some(Version::V0x13)
+      || params.map_or(true, |p| {
+        p.m_cost() != current.m_cost()
+          || p.t_cost() != current.t_cost()
+          || p.p_cost() != current.p_cost()
+      })
+  }
+}
+
+/// Run a CPU-bound closure on the blocking thread pool
+///
+/// A task that panics or is cancelled is an `InternalError`, not a
+/// `PasswordHashingError`: login treats the latter as a bad stored hash.
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+  F: FnOnce() -> Result<T> + Send + 'static,
+  T: Send + 'static,
+{
+  tokio::task::spawn_blocking(f)
+    .await
+    .map_err(|e| AuthError::InternalError(format!("Hashing task failed: {}", e)))?
 }
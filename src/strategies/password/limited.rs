@@ -0,0 +1,48 @@
+use super::PasswordStrategy;
+use crate::error::{AuthError, Result};
+use async_trait::async_trait;
+use tokio::sync::Semaphore;
+
+/// Password strategy wrapper capping how many hashes run at once
+///
+/// Installed by `AuthBuilder::max_concurrent_hashes`. Hashing and
+/// verification wait for a permit, so a burst of logins queues up instead
+/// of running every (memory-hungry) hash in parallel.
+pub(crate) struct LimitedPasswordStrategy {
+  inner: Box<dyn PasswordStrategy>,
+  permits: Semaphore,
+}
+
+impl LimitedPasswordStrategy {
+  pub(crate) fn new(inner: Box<dyn PasswordStrategy>, max_concurrent: usize) -> Self {
+    Self {
+      inner,
+      permits: Semaphore::new(max_concurrent),
+    }
+  }
+
+  async fn permit(&self) -> Result<tokio::sync::SemaphorePermit<'_>> {
+    self
+      .permits
+      .acquire()
+      .await
+      .map_err(|e| AuthError::InternalError(e.to_string()))
+  }
+}
+
+#[async_trait]
+impl PasswordStrategy for LimitedPasswordStrategy {
+  async fn hash_password(&self, password: &str) -> Result<String> {
+    let _permit = self.permit().await?;
+    self.inner.hash_password(password).await
+  }
+
+  async fn verify_password(&self, password: &str, hash: &str) -> Result<bool> {
+    let _permit = self.permit().await?;
+    self.inner.verify_password(password, hash).await
+  }
+
+  fn needs_rehash(&self, hash: &str) -> bool {
+    self.inner.needs_rehash(hash)
+  }
+}
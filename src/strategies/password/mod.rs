@@ -2,6 +2,7 @@
 
 #[cfg(feature = "argon2")]
 pub mod argon2_strategy;
+pub(crate) mod limited;
 
 use crate::error::Result;
 use async_trait::async_trait;
@@ -14,6 +15,10 @@ pub(crate) trait PasswordStrategy: Send + Sync {
 
   /// Verify a password against a hash (timing-safe)
   async fn verify_password(&self, password: &str, hash: &str) -> Result<bool>;
+
+  /// Whether a stored hash was produced with different settings than the
+  /// strategy's current ones and should be replaced after a successful login
+  fn needs_rehash(&self, hash: &str) -> bool;
 }
 
 /// Public enum for selecting password strategy
@@ -90,7 +95,7 @@ impl PasswordStrategyType {
       #[cfg(feature = "bcrypt")]
       Self::Bcrypt => {
         // bcrypt strategy not yet implemented
-        Err(crate::error::AuthError::InternalError(
+        Err(crate::error::AuthError::ConfigError(
           "bcrypt password strategy is not yet implemented".to_string(),
         ))
       }
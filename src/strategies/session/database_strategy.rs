@@ -1,33 +1,130 @@
 use crate::database::models::DbSession;
 use crate::database::DatabaseTrait;
 use crate::error::Result;
-use crate::strategies::session::SessionStrategy;
+use crate::security::tokens::hash_token;
+use crate::strategies::session::{CreateSessionOpts, SessionStrategy, SessionStrategyOptions};
 use async_trait::async_trait;
 
 /// Database-backed session strategy
-pub(crate) struct DatabaseSessionStrategy;
+///
+/// With `hash_tokens`, only the SHA-256 hash of each session token is
+/// stored, so a database dump can't be replayed as live sessions. Sessions
+/// returned to callers still carry the plaintext token they presented.
+///
+/// With a signer, issued tokens carry an HMAC signature that is checked
+/// before any lookup.
+pub(crate) struct DatabaseSessionStrategy {
+  options: SessionStrategyOptions,
+}
+
+impl DatabaseSessionStrategy {
+  pub(crate) fn new(options: SessionStrategyOptions) -> Self {
+    Self { options }
+  }
+
+  /// The token handed to the client for a freshly generated one
+  fn issued_token(&self, token: &str) -> String {
+    match &self.options.signer {
+      Some(signer) => signer.sign(token),
+      None => token.to_string(),
+    }
+  }
+}
 
 #[async_trait]
 impl SessionStrategy for DatabaseSessionStrategy {
+  fn stored_token(&self, token: &str) -> String {
+    if self.options.hash_tokens {
+      hash_token(token)
+    } else {
+      token.to_string()
+    }
+  }
+
+  fn accepts_token(&self, token: &str) -> bool {
+    self
+      .options
+      .signer
+      .as_ref()
+      .map_or(true, |signer| signer.verify(token))
+  }
+
   async fn create_session(
     &self,
     db: &dyn DatabaseTrait,
-    id: &str,
-    token: &str,
-    user_id: &str,
-    expires_at: i64,
-    ip_address: Option<&str>,
-    user_agent: Option<&str>,
-  ) -> Result<()> {
-    db.create_session(id, token, user_id, expires_at, ip_address, user_agent)
-      .await
+    opts: CreateSessionOpts<'_>,
+  ) -> Result<DbSession> {
+    let token = self.issued_token(opts.token);
+    let stored = self.stored_token(&token);
+    let token_prefix = self
+      .options
+      .token_prefix_len
+      .map(|len| stored.chars().take(len).collect());
+    let mut session = DbSession {
+      id: opts.id.to_string(),
+      user_id: opts.user_id.to_string(),
+      token: stored,
+      expires_at: opts.expires_at,
+      created_at: opts.created_at,
+      ip_address: opts.ip_address.map(str::to_string),
+      user_agent: opts.user_agent.map(str::to_string),
+      remember_me: opts.remember_me,
+      last_activity_at: opts.created_at,
+      token_prefix,
+    };
+
+    db.create_session(&session).await?;
+
+    session.token = token;
+    Ok(session)
   }
 
   async fn find_session(&self, db: &dyn DatabaseTrait, token: &str) -> Result<Option<DbSession>> {
-    db.find_session(token).await
+    if !self.accepts_token(token) {
+      return Ok(None);
+    }
+    let session = db.find_session(&self.stored_token(token)).await?;
+
+    Ok(session.map(|session| DbSession {
+      token: token.to_string(),
+      ..session
+    }))
   }
 
   async fn delete_session(&self, db: &dyn DatabaseTrait, token: &str) -> Result<()> {
-    db.delete_session(token).await
+    if !self.accepts_token(token) {
+      return Ok(());
+    }
+    db.delete_session(&self.stored_token(token)).await
+  }
+
+  async fn rotate_session(
+    &self,
+    db: &dyn DatabaseTrait,
+    old_token: &str,
+    new_token: &str,
+  ) -> Result<Option<DbSession>> {
+    if !self.accepts_token(old_token) {
+      return Ok(None);
+    }
+    let new_token = self.issued_token(new_token);
+
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    let rotated = db
+      .rotate_session_token(
+        &self.stored_token(old_token),
+        &self.stored_token(&new_token),
+        now,
+      )
+      .await?;
+    if !rotated {
+      return Ok(None);
+    }
+
+    self.find_session(db, &new_token).await
   }
 }
@@ -7,6 +7,20 @@ use crate::database::DatabaseTrait;
 use crate::error::Result;
 use async_trait::async_trait;
 
+/// Options for creating a session
+#[derive(Debug, Clone)]
+pub(crate) struct CreateSessionOpts<'a> {
+  pub id: &'a str,
+  pub token: &'a str,
+  pub user_id: &'a str,
+  pub expires_at: i64,
+  pub created_at: i64,
+  pub ip_address: Option<&'a str>,
+  pub user_agent: Option<&'a str>,
+  /// Whether this is a long-lived "remember me" session
+  pub remember_me: bool,
+}
+
 /// Session management strategy trait (internal)
 #[async_trait]
 pub(crate) trait SessionStrategy: Send + Sync {
@@ -14,19 +28,29 @@ pub(crate) trait SessionStrategy: Send + Sync {
   async fn create_session(
     &self,
     db: &dyn DatabaseTrait,
-    id: &str,
-    token: &str,
-    user_id: &str,
-    expires_at: i64,
-    ip_address: Option<&str>,
-    user_agent: Option<&str>,
-  ) -> Result<()>;
+    opts: CreateSessionOpts<'_>,
+  ) -> Result<DbSession>;
+
+  /// The value stored in the `sessions.token` column for a token
+  fn stored_token(&self, token: &str) -> String;
+
+  /// Whether a presented token is well-formed for this strategy (e.g. its
+  /// signature checks out), so obviously bad tokens skip the database
+  fn accepts_token(&self, token: &str) -> bool;
 
   /// Find a session by token
   async fn find_session(&self, db: &dyn DatabaseTrait, token: &str) -> Result<Option<DbSession>>;
 
   /// Delete a session
   async fn delete_session(&self, db: &dyn DatabaseTrait, token: &str) -> Result<()>;
+
+  /// Swap a session's token for a new one, invalidating the old token
+  async fn rotate_session(
+    &self,
+    db: &dyn DatabaseTrait,
+    old_token: &str,
+    new_token: &str,
+  ) -> Result<Option<DbSession>>;
 }
 
 /// Public enum for selecting session strategy
@@ -38,9 +62,22 @@ pub enum SessionStrategyType {
 }
 
 impl SessionStrategyType {
-  pub(crate) fn create_strategy(self) -> Box<dyn SessionStrategy> {
+  pub(crate) fn create_strategy(self, options: SessionStrategyOptions) -> Box<dyn SessionStrategy> {
     match self {
-      Self::Database => Box::new(database_strategy::DatabaseSessionStrategy),
+      Self::Database => Box::new(database_strategy::DatabaseSessionStrategy::new(options)),
     }
   }
 }
+
+/// Builder settings threaded into the session strategy
+#[derive(Clone)]
+pub(crate) struct SessionStrategyOptions {
+  /// Store token hashes instead of plaintext tokens
+  pub(crate) hash_tokens: bool,
+
+  /// Length of the `token_prefix` lookup hint to store, if any
+  pub(crate) token_prefix_len: Option<usize>,
+
+  /// Sign issued tokens and reject presented tokens without a valid signature
+  pub(crate) signer: Option<crate::security::signing::TokenSigner>,
+}
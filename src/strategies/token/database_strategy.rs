@@ -1,25 +1,93 @@
-use super::{Token, TokenStrategy, TokenType, VerifiedToken};
+use super::{Token, TokenStrategy, TokenStrategyOptions, TokenType, VerifiedToken};
 use crate::database::DatabaseTrait;
 use crate::error::{AuthError, Result};
-use crate::security::tokens;
+use crate::security::timing;
+use crate::token_store::{SqlTokenStore, StoredToken, TokenStore};
 use async_trait::async_trait;
+use std::time::Duration;
+
+/// Wrong codes allowed per identifier before its codes are invalidated
+const MAX_CODE_ATTEMPTS: u64 = 5;
+
+/// Window the failed code attempts are counted in
+const CODE_ATTEMPT_WINDOW: Duration = Duration::from_secs(15 * 60);
 
 /// Database-backed token strategy
 ///
-/// This strategy stores tokens in the verification table and handles:
+/// This strategy stores tokens in the verification table (or the configured
+/// `TokenStore`) and handles:
 /// - Email verification tokens
 /// - Password reset tokens
 /// - Magic link tokens
-pub(crate) struct DatabaseTokenStrategy;
+///
+/// Tokens are stored hashed with the configured `TokenHashAlgo` (SHA-256 by
+/// default), or encrypted with a configured key
+/// when the `encrypted-tokens` feature is in use.
+pub(crate) struct DatabaseTokenStrategy {
+  options: TokenStrategyOptions,
+}
 
 impl DatabaseTokenStrategy {
-  /// Hash a token using SHA-256 for secure storage
-  #[allow(dead_code)]
-  fn hash_token(token: &str) -> String {
-    use sha2::{Digest, Sha256};
-    let mut hasher = Sha256::new();
-    hasher.update(token.as_bytes());
-    hex::encode(hasher.finalize())
+  pub(crate) fn new(options: TokenStrategyOptions) -> Self {
+    Self { options }
+  }
+
+  /// The configured token store, or the `verification` table of `db`
+  fn store<'a>(&'a self, db: &'a dyn DatabaseTrait) -> Store<'a> {
+    match &self.options.store {
+      Some(store) => Store::External(store.as_ref()),
+      None => Store::Sql(SqlTokenStore(db)),
+    }
+  }
+
+  /// Compute the value stored in (and looked up from) the `token_hash` column
+  fn protect(&self, token: &str) -> Result<String> {
+    #[cfg(feature = "encrypted-tokens")]
+    if let Some(cipher) = &self.options.cipher {
+      return cipher.encrypt(token);
+    }
+
+    Ok(self.options.hash_algo.hash(token))
+  }
+
+  /// Find a token by its protected value, only if it has `token_type`
+  async fn find_typed(
+    &self,
+    db: &dyn DatabaseTrait,
+    token_hash: &str,
+    token_type: TokenType,
+  ) -> Result<Option<StoredToken>> {
+    Ok(
+      self
+        .store(db)
+        .find(token_hash)
+        .await?
+        .filter(|token| token.token_type == token_type.as_str()),
+    )
+  }
+
+  /// Protect a one-time code bound to its identifier
+  fn protect_code(&self, identifier: &str, code: &str) -> Result<String> {
+    self.protect(&format!("{}:{}", identifier, code))
+  }
+
+  fn check_usable(used_at: Option<i64>, expires_at: i64) -> Result<()> {
+    if used_at.is_some() {
+      return Err(AuthError::TokenAlreadyUsed(
+        "This token has already been used".to_string(),
+      ));
+    }
+
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    if expires_at < now {
+      return Err(AuthError::TokenExpired("Token has expired".to_string()));
+    }
+
+    Ok(())
   }
 }
 
@@ -28,15 +96,15 @@ impl TokenStrategy for DatabaseTokenStrategy {
   async fn generate_token(
     &self,
     db: &dyn DatabaseTrait,
-    user_id: &str,
+    user_id: Option<&str>,
     identifier: &str,
     token_type: TokenType,
     expires_in_seconds: i64,
   ) -> Result<Token> {
     // Generate cryptographically secure random token
-    let token = tokens::generate_token();
-    let token_hash = Self::hash_token(&token);
-    let id = tokens::generate_id();
+    let token = self.options.token_generator.generate_token();
+    let token_hash = self.protect(&token)?;
+    let id = self.options.token_generator.generate_id();
 
     let now = std::time::SystemTime::now()
       .duration_since(std::time::UNIX_EPOCH)
@@ -45,21 +113,23 @@ impl TokenStrategy for DatabaseTokenStrategy {
 
     let expires_at = now + expires_in_seconds;
 
-    // Store token in verification table
-    db.create_verification(
-      &id,
-      Some(user_id),
-      identifier,
-      &token_hash,
-      token_type.as_str(),
-      expires_at,
-      now,
-    )
-    .await?;
+    self
+      .store(db)
+      .create(&StoredToken {
+        id: id.clone(),
+        user_id: user_id.map(str::to_string),
+        identifier: identifier.to_string(),
+        token_hash: token_hash.clone(),
+        token_type: token_type.as_str().to_string(),
+        expires_at,
+        created_at: now,
+        used_at: None,
+      })
+      .await?;
 
     Ok(Token {
       id,
-      user_id: Some(user_id.to_string()),
+      user_id: user_id.map(str::to_string),
       identifier: identifier.to_string(),
       token_hash,
       token,
@@ -75,31 +145,150 @@ impl TokenStrategy for DatabaseTokenStrategy {
     token: &str,
     token_type: TokenType,
   ) -> Result<VerifiedToken> {
-    let token_hash = Self::hash_token(token);
+    let token_hash = self.protect(token)?;
 
-    // Find token in verification table
-    let db_token = db
-      .find_verification(&token_hash, token_type.as_str())
+    let db_token = self
+      .find_typed(db, &token_hash, token_type)
       .await?
       .ok_or_else(|| AuthError::InvalidToken("Token not found or invalid".to_string()))?;
 
-    // Check if token has already been used
-    if db_token.used_at.is_some() {
-      return Err(AuthError::TokenAlreadyUsed(
-        "This token has already been used".to_string(),
-      ));
-    }
+    // Check the token hasn't been used or expired
+    Self::check_usable(db_token.used_at, db_token.expires_at)?;
+
+    Ok(VerifiedToken {
+      id: db_token.id,
+      user_id: db_token.user_id,
+      identifier: db_token.identifier,
+      token_type,
+    })
+  }
+
+  async fn find_token_type(&self, db: &dyn DatabaseTrait, token: &str) -> Result<Option<String>> {
+    let token_hash = self.protect(token)?;
+
+    Ok(
+      self
+        .store(db)
+        .find(&token_hash)
+        .await?
+        .map(|verification| verification.token_type),
+    )
+  }
+
+  async fn find_token_user_id(
+    &self,
+    db: &dyn DatabaseTrait,
+    token: &str,
+    token_type: TokenType,
+  ) -> Result<Option<String>> {
+    let token_hash = self.protect(token)?;
+
+    Ok(
+      self
+        .find_typed(db, &token_hash, token_type)
+        .await?
+        .and_then(|verification| verification.user_id),
+    )
+  }
+
+  async fn generate_code(
+    &self,
+    db: &dyn DatabaseTrait,
+    user_id: &str,
+    identifier: &str,
+    token_type: TokenType,
+    digits: usize,
+    expires_in_seconds: i64,
+  ) -> Result<Token> {
+    let code = self.options.token_generator.generate_numeric_code(digits);
+    let token_hash = self.protect_code(identifier, &code)?;
+    let id = self.options.token_generator.generate_id();
 
-    // Check if token has expired
     let now = std::time::SystemTime::now()
       .duration_since(std::time::UNIX_EPOCH)
       .unwrap()
       .as_secs() as i64;
 
-    if db_token.expires_at < now {
-      return Err(AuthError::TokenExpired("Token has expired".to_string()));
+    let expires_at = now + expires_in_seconds;
+
+    self
+      .store(db)
+      .create(&StoredToken {
+        id: id.clone(),
+        user_id: Some(user_id.to_string()),
+        identifier: identifier.to_string(),
+        token_hash: token_hash.clone(),
+        token_type: token_type.as_str().to_string(),
+        expires_at,
+        created_at: now,
+        used_at: None,
+      })
+      .await?;
+
+    Ok(Token {
+      id,
+      user_id: Some(user_id.to_string()),
+      identifier: identifier.to_string(),
+      token_hash,
+      token: code,
+      token_type,
+      expires_at,
+      created_at: now,
+    })
+  }
+
+  async fn verify_code(
+    &self,
+    db: &dyn DatabaseTrait,
+    identifier: &str,
+    code: &str,
+    token_type: TokenType,
+  ) -> Result<VerifiedToken> {
+    let attempts = &self.options.rate_limit_store;
+    let attempts_key = format!("code:{}:{}", token_type.as_str(), identifier);
+    if attempts.get(&attempts_key).await? >= MAX_CODE_ATTEMPTS {
+      return Err(AuthError::RateLimitExceeded(
+        "Too many invalid codes, request a new one later".to_string(),
+      ));
     }
 
+    let code_hash = self.protect_code(identifier, code)?;
+
+    let store = self.store(db);
+    let candidates = store
+      .find_by_identifier(identifier, token_type.as_str())
+      .await?;
+
+    // Compare against every candidate without short-circuiting, so timing
+    // doesn't reveal how many codes exist or which one matched
+    let mut matched = None;
+    for candidate in &candidates {
+      if timing::constant_time_compare(&candidate.token_hash, &code_hash) && matched.is_none() {
+        matched = Some(candidate.clone());
+      }
+    }
+
+    let Some(db_token) = matched else {
+      if attempts.incr(&attempts_key, CODE_ATTEMPT_WINDOW).await? >= MAX_CODE_ATTEMPTS {
+        // Burn the outstanding codes, so guessing can't resume once the
+        // window has passed; the user has to request a new code
+        let now = std::time::SystemTime::now()
+          .duration_since(std::time::UNIX_EPOCH)
+          .unwrap()
+          .as_secs() as i64;
+        for candidate in candidates.iter().filter(|c| c.used_at.is_none()) {
+          store.mark_used(&candidate.token_hash, now).await?;
+        }
+      }
+
+      return Err(AuthError::InvalidToken(
+        "Token not found or invalid".to_string(),
+      ));
+    };
+
+    Self::check_usable(db_token.used_at, db_token.expires_at)?;
+    attempts.reset(&attempts_key).await?;
+
     Ok(VerifiedToken {
       id: db_token.id,
       user_id: db_token.user_id,
@@ -108,18 +297,83 @@ impl TokenStrategy for DatabaseTokenStrategy {
     })
   }
 
+  async fn mark_code_as_used(
+    &self,
+    db: &dyn DatabaseTrait,
+    identifier: &str,
+    code: &str,
+  ) -> Result<()> {
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    claim_verification(&*self.store(db), &self.protect_code(identifier, code)?, now).await
+  }
+
+  fn stored_token(&self, token: &str) -> Result<String> {
+    self.protect(token)
+  }
+
+  fn uses_verification_table(&self) -> bool {
+    self.options.store.is_none()
+  }
+
   async fn mark_token_as_used(&self, db: &dyn DatabaseTrait, token: &str) -> Result<()> {
-    let token_hash = Self::hash_token(token);
+    let token_hash = self.protect(token)?;
     let now = std::time::SystemTime::now()
       .duration_since(std::time::UNIX_EPOCH)
       .unwrap()
       .as_secs() as i64;
 
-    db.mark_verification_used(&token_hash, now).await
+    claim_verification(&*self.store(db), &token_hash, now).await
+  }
+
+  #[cfg(feature = "encrypted-tokens")]
+  fn decrypt_stored_token(&self, stored: &str) -> Result<String> {
+    let cipher =
+      self.options.cipher.as_ref().ok_or_else(|| {
+        AuthError::InternalError("Token encryption is not configured".to_string())
+      })?;
+
+    cipher.decrypt(stored)
   }
 
   async fn clean_expired_tokens(&self, db: &dyn DatabaseTrait) -> Result<()> {
-    db.delete_expired_verifications().await?;
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    self.store(db).delete_expired(now).await?;
+    Ok(())
+  }
+}
+
+/// Atomically claim a verification row; losing a race reads as already used
+async fn claim_verification(store: &dyn TokenStore, token_hash: &str, now: i64) -> Result<()> {
+  if store.mark_used(token_hash, now).await? {
     Ok(())
+  } else {
+    Err(AuthError::TokenAlreadyUsed(
+      "This token has already been used".to_string(),
+    ))
+  }
+}
+
+/// The token store in use for one call
+enum Store<'a> {
+  Sql(SqlTokenStore<'a>),
+  External(&'a dyn TokenStore),
+}
+
+impl<'a> std::ops::Deref for Store<'a> {
+  type Target = dyn TokenStore + 'a;
+
+  fn deref(&self) -> &Self::Target {
+    match self {
+      Store::Sql(store) => store,
+      Store::External(store) => *store,
+    }
   }
 }
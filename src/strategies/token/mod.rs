@@ -1,5 +1,7 @@
-use crate::error::Result;
+use crate::error::{AuthError, Result};
+use crate::security::tokens::{TokenGenerator, TokenHashAlgo};
 use async_trait::async_trait;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy, Default)]
 pub enum TokenStrategyType {
@@ -8,28 +10,107 @@ pub enum TokenStrategyType {
 }
 
 impl TokenStrategyType {
-  pub(crate) fn create_strategy(self) -> Box<dyn TokenStrategy> {
+  pub(crate) fn create_strategy(self, options: TokenStrategyOptions) -> Box<dyn TokenStrategy> {
     match self {
-      TokenStrategyType::Database => Box::new(database_strategy::DatabaseTokenStrategy),
+      TokenStrategyType::Database => {
+        Box::new(database_strategy::DatabaseTokenStrategy::new(options))
+      }
     }
   }
 }
 
+/// Builder settings threaded into the token strategy
+#[derive(Clone)]
+pub(crate) struct TokenStrategyOptions {
+  /// Source of randomness for tokens and ids
+  pub(crate) token_generator: Arc<dyn TokenGenerator>,
+
+  /// Hash applied to tokens before they are stored
+  pub(crate) hash_algo: TokenHashAlgo,
+
+  /// Where tokens are kept instead of the `verification` table, if set
+  pub(crate) store: Option<Arc<dyn crate::token_store::TokenStore>>,
+
+  /// Counts failed one-time code attempts per identifier
+  pub(crate) rate_limit_store: Arc<dyn crate::rate_limit::RateLimitStore>,
+
+  /// Encrypt tokens at rest with this cipher instead of hashing them
+  #[cfg(feature = "encrypted-tokens")]
+  pub(crate) cipher: Option<crate::security::encryption::TokenCipher>,
+}
+
+/// Purpose of a verification token
+///
+/// Tokens only verify against the purpose they were issued for.
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq, Eq, Copy)]
 pub enum TokenType {
   EmailVerification,
   PasswordReset,
   MagicLink,
+  SecondaryEmailVerification,
+  PhoneVerification,
+  /// Invitation to set a password, see `Auth::invite_user`
+  Invitation,
+  /// One-time code in a verification link, see
+  /// `Auth::issue_verification_link_code`
+  VerificationLinkCode,
+  /// Application-defined purpose, e.g. `Custom("whatsapp_verification")`
+  ///
+  /// The name must be non-empty and differ from every built-in purpose's
+  /// `as_str`, or `Auth::issue_verification` and `Auth::consume_verification`
+  /// reject it.
+  Custom(&'static str),
 }
 
 impl TokenType {
+  /// Every purpose except `Custom`
+  const BUILT_IN: [TokenType; 7] = [
+    TokenType::EmailVerification,
+    TokenType::PasswordReset,
+    TokenType::MagicLink,
+    TokenType::SecondaryEmailVerification,
+    TokenType::PhoneVerification,
+    TokenType::Invitation,
+    TokenType::VerificationLinkCode,
+  ];
+
+  /// Fail with `InvalidInput` for a `Custom` purpose with an empty or
+  /// built-in name, which would mix its tokens with another purpose's
+  pub(crate) fn validate(&self) -> Result<()> {
+    let TokenType::Custom(name) = self else {
+      return Ok(());
+    };
+
+    if name.trim().is_empty() {
+      return Err(AuthError::InvalidInput(
+        "Custom token type name must not be empty".to_string(),
+      ));
+    }
+    if Self::BUILT_IN
+      .iter()
+      .any(|built_in| built_in.as_str() == *name)
+    {
+      return Err(AuthError::InvalidInput(format!(
+        "Custom token type name '{}' is reserved for a built-in token type",
+        name
+      )));
+    }
+
+    Ok(())
+  }
+
   #[allow(dead_code)]
   pub fn as_str(&self) -> &'static str {
     match self {
       TokenType::EmailVerification => "email_verification",
       TokenType::PasswordReset => "password_reset",
       TokenType::MagicLink => "magic_link",
+      TokenType::SecondaryEmailVerification => "secondary_email_verification",
+      TokenType::PhoneVerification => "phone_verification",
+      TokenType::Invitation => "invitation",
+      TokenType::VerificationLinkCode => "verification_link_code",
+      TokenType::Custom(purpose) => purpose,
     }
   }
 }
@@ -61,10 +142,13 @@ pub struct VerifiedToken {
 #[allow(dead_code)]
 pub(crate) trait TokenStrategy: Send + Sync {
   /// Generate a new verification token
+  ///
+  /// `identifier` is whatever is being verified (an email, a phone number,
+  /// ...); `user_id` is optional for identifiers not yet tied to a user.
   async fn generate_token(
     &self,
     db: &dyn crate::database::DatabaseTrait,
-    user_id: &str,
+    user_id: Option<&str>,
     identifier: &str,
     token_type: TokenType,
     expires_in_seconds: i64,
@@ -78,13 +162,84 @@ pub(crate) trait TokenStrategy: Send + Sync {
     token_type: TokenType,
   ) -> Result<VerifiedToken>;
 
+  /// Look up the stored type of a token without verifying or consuming it
+  ///
+  /// Returns `None` when no verification record matches the token.
+  async fn find_token_type(
+    &self,
+    db: &dyn crate::database::DatabaseTrait,
+    token: &str,
+  ) -> Result<Option<String>>;
+
+  /// Look up the user a token of `token_type` was issued to, without
+  /// verifying or consuming it
+  ///
+  /// Used and expired tokens still resolve. Returns `None` when no record
+  /// matches or the token isn't tied to a user.
+  async fn find_token_user_id(
+    &self,
+    db: &dyn crate::database::DatabaseTrait,
+    token: &str,
+    token_type: TokenType,
+  ) -> Result<Option<String>>;
+
+  /// Generate a short numeric one-time code scoped to an identifier
+  ///
+  /// Codes are stored hashed together with the identifier, so the same code
+  /// issued to two identifiers never matches across them.
+  async fn generate_code(
+    &self,
+    db: &dyn crate::database::DatabaseTrait,
+    user_id: &str,
+    identifier: &str,
+    token_type: TokenType,
+    digits: usize,
+    expires_in_seconds: i64,
+  ) -> Result<Token>;
+
+  /// Verify a one-time code for an identifier
+  ///
+  /// Candidates are fetched by identifier and compared in constant time,
+  /// rather than relying on a lookup by the (guessable) code's hash.
+  /// After too many wrong codes for the identifier, its outstanding codes
+  /// are invalidated and further attempts fail with `RateLimitExceeded`.
+  async fn verify_code(
+    &self,
+    db: &dyn crate::database::DatabaseTrait,
+    identifier: &str,
+    code: &str,
+    token_type: TokenType,
+  ) -> Result<VerifiedToken>;
+
+  /// Mark a one-time code as used
+  async fn mark_code_as_used(
+    &self,
+    db: &dyn crate::database::DatabaseTrait,
+    identifier: &str,
+    code: &str,
+  ) -> Result<()>;
+
+  /// The value stored in (and looked up from) the `token_hash` column for a
+  /// token
+  fn stored_token(&self, token: &str) -> Result<String>;
+
+  /// Whether tokens live in the SQL `verification` table, so a caller can
+  /// claim one inside its own database transaction
+  fn uses_verification_table(&self) -> bool;
+
   /// Mark a token as used (so it can't be reused)
+  ///
+  /// Fails with `TokenAlreadyUsed` if another caller consumed it first.
   async fn mark_token_as_used(
     &self,
     db: &dyn crate::database::DatabaseTrait,
     token: &str,
   ) -> Result<()>;
 
+  /// Recover the plaintext token from its stored (encrypted) value
+  #[cfg(feature = "encrypted-tokens")]
+  fn decrypt_stored_token(&self, stored: &str) -> Result<String>;
+
   /// Clean up expired tokens from the database
   async fn clean_expired_tokens(&self, db: &dyn crate::database::DatabaseTrait) -> Result<()>;
 }
@@ -0,0 +1,125 @@
+//! OpenTelemetry spans for operations and the email worker
+//!
+//! Only available with the `otel` feature.
+
+use crate::error::Result;
+use crate::operations::{RegisterOutcome, SendVerificationOutcome, VerifiedSession};
+use crate::types::{Session, User, VerificationToken};
+use opentelemetry::global::{self, BoxedTracer};
+use opentelemetry::trace::{FutureExt, Span, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Instrumentation scope name of AuthKit spans
+const TRACER_NAME: &str = "authkit";
+
+/// Where AuthKit spans go
+///
+/// Uses the tracer from `AuthBuilder::tracer` when one is set, otherwise the
+/// global tracer provider as installed by the application at the time each
+/// span starts.
+#[derive(Clone, Default)]
+pub(crate) struct Telemetry {
+  tracer: Option<Arc<BoxedTracer>>,
+}
+
+impl Telemetry {
+  pub(crate) fn new(tracer: Option<BoxedTracer>) -> Self {
+    Self {
+      tracer: tracer.map(Arc::new),
+    }
+  }
+
+  /// Run `operation` inside a span named `name`, child of `parent`
+  ///
+  /// The span's context is attached to the future, so spans started by
+  /// anything it awaits (and email jobs it enqueues) nest under it. The span
+  /// records `authkit.outcome`, the error code on failure, and a hash of the
+  /// user id when the result carries one.
+  pub(crate) async fn in_span<T: Traced>(
+    &self,
+    name: impl Into<Cow<'static, str>>,
+    parent: &Context,
+    attributes: Vec<KeyValue>,
+    operation: impl Future<Output = Result<T>>,
+  ) -> Result<T> {
+    let mut span = match &self.tracer {
+      Some(tracer) => tracer.start_with_context(name, parent),
+      None => global::tracer(TRACER_NAME).start_with_context(name, parent),
+    };
+    span.set_attributes(attributes);
+
+    let cx = parent.with_span(span);
+    let result = operation.with_context(cx.clone()).await;
+
+    let span = cx.span();
+    match &result {
+      Ok(value) => {
+        span.set_attribute(KeyValue::new("authkit.outcome", "success"));
+        if let Some(user_id) = value.user_id() {
+          span.set_attribute(KeyValue::new("authkit.user_id_hash", hash_user_id(user_id)));
+        }
+      }
+      Err(e) => {
+        span.set_attribute(KeyValue::new("authkit.outcome", "failure"));
+        span.set_attribute(KeyValue::new("authkit.error_code", e.code()));
+        span.set_status(Status::error(e.code()));
+      }
+    }
+    span.end();
+
+    result
+  }
+}
+
+/// Hash a user id for span attributes, so traces don't carry raw ids
+pub(crate) fn hash_user_id(user_id: &str) -> String {
+  hex::encode(&Sha256::digest(user_id.as_bytes())[..8])
+}
+
+/// Operation results that identify a user
+pub(crate) trait Traced {
+  fn user_id(&self) -> Option<&str> {
+    None
+  }
+}
+
+impl Traced for () {}
+
+impl Traced for VerificationToken {}
+
+impl Traced for User {
+  fn user_id(&self) -> Option<&str> {
+    Some(&self.id)
+  }
+}
+
+impl Traced for Session {
+  fn user_id(&self) -> Option<&str> {
+    Some(&self.user_id)
+  }
+}
+
+impl Traced for RegisterOutcome {
+  fn user_id(&self) -> Option<&str> {
+    Some(&self.user.id)
+  }
+}
+
+impl Traced for SendVerificationOutcome {}
+
+impl Traced for VerifiedSession {
+  fn user_id(&self) -> Option<&str> {
+    Some(&self.user.id)
+  }
+}
+
+/// The only `String` result is `resolve_user_id`'s user ID
+impl Traced for String {
+  fn user_id(&self) -> Option<&str> {
+    Some(self)
+  }
+}
@@ -0,0 +1,102 @@
+//! Helpers for testing applications built on AuthKit
+//!
+//! Only available with the `test-util` feature. Enable it in
+//! `[dev-dependencies]` so it never ships in release builds:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! authkit = { version = "0.1", features = ["test-util"] }
+//! ```
+
+use crate::email::{CustomEmailContext, EmailContext, EmailSender};
+use crate::error::Result;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+/// Email sender that records every email instead of sending it
+///
+/// Clones share the same record, so keep one clone for assertions and hand
+/// another to the builder.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::test_util::RecordingEmailSender;
+///
+/// let sender = RecordingEmailSender::new();
+/// let auth = Auth::builder()
+///     .database(db)
+///     .email_sender(Box::new(sender.clone()))
+///     .build()?;
+///
+/// let token = auth.send_email_verification(SendEmailVerification { user_id }).await?;
+///
+/// sender.assert_count(1);
+/// assert_eq!(sender.last_token().as_deref(), Some(token.token.as_str()));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RecordingEmailSender {
+  verification: Arc<Mutex<Vec<EmailContext>>>,
+  custom: Arc<Mutex<Vec<CustomEmailContext>>>,
+}
+
+impl RecordingEmailSender {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// All verification emails sent so far, oldest first
+  pub fn sent(&self) -> Vec<EmailContext> {
+    self.verification.lock().unwrap().clone()
+  }
+
+  /// All custom emails sent so far, oldest first
+  pub fn sent_custom(&self) -> Vec<CustomEmailContext> {
+    self.custom.lock().unwrap().clone()
+  }
+
+  /// Number of verification emails sent so far
+  pub fn count(&self) -> usize {
+    self.verification.lock().unwrap().len()
+  }
+
+  /// The most recent verification email, if any
+  pub fn last(&self) -> Option<EmailContext> {
+    self.verification.lock().unwrap().last().cloned()
+  }
+
+  /// Token from the most recent verification email, if any
+  pub fn last_token(&self) -> Option<String> {
+    self.last().map(|context| context.token)
+  }
+
+  /// Panic unless exactly `expected` verification emails were sent
+  #[track_caller]
+  pub fn assert_count(&self, expected: usize) {
+    let actual = self.count();
+    assert_eq!(
+      actual, expected,
+      "expected {} verification email(s), got {}",
+      expected, actual
+    );
+  }
+
+  /// Forget everything recorded so far
+  pub fn clear(&self) {
+    self.verification.lock().unwrap().clear();
+    self.custom.lock().unwrap().clear();
+  }
+}
+
+#[async_trait]
+impl EmailSender for RecordingEmailSender {
+  async fn send_verification_email(&self, context: EmailContext) -> Result<()> {
+    self.verification.lock().unwrap().push(context);
+    Ok(())
+  }
+
+  async fn send_custom_email(&self, context: CustomEmailContext) -> Result<()> {
+    self.custom.lock().unwrap().push(context);
+    Ok(())
+  }
+}
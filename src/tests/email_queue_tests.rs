@@ -0,0 +1,570 @@
+//! Tests for the background email queue and worker
+//!
+//! Most of these drive the queue directly with a recording sender, so no
+//! database is needed.
+
+use crate::email::{CustomEmailContext, EmailContext, EmailSender};
+use crate::email_job::{create_email_queue, EmailQueueError, EmailWorkerConfig};
+use crate::error::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Email sender that records every custom email it is asked to send
+#[derive(Default)]
+struct RecordingSender {
+  custom: Arc<Mutex<Vec<CustomEmailContext>>>,
+}
+
+#[async_trait]
+impl EmailSender for RecordingSender {
+  async fn send_verification_email(&self, _context: EmailContext) -> Result<()> {
+    Ok(())
+  }
+
+  async fn send_custom_email(&self, context: CustomEmailContext) -> Result<()> {
+    self.custom.lock().unwrap().push(context);
+    Ok(())
+  }
+}
+
+#[tokio::test]
+async fn test_enqueue_custom_email_reaches_sender() {
+  let sender = RecordingSender::default();
+  let sent = sender.custom.clone();
+
+  let (queue, worker) = create_email_queue(
+    Arc::new(Box::new(sender) as Box<dyn EmailSender>),
+    EmailWorkerConfig::default(),
+  );
+  let handle = tokio::spawn(worker.run());
+
+  let mut data = HashMap::new();
+  data.insert("order_id".to_string(), "1234".to_string());
+
+  queue
+    .enqueue_custom("buyer@example.com", "order_confirmation", data)
+    .await
+    .unwrap();
+
+  // Closing the queue lets the worker drain and stop
+  drop(queue);
+  handle.await.unwrap();
+
+  let sent = sent.lock().unwrap();
+  assert_eq!(sent.len(), 1);
+  assert_eq!(sent[0].email, "buyer@example.com");
+  assert_eq!(sent[0].template_id, "order_confirmation");
+  assert_eq!(
+    sent[0].data.get("order_id").map(String::as_str),
+    Some("1234")
+  );
+}
+
+#[test]
+fn test_worker_config_validation() {
+  use std::time::Duration;
+
+  assert!(EmailWorkerConfig::default().validate().is_ok());
+
+  let invalid = [
+    EmailWorkerConfig::default().with_buffer_size(0),
+    EmailWorkerConfig::default().with_retry_delay(Duration::ZERO),
+    EmailWorkerConfig::default()
+      .with_retry_delay(Duration::from_secs(10))
+      .with_max_retry_delay(Duration::from_secs(5)),
+    EmailWorkerConfig::default().with_max_send_rate(0),
+    EmailWorkerConfig {
+      default_max_attempts: 0,
+      ..EmailWorkerConfig::default()
+    },
+    EmailWorkerConfig {
+      retry_jitter: 1.5,
+      ..EmailWorkerConfig::default()
+    },
+  ];
+
+  for config in invalid {
+    assert!(
+      matches!(config.validate(), Err(EmailQueueError::ConfigError(_))),
+      "accepted invalid config: {:?}",
+      config
+    );
+  }
+}
+
+#[test]
+fn test_worker_config_shares_retry_policy() {
+  use std::time::Duration;
+
+  let policy = crate::RetryPolicy {
+    max_attempts: 4,
+    base: Duration::from_millis(5),
+    max: Duration::from_millis(40),
+    jitter: 0.0,
+  };
+  let config = EmailWorkerConfig::default().with_retry_policy(policy.clone());
+
+  assert_eq!(config.default_max_attempts, 4);
+  assert_eq!(config.retry_policy(), policy);
+
+  let (_queue, worker) = create_email_queue(
+    Arc::new(Box::new(RecordingSender::default()) as Box<dyn EmailSender>),
+    config,
+  );
+  let delays: Vec<u128> = (1..=5)
+    .map(|attempt| worker.calculate_backoff(attempt).as_millis())
+    .collect();
+  assert_eq!(delays, vec![5, 10, 20, 40, 40]);
+}
+
+#[test]
+fn test_backoff_grows_and_is_capped() {
+  use std::time::Duration;
+
+  // Delays under 10ms have no jitter, so the sequence is exact
+  let config = EmailWorkerConfig::default()
+    .with_retry_delay(Duration::from_millis(1))
+    .with_max_retry_delay(Duration::from_millis(8));
+  let (_queue, worker) = create_email_queue(
+    Arc::new(Box::new(RecordingSender::default()) as Box<dyn EmailSender>),
+    config,
+  );
+
+  let delays: Vec<u128> = (1..=6)
+    .map(|attempt| worker.calculate_backoff(attempt).as_millis())
+    .collect();
+  assert_eq!(delays, vec![1, 2, 4, 8, 8, 8]);
+
+  // With jitter, delays stay within 10% of the doubling curve and never
+  // exceed the max
+  let config = EmailWorkerConfig::default()
+    .with_retry_delay(Duration::from_millis(100))
+    .with_max_retry_delay(Duration::from_millis(1000));
+  let (_queue, worker) = create_email_queue(
+    Arc::new(Box::new(RecordingSender::default()) as Box<dyn EmailSender>),
+    config,
+  );
+
+  for attempt in 1..=10u32 {
+    let expected = (100u128 << (attempt - 1)).min(1000);
+    let delay = worker.calculate_backoff(attempt).as_millis();
+    assert!(
+      delay >= expected * 9 / 10,
+      "attempt {}: {}ms",
+      attempt,
+      delay
+    );
+    assert!(
+      delay <= (expected * 11 / 10).min(1000),
+      "attempt {}: {}ms",
+      attempt,
+      delay
+    );
+  }
+}
+
+#[tokio::test]
+async fn test_builder_rejects_invalid_worker_config() {
+  use crate::tests::integration_tests::setup_test_database;
+  use std::time::Duration;
+
+  let result = crate::Auth::builder()
+    .database(setup_test_database().await.unwrap())
+    .email_sender(Box::new(RecordingSender::default()))
+    .email_queue(EmailWorkerConfig::default().with_retry_delay(Duration::ZERO))
+    .build();
+
+  match result {
+    Err(crate::AuthError::ConfigError(msg)) => assert!(msg.contains("base_retry_delay")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_batch_enqueue_respects_send_rate() {
+  use crate::email_job::EmailJob;
+  use tokio::time::{Duration, Instant};
+
+  let sender = RecordingSender::default();
+  let sent = sender.custom.clone();
+
+  let (queue, worker) = create_email_queue(
+    Arc::new(Box::new(sender) as Box<dyn EmailSender>),
+    EmailWorkerConfig::default()
+      .with_buffer_size(64)
+      .with_max_send_rate(10),
+  );
+
+  let jobs = (0..50)
+    .map(|i| {
+      EmailJob::custom(
+        format!("user{}@example.com", i),
+        "announcement".to_string(),
+        HashMap::new(),
+      )
+    })
+    .collect();
+  assert_eq!(queue.enqueue_batch(jobs).await.unwrap(), 50);
+
+  // The clock is paused, so the worker's pacing sleeps advance mock time
+  let started = Instant::now();
+  drop(queue);
+  worker.run().await;
+  let elapsed = started.elapsed();
+
+  let sent = sent.lock().unwrap();
+  assert_eq!(sent.len(), 50);
+  assert_eq!(sent[0].email, "user0@example.com");
+  assert_eq!(sent[49].email, "user49@example.com");
+
+  // 10/s means 49 gaps of 100ms after the first send
+  assert!(elapsed >= Duration::from_millis(4900), "{:?}", elapsed);
+  assert!(elapsed < Duration::from_millis(5100), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn test_enqueue_announcement_requires_queue() {
+  use crate::tests::integration_tests::setup_test_auth;
+
+  let auth = setup_test_auth().await.unwrap();
+  let result = auth
+    .enqueue_announcement(["a@example.com"], "announcement", HashMap::new())
+    .await;
+
+  assert!(matches!(result, Err(crate::AuthError::ConfigError(_))));
+}
+
+#[tokio::test]
+async fn test_dead_email_worker_can_be_detected_and_restarted() {
+  use crate::tests::integration_tests::setup_test_auth_with;
+
+  let sender = RecordingSender::default();
+  let sent = sender.custom.clone();
+  let auth = setup_test_auth_with(|b| {
+    b.email_sender(Box::new(sender))
+      .email_queue(EmailWorkerConfig::default())
+  })
+  .await
+  .unwrap();
+
+  assert!(!auth.email_worker_healthy());
+  let handle = auth.start_email_worker();
+  assert!(auth.email_worker_healthy());
+
+  handle.abort();
+  while handle.is_running() {
+    tokio::task::yield_now().await;
+  }
+  assert!(!auth.email_worker_healthy());
+  assert!(auth.email_queue().is_none());
+
+  let handle = auth.restart_email_worker();
+  assert!(auth.email_worker_healthy());
+  auth
+    .enqueue_announcement(["after@example.com"], "announcement", HashMap::new())
+    .await
+    .unwrap();
+
+  handle.shutdown().await.unwrap();
+  assert!(!auth.email_worker_healthy());
+  let sent = sent.lock().unwrap();
+  assert_eq!(sent.len(), 1);
+  assert_eq!(sent[0].email, "after@example.com");
+}
+
+#[cfg(feature = "otel")]
+#[tokio::test]
+async fn test_email_send_span_joins_register_trace() {
+  use crate::operations::Register;
+  use crate::tests::integration_tests::setup_test_auth_with;
+  use opentelemetry::trace::TracerProvider;
+  use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+  let exporter = InMemorySpanExporter::default();
+  let provider = SdkTracerProvider::builder()
+    .with_simple_exporter(exporter.clone())
+    .build();
+
+  let auth = setup_test_auth_with(|b| {
+    b.email_sender(Box::new(RecordingSender::default()))
+      .send_verification_on_register(true)
+      .email_queue(EmailWorkerConfig::default())
+      .tracer(provider.tracer("authkit"))
+  })
+  .await
+  .unwrap();
+  let handle = auth.start_email_worker();
+
+  auth
+    .register(Register::new("traced@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+  handle.shutdown().await.unwrap();
+
+  let spans = exporter.get_finished_spans().unwrap();
+  let register = spans.iter().find(|s| s.name == "authkit.register").unwrap();
+  let send = spans
+    .iter()
+    .find(|s| s.name == "authkit.email.send")
+    .unwrap();
+
+  // The worker's span is a child of the register span, across the task boundary
+  assert_eq!(
+    send.span_context.trace_id(),
+    register.span_context.trace_id()
+  );
+  assert_eq!(send.parent_span_id, register.span_context.span_id());
+  assert!(
+    send.attributes.iter().any(
+      |kv| kv.key.as_str() == "authkit.email.type" && kv.value.as_str() == "email_verification"
+    )
+  );
+}
+
+#[cfg(all(feature = "persistent-queue", feature = "sqlite"))]
+async fn open_job_store(path: &str) -> crate::email_job::JobStore {
+  let db = crate::Database::sqlite(path).await.unwrap();
+  let db = crate::database::create_database_trait(db.inner);
+  db.migrate().await.unwrap();
+  crate::email_job::JobStore::new(Arc::new(db), Arc::new(crate::OsTokenGenerator))
+}
+
+#[cfg(all(feature = "persistent-queue", feature = "sqlite"))]
+#[tokio::test]
+async fn test_persistent_jobs_survive_restart() {
+  use crate::email_job::create_persistent_email_queue;
+  use crate::tests::test_helpers::TempSqliteFile;
+
+  let file = TempSqliteFile::new("authkit-email-jobs");
+  let config = EmailWorkerConfig::default().persistent();
+
+  // First process: queue two jobs, start sending one, then stop without a worker
+  {
+    let store = open_job_store(file.path()).await;
+    let (queue, _worker) = create_persistent_email_queue(
+      Arc::new(Box::new(RecordingSender::default()) as Box<dyn EmailSender>),
+      config.clone(),
+      store.clone(),
+    );
+    queue
+      .enqueue_custom("pending@example.com", "announcement", HashMap::new())
+      .await
+      .unwrap();
+    queue
+      .enqueue_custom("claimed@example.com", "announcement", HashMap::new())
+      .await
+      .unwrap();
+
+    // Jobs created in the same second may be claimed in either order
+    let claimed = store.claim_next().await.unwrap().unwrap();
+    assert!(claimed.recipient.ends_with("@example.com"));
+  }
+
+  // Make the claimed job look abandoned by a crashed worker
+  let db = crate::Database::sqlite(file.path()).await.unwrap();
+  crate::tests::test_helpers::execute_sql(
+    &db,
+    "UPDATE email_jobs SET updated_at = 0 WHERE status = 'sending'",
+  )
+  .await
+  .unwrap();
+
+  // Second process: a fresh worker sends both
+  let sender = RecordingSender::default();
+  let sent = sender.custom.clone();
+  let store = open_job_store(file.path()).await;
+  let (queue, worker) = create_persistent_email_queue(
+    Arc::new(Box::new(sender) as Box<dyn EmailSender>),
+    config,
+    store.clone(),
+  );
+  drop(queue);
+  worker.run().await;
+
+  let mut recipients: Vec<_> = sent
+    .lock()
+    .unwrap()
+    .iter()
+    .map(|c| c.email.clone())
+    .collect();
+  recipients.sort();
+  assert_eq!(recipients, ["claimed@example.com", "pending@example.com"]);
+  assert!(store.claim_next().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "persistent-queue", feature = "sqlite"))]
+#[tokio::test]
+async fn test_persistent_jobs_are_claimed_once() {
+  use crate::email_job::EmailJob;
+  use crate::tests::test_helpers::TempSqliteFile;
+  use std::collections::HashSet;
+
+  let file = TempSqliteFile::new("authkit-email-claims");
+  let store = open_job_store(file.path()).await;
+  for i in 0..20 {
+    let job = EmailJob::custom(
+      format!("user{}@example.com", i),
+      "announcement".to_string(),
+      HashMap::new(),
+    );
+    store.insert(&job).await.unwrap();
+  }
+
+  let claimers: Vec<_> = (0..4)
+    .map(|_| {
+      let store = store.clone();
+      tokio::spawn(async move {
+        let mut claimed = Vec::new();
+        while let Some(job) = store.claim_next().await.unwrap() {
+          claimed.push(job.recipient);
+        }
+        claimed
+      })
+    })
+    .collect();
+
+  let mut all = Vec::new();
+  for claimer in claimers {
+    all.extend(claimer.await.unwrap());
+  }
+  let unique: HashSet<_> = all.iter().collect();
+  assert_eq!(all.len(), 20);
+  assert_eq!(unique.len(), 20);
+}
+
+#[cfg(all(feature = "persistent-queue", feature = "sqlite"))]
+#[tokio::test]
+async fn test_persistent_job_with_unreadable_data_fails() {
+  use crate::email_job::EmailJob;
+  use crate::tests::test_helpers::{execute_sql, query_i64, TempSqliteFile};
+
+  let file = TempSqliteFile::new("authkit-email-corrupt");
+  let store = open_job_store(file.path()).await;
+  let job = EmailJob::custom(
+    "corrupt@example.com".to_string(),
+    "announcement".to_string(),
+    HashMap::new(),
+  );
+  store.insert(&job).await.unwrap();
+
+  let db = crate::Database::sqlite(file.path()).await.unwrap();
+  execute_sql(&db, "UPDATE email_jobs SET data = 'not json'")
+    .await
+    .unwrap();
+
+  assert!(store.claim_next().await.unwrap().is_none());
+  let failed = query_i64(
+    &db,
+    "SELECT COUNT(*) FROM email_jobs WHERE status = 'failed' AND last_error LIKE 'Invalid job data%'",
+  )
+  .await
+  .unwrap();
+  assert_eq!(failed, 1);
+}
+
+#[cfg(all(feature = "persistent-queue", feature = "sqlite"))]
+#[tokio::test]
+async fn test_persistent_finished_jobs_are_pruned() {
+  use crate::email_job::EmailJob;
+  use crate::tests::test_helpers::{execute_sql, query_i64, TempSqliteFile};
+  use std::time::Duration;
+
+  let file = TempSqliteFile::new("authkit-email-prune");
+  let store = open_job_store(file.path()).await;
+  for i in 0..3 {
+    let job = EmailJob::custom(
+      format!("user{}@example.com", i),
+      "announcement".to_string(),
+      HashMap::new(),
+    );
+    store.insert(&job).await.unwrap();
+  }
+  for _ in 0..2 {
+    let job = store.claim_next().await.unwrap().unwrap();
+    store.finish(&job, true).await;
+  }
+
+  let db = crate::Database::sqlite(file.path()).await.unwrap();
+  // Age one sent job and the pending one; only the sent job is pruned
+  execute_sql(
+    &db,
+    "UPDATE email_jobs SET updated_at = 0 \
+     WHERE status = 'pending' OR id = (SELECT MIN(id) FROM email_jobs WHERE status = 'sent')",
+  )
+  .await
+  .unwrap();
+
+  let retention = Duration::from_secs(60 * 60);
+  assert_eq!(store.prune_finished(retention).await.unwrap(), 1);
+  let remaining = query_i64(&db, "SELECT COUNT(*) FROM email_jobs")
+    .await
+    .unwrap();
+  assert_eq!(remaining, 2);
+}
+
+#[cfg(all(
+  feature = "persistent-queue",
+  feature = "encrypted-tokens",
+  feature = "sqlite"
+))]
+#[tokio::test]
+async fn test_persistent_job_tokens_encrypted_at_rest() {
+  use crate::email_job::EmailJob;
+  use crate::security::encryption::TokenCipher;
+  use crate::tests::test_helpers::{query_i64, TempSqliteFile};
+
+  let file = TempSqliteFile::new("authkit-email-encrypted");
+  let store = open_job_store(file.path())
+    .await
+    .with_cipher(Some(TokenCipher::new([7u8; 32])));
+  let job = EmailJob::verification(
+    "user@example.com".to_string(),
+    "plaintext-token".to_string(),
+    0,
+    "user-1".to_string(),
+  );
+  store.insert(&job).await.unwrap();
+
+  let db = crate::Database::sqlite(file.path()).await.unwrap();
+  let plaintext = query_i64(
+    &db,
+    "SELECT COUNT(*) FROM email_jobs WHERE token = 'plaintext-token'",
+  )
+  .await
+  .unwrap();
+  assert_eq!(plaintext, 0);
+
+  let claimed = store.claim_next().await.unwrap().unwrap();
+  assert_eq!(claimed.token, "plaintext-token");
+}
+
+#[tokio::test]
+async fn test_send_email_verification_detailed_reports_queued() {
+  use crate::tests::integration_tests::setup_test_auth_with;
+  use crate::{Register, SendEmailVerification, SendStatus};
+
+  let auth = setup_test_auth_with(|b| {
+    b.email_sender(Box::new(RecordingSender::default()))
+      .email_queue(EmailWorkerConfig::default())
+  })
+  .await
+  .unwrap();
+  let handle = auth.start_email_worker();
+
+  let user = auth
+    .register(Register {
+      name: None,
+      email: "queued@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+  let outcome = auth
+    .send_email_verification_detailed(SendEmailVerification { user_id: user.id })
+    .await
+    .unwrap();
+  assert_eq!(outcome.status, SendStatus::Queued);
+
+  handle.shutdown().await.unwrap();
+}
@@ -2,8 +2,21 @@
 mod tests {
   use crate::prelude::*;
   use crate::tests::integration_tests::{
-    register_and_verify_user, setup_test_auth, setup_test_auth_with_email_verification,
+    register_and_verify_user, setup_test_auth, setup_test_auth_with,
+    setup_test_auth_with_email_verification, setup_test_database,
   };
+  use crate::tests::test_helpers::execute_sql;
+  use std::time::Duration;
+
+  /// Email sender whose delivery always fails
+  struct FailingSender;
+
+  #[async_trait::async_trait]
+  impl EmailSender for FailingSender {
+    async fn send_verification_email(&self, _context: EmailContext) -> Result<()> {
+      Err(AuthError::EmailSendFailed("smtp unavailable".into()))
+    }
+  }
 
   #[tokio::test]
   async fn test_send_email_verification_success() {
@@ -33,6 +46,94 @@ mod tests {
     assert!(verification.expires_at > 0);
   }
 
+  #[tokio::test]
+  async fn test_verification_token_expiry() {
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "expiry@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+
+    // Tokens are valid for 24 hours
+    assert_eq!(verification.identifier, "expiry@example.com");
+    assert!(!verification.is_expired(now));
+    assert!(!verification.is_expired(verification.expires_at));
+    assert!(verification.is_expired(verification.expires_at + 1));
+    assert!(verification.is_expired(now + 25 * 60 * 60));
+
+    // The token carried in the struct is the plaintext that verifies
+    let verified = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await
+      .unwrap();
+    assert!(verified.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_verification_url_from_template() {
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register::new("url@example.com", "SecurePass123!"))
+      .await
+      .unwrap();
+
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    let token = &verification.token;
+
+    assert_eq!(
+      verification.verification_url("https://myapp.com/verify-email?token={token}&src=email"),
+      format!("https://myapp.com/verify-email?token={}&src=email", token)
+    );
+    assert_eq!(
+      verification.verification_url("https://myapp.com/verify/{token}"),
+      format!("https://myapp.com/verify/{}", token)
+    );
+
+    // A base URL gets the token as a query parameter
+    assert_eq!(
+      verification.verification_url("https://myapp.com/verify"),
+      format!("https://myapp.com/verify?token={}", token)
+    );
+    assert_eq!(
+      verification.verification_url("https://myapp.com/verify?lang=en"),
+      format!("https://myapp.com/verify?lang=en&token={}", token)
+    );
+
+    // Tokens are percent-encoded
+    let custom = VerificationToken {
+      token: "a b/c+d".to_string(),
+      identifier: "url@example.com".to_string(),
+      expires_at: verification.expires_at,
+    };
+    assert_eq!(
+      custom.verification_url("https://myapp.com/verify?token={token}"),
+      "https://myapp.com/verify?token=a%20b%2Fc%2Bd"
+    );
+  }
+
   #[tokio::test]
   async fn test_send_email_verification_user_not_found() {
     let auth = setup_test_auth().await.unwrap();
@@ -126,6 +227,74 @@ mod tests {
     assert_eq!(verified_user.email, "test@example.com");
   }
 
+  #[tokio::test]
+  async fn test_verify_email_token_with_trailing_newline() {
+    let auth = setup_test_auth().await.unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "pasted@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    let verification = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    // Copied out of an email along with the line break
+    let verified_user = auth
+      .verify_email(VerifyEmail {
+        token: format!("{}\n", verification.token),
+      })
+      .await
+      .unwrap();
+    assert!(verified_user.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_idempotent_email_verify_double_click() {
+    let auth = setup_test_auth_with(|b| b.idempotent_email_verify(true))
+      .await
+      .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "twice@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    for _ in 0..2 {
+      let verified = auth
+        .verify_email(VerifyEmail {
+          token: verification.token.clone(),
+        })
+        .await
+        .unwrap();
+      assert_eq!(verified.id, user.id);
+      assert!(verified.email_verified);
+    }
+
+    let bogus = auth
+      .verify_email(VerifyEmail {
+        token: "not-a-real-token".to_string(),
+      })
+      .await;
+    assert!(matches!(bogus, Err(AuthError::InvalidToken(_))));
+  }
+
   #[tokio::test]
   async fn test_verify_email_invalid_token() {
     let auth = setup_test_auth().await.unwrap();
@@ -183,6 +352,139 @@ mod tests {
     ));
   }
 
+  #[tokio::test]
+  async fn test_verify_email_deletes_other_outstanding_tokens() {
+    let auth = setup_test_auth().await.unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "two-tokens@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    let first = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    let second = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    auth
+      .verify_email(VerifyEmail { token: first.token })
+      .await
+      .unwrap();
+
+    // The second token was deleted, so it is no longer recognised at all
+    let result = auth
+      .verify_email(VerifyEmail {
+        token: second.token,
+      })
+      .await;
+
+    assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_expired_token() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "expired@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    let verification = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    execute_sql(&db, "UPDATE verification SET expires_at = 0")
+      .await
+      .unwrap();
+
+    let result = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await;
+
+    assert!(matches!(result.unwrap_err(), AuthError::TokenExpired(_)));
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_errors_are_distinct() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+
+    // Unknown token
+    let not_found = auth
+      .verify_email(VerifyEmail {
+        token: "does-not-exist".to_string(),
+      })
+      .await
+      .unwrap_err();
+    assert!(matches!(not_found, AuthError::InvalidToken(_)));
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "distinct@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    let used = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    let expired = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    // Mark one token used and the other expired directly, leaving the user unverified
+    execute_sql(&db, "UPDATE verification SET expires_at = 0")
+      .await
+      .unwrap();
+    auth
+      .inner
+      .token_strategy
+      .mark_token_as_used(auth.inner.db.as_ref().as_ref(), &used.token)
+      .await
+      .unwrap();
+
+    let used_err = auth
+      .verify_email(VerifyEmail { token: used.token })
+      .await
+      .unwrap_err();
+    assert!(matches!(used_err, AuthError::TokenAlreadyUsed(_)));
+
+    let expired_err = auth
+      .verify_email(VerifyEmail {
+        token: expired.token,
+      })
+      .await
+      .unwrap_err();
+    assert!(matches!(expired_err, AuthError::TokenExpired(_)));
+  }
+
   #[tokio::test]
   async fn test_verify_email_already_verified() {
     let auth = setup_test_auth().await.unwrap();
@@ -251,6 +553,151 @@ mod tests {
     assert_ne!(first_verification.token, second_verification.token);
   }
 
+  #[tokio::test]
+  async fn test_resend_email_verification_cooldown() {
+    let auth = setup_test_auth_with(|b| b.email_send_cooldown(Duration::from_secs(60)))
+      .await
+      .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "cooldown@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    // A rapid second send to the same address is rejected
+    let result = auth
+      .resend_email_verification(ResendEmailVerification {
+        email: "cooldown@example.com".to_string(),
+      })
+      .await;
+    assert!(matches!(
+      result.unwrap_err(),
+      AuthError::Cooldown { seconds_remaining } if (1..=60).contains(&seconds_remaining)
+    ));
+
+    // Other addresses are unaffected
+    let other = auth
+      .register(Register {
+        name: None,
+        email: "other@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    assert!(auth
+      .send_email_verification(SendEmailVerification { user_id: other.id })
+      .await
+      .is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_send_cooldown_reports_seconds_remaining() {
+    use crate::operations::email_verification::check_send_cooldown_at;
+
+    let auth = setup_test_auth_with(|b| b.email_send_cooldown(Duration::from_secs(60)))
+      .await
+      .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "countdown@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    let sent_at = auth
+      .inner
+      .db
+      .find_latest_verification_send("countdown@example.com", "email_verification")
+      .await
+      .unwrap()
+      .unwrap();
+
+    // The remaining wait counts down from the cooldown as time passes
+    for (elapsed, remaining) in [(0, 60), (15, 45), (59, 1)] {
+      let result = check_send_cooldown_at(
+        &auth,
+        "countdown@example.com",
+        TokenType::EmailVerification,
+        sent_at + elapsed,
+      )
+      .await;
+      assert!(
+        matches!(result, Err(AuthError::Cooldown { seconds_remaining }) if seconds_remaining == remaining),
+        "after {}s: {:?}",
+        elapsed,
+        result
+      );
+    }
+
+    assert!(check_send_cooldown_at(
+      &auth,
+      "countdown@example.com",
+      TokenType::EmailVerification,
+      sent_at + 60
+    )
+    .await
+    .is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_resend_email_verification_max_resends() {
+    let auth = setup_test_auth_with(|b| b.max_verification_resends(2))
+      .await
+      .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "capped@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    // Two resends are allowed on top of the first email
+    for _ in 0..2 {
+      auth
+        .resend_email_verification(ResendEmailVerification {
+          email: "capped@example.com".to_string(),
+        })
+        .await
+        .unwrap();
+    }
+
+    let result = auth
+      .resend_email_verification(ResendEmailVerification {
+        email: "capped@example.com".to_string(),
+      })
+      .await;
+    assert!(matches!(
+      result.unwrap_err(),
+      AuthError::RateLimitExceeded(_)
+    ));
+  }
+
   #[tokio::test]
   async fn test_resend_email_verification_user_not_found() {
     let auth = setup_test_auth().await.unwrap();
@@ -323,6 +770,7 @@ mod tests {
     // Login should succeed without email verification
     let session = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "test@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -349,6 +797,7 @@ mod tests {
     // Attempt to login should fail with EmailNotVerified
     let result = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "unverified@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -374,6 +823,7 @@ mod tests {
     // Now login should succeed
     let session = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "verified@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -403,6 +853,7 @@ mod tests {
     // 2. User CAN login without email verification (not required by default)
     let session = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "newuser@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -469,6 +920,7 @@ mod tests {
     // 2. User CANNOT login without email verification
     let login_result = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "newuser@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -502,6 +954,7 @@ mod tests {
     // 5. Now user CAN login after email verification
     let session = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "newuser@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -582,6 +1035,7 @@ mod tests {
     // Both users can login (verification not required by default)
     let session1 = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "user1@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -590,6 +1044,7 @@ mod tests {
 
     let session2 = auth
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "user2@example.com".to_string(),
         password: "SecurePass123!".to_string(),
       })
@@ -667,4 +1122,1042 @@ mod tests {
 
     assert!(verified_user.email_verified);
   }
+
+  #[tokio::test]
+  async fn test_register_succeeds_when_verification_email_fails() {
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(FailingSender))
+        .send_verification_on_register(true)
+    })
+    .await
+    .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "lenient@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+
+    assert!(!user.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_register_fails_when_verification_email_fails_in_strict_mode() {
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(FailingSender))
+        .send_verification_on_register(true)
+        .fail_registration_on_email_error(true)
+    })
+    .await
+    .unwrap();
+
+    let result = auth
+      .register(Register {
+        name: None,
+        email: "strict@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await;
+
+    assert!(matches!(result, Err(AuthError::EmailSendFailed(_))));
+  }
+
+  #[tokio::test]
+  async fn test_failed_register_email_leaves_no_session() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder()
+      .database(db.clone())
+      .email_sender(Box::new(FailingSender))
+      .send_verification_on_register(true)
+      .fail_registration_on_email_error(true)
+      .session_on_register(true)
+      .build()
+      .unwrap();
+
+    let result = auth
+      .register_detailed(Register::new("strict@example.com", "SecurePass123"))
+      .await;
+    assert!(matches!(result, Err(AuthError::EmailSendFailed(_))));
+
+    let sessions = crate::tests::test_helpers::query_i64(&db, "SELECT COUNT(*) FROM sessions")
+      .await
+      .unwrap();
+    assert_eq!(sessions, 0);
+  }
+
+  /// Email sender that counts verification emails
+  #[derive(Clone, Default)]
+  struct CountingSender(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+  impl CountingSender {
+    fn count(&self) -> usize {
+      self.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+  }
+
+  #[async_trait::async_trait]
+  impl EmailSender for CountingSender {
+    async fn send_verification_email(&self, _context: EmailContext) -> Result<()> {
+      self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+      Ok(())
+    }
+  }
+
+  async fn register_one(auth: &Auth, email: &str) {
+    auth
+      .register(Register {
+        name: None,
+        email: email.into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_verification_on_register_never() {
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(sender.clone()))
+        .send_verification_on_register(VerificationOnRegister::Never)
+    })
+    .await
+    .unwrap();
+
+    register_one(&auth, "never@example.com").await;
+    assert_eq!(sender.count(), 0);
+    assert!(!auth.sends_verification_on_register());
+  }
+
+  #[tokio::test]
+  async fn test_verification_on_register_if_sender_present() {
+    // Without a sender the email is skipped
+    let auth = setup_test_auth_with(|builder| {
+      builder.send_verification_on_register(VerificationOnRegister::IfSenderPresent)
+    })
+    .await
+    .unwrap();
+    register_one(&auth, "nosender@example.com").await;
+    assert!(!auth.sends_verification_on_register());
+
+    // With a sender it's sent
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(sender.clone()))
+        .send_verification_on_register(true)
+    })
+    .await
+    .unwrap();
+    register_one(&auth, "sender@example.com").await;
+    assert_eq!(sender.count(), 1);
+    assert!(auth.sends_verification_on_register());
+  }
+
+  #[tokio::test]
+  async fn test_register_detailed_returns_verification_token() {
+    let auth = setup_test_auth_with(|builder| builder.return_verification_token_on_register(true))
+      .await
+      .unwrap();
+
+    let outcome = auth
+      .register_detailed(Register::new("spa@example.com", "SecurePass123!"))
+      .await
+      .unwrap();
+    assert!(!outcome.user.email_verified);
+    let verification = outcome.verification.expect("token returned on register");
+    assert_eq!(verification.identifier, "spa@example.com");
+
+    // The returned token is a live verification token
+    let verified = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await
+      .unwrap();
+    assert_eq!(verified.id, outcome.user.id);
+    assert!(verified.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_plain_register_issues_no_returned_token() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder()
+      .database(db.clone())
+      .return_verification_token_on_register(true)
+      .build()
+      .unwrap();
+
+    register_one(&auth, "plain@example.com").await;
+
+    // Nobody could deliver a token issued here
+    let tokens = crate::tests::test_helpers::query_i64(&db, "SELECT COUNT(*) FROM verification")
+      .await
+      .unwrap();
+    assert_eq!(tokens, 0);
+  }
+
+  #[tokio::test]
+  async fn test_register_detailed_without_returned_token() {
+    // Disabled by default
+    let auth = setup_test_auth().await.unwrap();
+    let outcome = auth
+      .register_detailed(Register::new("default@example.com", "SecurePass123!"))
+      .await
+      .unwrap();
+    assert!(outcome.verification.is_none());
+
+    // With a sender the token is emailed, not returned
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(sender.clone()))
+        .send_verification_on_register(true)
+        .return_verification_token_on_register(true)
+    })
+    .await
+    .unwrap();
+    let outcome = auth
+      .register_detailed(Register::new("sender@example.com", "SecurePass123!"))
+      .await
+      .unwrap();
+    assert!(outcome.verification.is_none());
+    assert_eq!(sender.count(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_verification_on_register_always() {
+    let result = Auth::builder()
+      .database(setup_test_database().await.unwrap())
+      .send_verification_on_register(VerificationOnRegister::Always)
+      .build();
+    assert!(matches!(result, Err(AuthError::ConfigError(_))));
+
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|builder| {
+      builder
+        .email_sender(Box::new(sender.clone()))
+        .send_verification_on_register(VerificationOnRegister::Always)
+    })
+    .await
+    .unwrap();
+    register_one(&auth, "always@example.com").await;
+    assert_eq!(sender.count(), 1);
+  }
+
+  #[cfg(feature = "test-util")]
+  #[tokio::test]
+  async fn test_recording_sender_captures_verification_email() {
+    use crate::test_util::RecordingEmailSender;
+
+    let sender = RecordingEmailSender::new();
+    let auth = setup_test_auth_with(|builder| builder.email_sender(Box::new(sender.clone())))
+      .await
+      .unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "recorded@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+    sender.assert_count(0);
+
+    let token = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    sender.assert_count(1);
+    assert_eq!(sender.last_token(), Some(token.token));
+    assert_eq!(sender.last().unwrap().email, "recorded@example.com");
+  }
+
+  #[tokio::test]
+  async fn test_find_unverified_users_registered_before() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+
+    for email in ["old@example.com", "new@example.com"] {
+      auth
+        .register(Register {
+          name: None,
+          email: email.into(),
+          password: "SecurePass123!".into(),
+        })
+        .await
+        .unwrap();
+    }
+    register_and_verify_user(&auth, "verified@example.com", "SecurePass123!")
+      .await
+      .unwrap();
+
+    // Backdate the old signup and the verified one
+    execute_sql(
+      &db,
+      "UPDATE users SET created_at = 1000 WHERE email IN ('old@example.com', 'verified@example.com')",
+    )
+    .await
+    .unwrap();
+
+    let users = auth
+      .find_unverified_users_registered_before(5000)
+      .await
+      .unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].email, "old@example.com");
+    assert!(!users[0].email_verified);
+  }
+
+  #[cfg(feature = "sqlite")]
+  #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+  async fn test_concurrent_verify_email_succeeds_once() {
+    let file = crate::tests::test_helpers::TempSqliteFile::new("authkit-verify-race");
+    let db = Database::sqlite(file.path()).await.unwrap();
+    let auth = Auth::builder().database(db).build().unwrap();
+    auth.migrate().await.unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "race@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    let verification = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    let attempts: Vec<_> = (0..8)
+      .map(|_| {
+        let auth = auth.clone();
+        let token = verification.token.clone();
+        tokio::spawn(async move { auth.verify_email(VerifyEmail { token }).await })
+      })
+      .collect();
+
+    let mut successes = 0;
+    for attempt in attempts {
+      match attempt.await.unwrap() {
+        Ok(user) => {
+          assert!(user.email_verified);
+          successes += 1;
+        }
+        Err(AuthError::TokenAlreadyUsed(_)) | Err(AuthError::EmailAlreadyVerified(_)) => {}
+        Err(e) => panic!("unexpected error: {:?}", e),
+      }
+    }
+
+    assert_eq!(successes, 1);
+  }
+
+  #[tokio::test]
+  async fn test_verification_status_report() {
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "status@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+
+    // Unverified, nothing sent yet
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert_eq!(status.email, "status@example.com");
+    assert!(!status.email_verified);
+    assert_eq!(status.email_verified_at, None);
+    assert!(!status.has_pending_token);
+    assert_eq!(status.pending_token_expires_at, None);
+
+    // Unverified with a pending token
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert!(!status.email_verified);
+    assert!(status.has_pending_token);
+    assert_eq!(
+      status.pending_token_expires_at,
+      Some(verification.expires_at)
+    );
+
+    // Verified; the consumed token is no longer pending
+    auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await
+      .unwrap();
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert!(status.email_verified);
+    assert!(status.email_verified_at.is_some());
+    assert!(!status.has_pending_token);
+
+    let missing = auth.verification_status("no-such-user").await;
+    assert!(matches!(missing, Err(AuthError::UserNotFound)));
+  }
+
+  #[tokio::test]
+  async fn test_user_for_verification_token() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "lookup@example.com".to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    let found = auth
+      .user_for_verification_token(&verification.token)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(found.id, user.id);
+    assert!(!found.email_verified);
+
+    // Expired tokens still resolve, and the lookup consumed nothing
+    execute_sql(&db, "UPDATE verification SET expires_at = 0")
+      .await
+      .unwrap();
+    let found = auth
+      .user_for_verification_token(&verification.token)
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(found.id, user.id);
+    let result = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await;
+    assert!(matches!(result, Err(AuthError::TokenExpired(_))));
+
+    let missing = auth.user_for_verification_token("no-such-token").await;
+    assert!(matches!(missing, Ok(None)));
+  }
+
+  /// Register `email` and issue it an email verification code
+  async fn register_with_code(auth: &Auth, email: &str) -> (User, VerificationToken) {
+    let user = auth
+      .register(Register {
+        name: None,
+        email: email.to_string(),
+        password: "SecurePass123!".to_string(),
+      })
+      .await
+      .unwrap();
+    let code = auth
+      .send_email_verification_code(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    (user, code)
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_with_identifier_correct_code() {
+    let auth = setup_test_auth().await.unwrap();
+    let (user, code) = register_with_code(&auth, "otp@example.com").await;
+    assert_eq!(code.token.len(), 6);
+    assert_eq!(code.identifier, "otp@example.com");
+
+    let verified = auth
+      .verify_email_with_identifier("otp@example.com", &code.token)
+      .await
+      .unwrap();
+    assert_eq!(verified.id, user.id);
+    assert!(verified.email_verified);
+
+    let again = auth
+      .verify_email_with_identifier("otp@example.com", &code.token)
+      .await;
+    assert!(matches!(again, Err(AuthError::TokenAlreadyUsed(_))));
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_with_identifier_wrong_code() {
+    let auth = setup_test_auth().await.unwrap();
+    let (user, code) = register_with_code(&auth, "otp@example.com").await;
+
+    let wrong = if code.token == "000000" {
+      "000001"
+    } else {
+      "000000"
+    };
+    let result = auth
+      .verify_email_with_identifier("otp@example.com", wrong)
+      .await;
+    assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+
+    // Codes aren't link tokens either
+    let result = auth
+      .verify_email(VerifyEmail {
+        token: code.token.clone(),
+      })
+      .await;
+    assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert!(!status.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_with_identifier_rejects_other_users_code() {
+    let auth = setup_test_auth().await.unwrap();
+    let (alice, alice_code) = register_with_code(&auth, "alice@example.com").await;
+    let (bob, _) = register_with_code(&auth, "bob@example.com").await;
+
+    let result = auth
+      .verify_email_with_identifier("bob@example.com", &alice_code.token)
+      .await;
+    assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+    assert!(
+      !auth
+        .verification_status(&bob.id)
+        .await
+        .unwrap()
+        .email_verified
+    );
+
+    // Alice's code still works for Alice
+    let verified = auth
+      .verify_email_with_identifier("alice@example.com", &alice_code.token)
+      .await
+      .unwrap();
+    assert_eq!(verified.id, alice.id);
+  }
+
+  #[tokio::test]
+  async fn test_verify_email_with_identifier_locks_out_after_misses() {
+    let auth = setup_test_auth().await.unwrap();
+    let (user, code) = register_with_code(&auth, "otp@example.com").await;
+
+    let wrong = if code.token == "000000" {
+      "000001"
+    } else {
+      "000000"
+    };
+    for _ in 0..5 {
+      let result = auth
+        .verify_email_with_identifier("otp@example.com", wrong)
+        .await;
+      assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+    }
+
+    let result = auth
+      .verify_email_with_identifier("otp@example.com", &code.token)
+      .await;
+    assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+    assert!(
+      !auth
+        .verification_status(&user.id)
+        .await
+        .unwrap()
+        .email_verified
+    );
+  }
+
+  #[tokio::test]
+  async fn test_send_email_verification_code_max_resends() {
+    let auth = setup_test_auth_with(|b| b.max_verification_resends(1))
+      .await
+      .unwrap();
+    let (user, _) = register_with_code(&auth, "capped@example.com").await;
+
+    auth
+      .send_email_verification_code(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    let result = auth
+      .send_email_verification_code(SendEmailVerification { user_id: user.id })
+      .await;
+    assert!(matches!(
+      result.unwrap_err(),
+      AuthError::RateLimitExceeded(_)
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_send_email_verification_detailed_status() {
+    // No sender: the application delivers the token itself
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "nosender@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+    let outcome = auth
+      .send_email_verification_detailed(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+    assert_eq!(outcome.status, SendStatus::TokenOnly);
+    assert_eq!(outcome.verification.identifier, "nosender@example.com");
+
+    // A working sender sends synchronously
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|b| b.email_sender(Box::new(sender.clone())))
+      .await
+      .unwrap();
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "sent@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+    let outcome = auth
+      .send_email_verification_detailed(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+    assert_eq!(outcome.status, SendStatus::Sent);
+    assert_eq!(sender.count(), 1);
+
+    // A failing sender is reported, not returned as an error
+    let auth = setup_test_auth_with(|b| b.email_sender(Box::new(FailingSender)))
+      .await
+      .unwrap();
+    let user = auth
+      .register(Register {
+        name: None,
+        email: "failed@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+    let outcome = auth
+      .send_email_verification_detailed(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+    assert_eq!(outcome.status, SendStatus::Failed);
+    assert!(!outcome.verification.token.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_send_verification_emails_batch() {
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|b| b.email_sender(Box::new(sender.clone())))
+      .await
+      .unwrap();
+
+    let mut user_ids = Vec::new();
+    for i in 0..3 {
+      let user = auth
+        .register(Register {
+          name: None,
+          email: format!("batch{}@example.com", i),
+          password: "SecurePass123".into(),
+        })
+        .await
+        .unwrap();
+      user_ids.push(user.id);
+    }
+    user_ids.push("no-such-user".to_string());
+
+    let results = auth.send_verification_emails(&user_ids).await;
+    assert_eq!(results.len(), 4);
+    assert!(matches!(results[3].result, Err(AuthError::UserNotFound)));
+    assert_eq!(sender.count(), 3);
+
+    let tokens: std::collections::HashSet<_> = results[..3]
+      .iter()
+      .map(|entry| {
+        let outcome = entry.result.as_ref().unwrap();
+        assert_eq!(outcome.status, SendStatus::Sent);
+        outcome.verification.token.clone()
+      })
+      .collect();
+    assert_eq!(tokens.len(), 3);
+
+    // Each token verifies its own user
+    for entry in &results[..3] {
+      let token = entry.result.as_ref().unwrap().verification.token.clone();
+      let user = auth.verify_email(VerifyEmail { token }).await.unwrap();
+      assert_eq!(user.id, entry.user_id);
+      assert!(user.email_verified);
+    }
+  }
+
+  #[cfg(feature = "sqlite")]
+  #[tokio::test]
+  async fn test_migrate_email_verification_on_base_schema() {
+    let db = Database::sqlite(":memory:").await.unwrap();
+    // A users table from before email verification existed
+    execute_sql(
+      &db,
+      "CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE,
+        name TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+      )",
+    )
+    .await
+    .unwrap();
+    let auth = Auth::builder().database(db).build().unwrap();
+    auth.migrate().await.unwrap();
+
+    auth.migrate_email_verification().await.unwrap();
+    auth.migrate_email_verification().await.unwrap();
+
+    let user = auth
+      .register(Register::new("legacy@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+    let verification = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+    let user = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await
+      .unwrap();
+    assert!(user.email_verified);
+  }
+
+  #[cfg(feature = "sqlite")]
+  #[tokio::test]
+  async fn test_verify_session_on_base_schema() {
+    let db = Database::sqlite(":memory:").await.unwrap();
+    // A users table without the email verification columns
+    execute_sql(
+      &db,
+      "CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE,
+        name TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+      )",
+    )
+    .await
+    .unwrap();
+    let auth = Auth::builder().database(db).build().unwrap();
+    auth.migrate().await.unwrap();
+
+    auth
+      .register(Register::new("legacy@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+    let session = auth
+      .login(Login::new("legacy@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+    let user = auth.verify(Verify::new(&session.token)).await.unwrap();
+    assert_eq!(user.email, "legacy@example.com");
+    assert!(!user.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_unverified_login_resends_verification() {
+    let sender = CountingSender::default();
+    let auth = setup_test_auth_with(|b| {
+      b.email_sender(Box::new(sender.clone()))
+        .require_email_verification(true)
+        .email_send_cooldown(Duration::from_secs(60))
+        .resend_verification_on_login(true)
+    })
+    .await
+    .unwrap();
+    let user = auth
+      .register(Register::new("resend@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+    assert_eq!(sender.count(), 0);
+
+    let result = auth
+      .login(Login::new("resend@example.com", "SecurePass123"))
+      .await;
+    match result {
+      Err(AuthError::EmailNotVerified(email)) => assert_eq!(email, "resend@example.com"),
+      other => panic!("expected EmailNotVerified, got {:?}", other),
+    }
+    assert_eq!(sender.count(), 1);
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert!(status.has_pending_token);
+
+    // A retry inside the cooldown doesn't send another email
+    let result = auth
+      .login(Login::new("resend@example.com", "SecurePass123"))
+      .await;
+    assert!(matches!(result, Err(AuthError::EmailNotVerified(_))));
+    assert_eq!(sender.count(), 1);
+
+    let result = Auth::builder()
+      .database(setup_test_database().await.unwrap())
+      .resend_verification_on_login(true)
+      .build();
+    assert!(matches!(result, Err(AuthError::ConfigError(_))));
+  }
+
+  #[tokio::test]
+  async fn test_accept_invitation() {
+    let auth = setup_test_auth().await.unwrap();
+
+    let invitation = auth
+      .invite_user(InviteUser {
+        email: "invitee@example.com".to_string(),
+        name: Some("Invitee".to_string()),
+      })
+      .await
+      .unwrap();
+    assert!(!invitation.user.email_verified);
+
+    // No password yet, so there is nothing to log in with
+    let before = auth
+      .login(Login::new("invitee@example.com", "InvitePass123"))
+      .await;
+    assert!(matches!(before, Err(AuthError::InvalidCredentials)));
+
+    let user = auth
+      .accept_invitation(AcceptInvitation {
+        token: invitation.verification.token.clone(),
+        password: "InvitePass123".to_string(),
+      })
+      .await
+      .unwrap();
+    assert_eq!(user.id, invitation.user.id);
+    assert!(user.email_verified);
+    assert!(user.email_verified_at.is_some());
+
+    let accounts = auth.list_accounts(&user.id).await.unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].provider, "credential");
+
+    auth
+      .login(Login::new("invitee@example.com", "InvitePass123"))
+      .await
+      .unwrap();
+
+    // The invitation works once
+    let again = auth
+      .accept_invitation(AcceptInvitation {
+        token: invitation.verification.token,
+        password: "OtherPass456".to_string(),
+      })
+      .await;
+    assert!(matches!(again, Err(AuthError::TokenAlreadyUsed(_))));
+  }
+
+  #[tokio::test]
+  async fn test_failed_accept_invitation_keeps_token() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+    let invitation = auth
+      .invite_user(InviteUser {
+        email: "invitee@example.com".to_string(),
+        name: None,
+      })
+      .await
+      .unwrap();
+
+    // A conflicting account makes the account insert fail
+    execute_sql(
+      &db,
+      &format!(
+        "INSERT INTO accounts (id, user_id, provider, provider_account_id, created_at, updated_at)
+         VALUES ('conflict', '{}', 'credential', 'invitee@example.com', 0, 0)",
+        invitation.user.id
+      ),
+    )
+    .await
+    .unwrap();
+    let request = AcceptInvitation {
+      token: invitation.verification.token,
+      password: "InvitePass123".to_string(),
+    };
+    assert!(auth.accept_invitation(request.clone()).await.is_err());
+
+    // The claim was rolled back with the insert, so the invitation still works
+    execute_sql(&db, "DELETE FROM accounts WHERE id = 'conflict'")
+      .await
+      .unwrap();
+    let user = auth.accept_invitation(request).await.unwrap();
+    assert!(user.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_accept_invitation_rejects_expired_token() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+
+    let invitation = auth
+      .invite_user(InviteUser {
+        email: "late@example.com".to_string(),
+        name: None,
+      })
+      .await
+      .unwrap();
+
+    execute_sql(&db, "UPDATE verification SET expires_at = 0")
+      .await
+      .unwrap();
+
+    let result = auth
+      .accept_invitation(AcceptInvitation {
+        token: invitation.verification.token,
+        password: "InvitePass123".to_string(),
+      })
+      .await;
+    assert!(matches!(result, Err(AuthError::TokenExpired(_))));
+
+    // Nothing was created
+    let accounts = auth.list_accounts(&invitation.user.id).await.unwrap();
+    assert!(accounts.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_accept_invitation_rejects_other_token_types() {
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register::new("registered@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+    let verification = auth
+      .send_email_verification(SendEmailVerification { user_id: user.id })
+      .await
+      .unwrap();
+
+    let result = auth
+      .accept_invitation(AcceptInvitation {
+        token: verification.token,
+        password: "InvitePass123".to_string(),
+      })
+      .await;
+    assert!(result.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_invite_existing_email_fails() {
+    let auth = setup_test_auth().await.unwrap();
+    auth
+      .register(Register::new("taken@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+
+    let result = auth
+      .invite_user(InviteUser {
+        email: "taken@example.com".to_string(),
+        name: None,
+      })
+      .await;
+    assert!(matches!(result, Err(AuthError::UserAlreadyExists(_))));
+  }
+
+  #[tokio::test]
+  async fn test_exchange_verification_code_once() {
+    let auth = setup_test_auth().await.unwrap();
+    let user = auth
+      .register(Register::new("landing@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+
+    let code = auth.issue_verification_link_code(&user.id).await.unwrap();
+    assert_eq!(code.identifier, "landing@example.com");
+
+    let verified = auth.exchange_verification_code(&code.token).await.unwrap();
+    assert_eq!(verified.id, user.id);
+    assert!(verified.email_verified);
+
+    // A leaked link is useless once exchanged
+    let reused = auth.exchange_verification_code(&code.token).await;
+    assert!(matches!(reused, Err(AuthError::TokenAlreadyUsed(_))));
+  }
+
+  #[tokio::test]
+  async fn test_exchange_verification_code_rejects_expired_and_other_tokens() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder().database(db.clone()).build().unwrap();
+    let user = auth
+      .register(Register::new("stale@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+
+    // A verification token is not a link code
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    let wrong_type = auth.exchange_verification_code(&verification.token).await;
+    assert!(wrong_type.is_err());
+
+    let code = auth.issue_verification_link_code(&user.id).await.unwrap();
+    execute_sql(
+      &db,
+      "UPDATE verification SET expires_at = 0 WHERE token_type = 'verification_link_code'",
+    )
+    .await
+    .unwrap();
+
+    let expired = auth.exchange_verification_code(&code.token).await;
+    assert!(matches!(expired, Err(AuthError::TokenExpired(_))));
+
+    let status = auth.verification_status(&user.id).await.unwrap();
+    assert!(!status.email_verified);
+  }
+
+  #[tokio::test]
+  async fn test_verification_link_code_ttl() {
+    let db = setup_test_database().await.unwrap();
+    let auth = Auth::builder()
+      .database(db.clone())
+      .verification_link_code_ttl(Duration::from_secs(15 * 60))
+      .build()
+      .unwrap();
+    let user = auth
+      .register(Register::new("ttl@example.com", "SecurePass123"))
+      .await
+      .unwrap();
+
+    let code = auth.issue_verification_link_code(&user.id).await.unwrap();
+    let now = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .unwrap()
+      .as_secs() as i64;
+    assert!(code.expires_at <= now + 15 * 60);
+    assert!(code.expires_at > now + 14 * 60);
+
+    let result = Auth::builder()
+      .database(db)
+      .verification_link_code_ttl(Duration::ZERO)
+      .build();
+    assert!(matches!(result, Err(AuthError::ConfigError(_))));
+  }
 }
@@ -15,7 +15,127 @@ async fn test_builder_missing_database() {
   let result = Auth::builder().build();
 
   assert!(result.is_err());
-  assert!(matches!(result.unwrap_err(), AuthError::MissingDatabase));
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("database")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[cfg(feature = "bcrypt")]
+#[tokio::test]
+async fn test_builder_unavailable_password_strategy() {
+  use crate::strategies::password::PasswordStrategyType;
+
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .password_strategy(PasswordStrategyType::Bcrypt)
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("bcrypt")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_builder_rejects_zero_durations() {
+  use crate::tests::integration_tests::setup_test_database;
+
+  let result = Auth::builder()
+    .database(setup_test_database().await.unwrap())
+    .inactivity_timeout(std::time::Duration::ZERO)
+    .build();
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("inactivity_timeout")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+
+  let result = Auth::builder()
+    .database(setup_test_database().await.unwrap())
+    .remember_me_duration(std::time::Duration::ZERO)
+    .build();
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("remember_me_duration")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_builder_rejects_short_session_signing_secret() {
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .session_signing_secrets(vec![7u8; 32], vec![b"too-short".to_vec()])
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("32 bytes")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_builder_token_reuse_requires_encryption_key() {
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .reuse_valid_verification_tokens(true)
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("token_encryption_key")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_builder_rejects_invalid_db_retry() {
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .db_retry(RetryPolicy {
+      max_attempts: 0,
+      ..RetryPolicy::default()
+    })
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("db_retry")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_builder_rejects_short_token_hash_secret() {
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .token_hash(crate::TokenHashAlgo::HmacSha256 {
+      secret: b"too short".to_vec(),
+    })
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("token_hash")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
 }
 
 #[tokio::test]
@@ -173,6 +293,7 @@ async fn test_sql_injection_in_password() {
   // Login should work with the same "malicious" password
   let login_result = auth
     .login(Login { ip_address: None, user_agent: None,
+      remember_me: false,
       email: "test@example.com".into(),
       password: "Password123'; DROP TABLE users; --".into(),
     })
@@ -214,6 +335,7 @@ async fn test_double_logout() {
 
   let session = auth
     .login(Login { ip_address: None, user_agent: None,
+      remember_me: false,
       email: "double@example.com".into(),
       password: "SecurePass123".into(),
     })
@@ -243,6 +365,7 @@ async fn test_verify_after_logout() {
 
   let session = auth
     .login(Login { ip_address: None, user_agent: None,
+      remember_me: false,
       email: "verify@example.com".into(),
       password: "SecurePass123".into(),
     })
@@ -279,6 +402,7 @@ async fn test_concurrent_operations() {
   let handle1 = tokio::spawn(async move {
     auth1
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "concurrent@example.com".into(),
         password: "SecurePass123".into(),
       })
@@ -288,6 +412,7 @@ async fn test_concurrent_operations() {
   let handle2 = tokio::spawn(async move {
     auth2
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "concurrent@example.com".into(),
         password: "SecurePass123".into(),
       })
@@ -297,6 +422,7 @@ async fn test_concurrent_operations() {
   let handle3 = tokio::spawn(async move {
     auth3
       .login(Login { ip_address: None, user_agent: None,
+        remember_me: false,
         email: "concurrent@example.com".into(),
         password: "SecurePass123".into(),
       })
@@ -355,6 +481,7 @@ async fn test_unicode_in_password() {
       // If registration succeeds, login should work
       let login_result = auth
         .login(Login { ip_address: None, user_agent: None,
+          remember_me: false,
           email: email.clone(),
           password: password.to_string(),
         })
@@ -396,8 +523,9 @@ async fn test_very_long_token() {
   let long_token = "a".repeat(10000);
   let result = auth.verify(Verify::new(long_token)).await;
 
+  // Rejected by the input size limit before any lookup
   assert!(result.is_err());
-  assert!(matches!(result.unwrap_err(), AuthError::InvalidSession));
+  assert!(matches!(result.unwrap_err(), AuthError::InvalidInput(_)));
 }
 
 #[tokio::test]
@@ -445,6 +573,19 @@ async fn test_auth_is_sync() {
   assert_sync::<Auth>();
 }
 
+#[tokio::test]
+async fn test_auth_shareable_across_tasks() {
+  let auth = setup_test_auth().await.unwrap();
+
+  // Moving a clone into a spawned task requires Auth: Send + 'static
+  let handle = tokio::spawn({
+    let auth = auth.clone();
+    async move { auth.verify(Verify::new("invalid-token")).await.is_err() }
+  });
+
+  assert!(handle.await.unwrap());
+}
+
 #[tokio::test]
 async fn test_login_with_email_verification_required() {
   // Use auth that requires email verification
@@ -462,6 +603,7 @@ async fn test_login_with_email_verification_required() {
   // Login should fail with EmailNotVerified
   let result = auth
     .login(Login { ip_address: None, user_agent: None,
+      remember_me: false,
       email: "unverified@example.com".into(),
       password: "SecurePass123".into(),
     })
@@ -473,3 +615,233 @@ async fn test_login_with_email_verification_required() {
     AuthError::EmailNotVerified(_)
   ));
 }
+
+#[test]
+fn test_error_codes_and_status_hints() {
+  let cases = [
+    (AuthError::InvalidCredentials, "invalid_credentials", 401),
+    (
+      AuthError::UserAlreadyExists("a@example.com".into()),
+      "user_already_exists",
+      409,
+    ),
+    (
+      AuthError::TokenExpired("expired".into()),
+      "token_expired",
+      400,
+    ),
+    (
+      AuthError::EmailNotVerified("a@example.com".into()),
+      "email_not_verified",
+      403,
+    ),
+    (
+      AuthError::RateLimitExceeded("slow down".into()),
+      "rate_limit_exceeded",
+      429,
+    ),
+    (
+      AuthError::Cooldown {
+        seconds_remaining: 30,
+      },
+      "cooldown",
+      429,
+    ),
+    (
+      AuthError::InternalError("boom".into()),
+      "internal_error",
+      500,
+    ),
+  ];
+
+  for (err, code, status) in cases {
+    assert_eq!(err.code(), code);
+    assert_eq!(err.http_status_hint(), status, "{}", code);
+  }
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_error_body_from_auth_error() {
+  use crate::http::ErrorBody;
+
+  let body = ErrorBody::from_auth_error(&AuthError::InvalidCredentials);
+  assert_eq!(body.code, "invalid_credentials");
+  assert_eq!(body.message, "Invalid email or password");
+  assert_eq!(body.correlation_id, None);
+
+  let body =
+    ErrorBody::from(&AuthError::TokenAlreadyUsed("used".into())).with_correlation_id("req-123");
+  assert_eq!(body.code, "token_already_used");
+  assert_eq!(body.correlation_id.as_deref(), Some("req-123"));
+
+  // Server-side details are not exposed
+  let body = ErrorBody::from_auth_error(&AuthError::InternalError("db password wrong".into()));
+  assert_eq!(body.code, "internal_error");
+  assert_eq!(body.message, "Internal server error");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_session_cookie_headers() {
+  use crate::http::{csrf_hidden_input, SameSite, SessionCookie};
+
+  let cookie = SessionCookie::default().with_domain("example.com");
+  assert_eq!(
+    cookie.set_cookie("abc123", 3600),
+    "authkit_session=abc123; Path=/; Max-Age=3600; Domain=example.com; Secure; HttpOnly; SameSite=Lax"
+  );
+  assert_eq!(
+    cookie.clear_cookie(),
+    "authkit_session=; Path=/; Max-Age=0; Domain=example.com; Secure; HttpOnly; SameSite=Lax"
+  );
+
+  let csrf = SessionCookie::csrf().with_same_site(SameSite::Strict);
+  assert_eq!(
+    csrf.set_cookie("f00d", 60),
+    "authkit_csrf=f00d; Path=/; Max-Age=60; Secure; SameSite=Strict"
+  );
+
+  let header = "theme=dark; authkit_session=abc123; authkit_csrf=f00d";
+  assert_eq!(cookie.value_from(header), Some("abc123"));
+  assert_eq!(csrf.value_from(header), Some("f00d"));
+  assert_eq!(SessionCookie::new("missing").value_from(header), None);
+
+  assert_eq!(
+    csrf_hidden_input("f00d"),
+    r#"<input type="hidden" name="csrf_token" value="f00d">"#
+  );
+}
+
+#[tokio::test]
+async fn test_builder_rejects_short_csrf_secret() {
+  let result = Auth::builder()
+    .database(
+      crate::tests::integration_tests::setup_test_database()
+        .await
+        .unwrap(),
+    )
+    .csrf_secret(b"too-short".to_vec())
+    .build();
+
+  match result {
+    Err(AuthError::ConfigError(msg)) => assert!(msg.contains("csrf_secret")),
+    other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+  }
+}
+
+#[tokio::test]
+async fn test_login_with_malformed_stored_hash() {
+  let db = crate::tests::integration_tests::setup_test_database()
+    .await
+    .unwrap();
+  let auth = Auth::builder().database(db.clone()).build().unwrap();
+  let user = auth
+    .register(Register {
+      name: None,
+      email: "corrupt@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+
+  crate::tests::test_helpers::execute_sql(
+    &db,
+    &format!(
+      "UPDATE accounts SET password_hash = 'not-a-phc-string' WHERE user_id = '{}'",
+      user.id
+    ),
+  )
+  .await
+  .unwrap();
+
+  let result = auth
+    .login(Login {
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+      email: "corrupt@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+
+  let result = auth.verify_password(&user.id, "SecurePass123").await;
+  assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+}
+
+#[tokio::test]
+async fn test_oversized_inputs_rejected() {
+  let auth = setup_test_auth().await.unwrap();
+  let huge = "A1a".repeat(350_000);
+
+  // The size check answers, not the password policy or the hasher
+  let result = auth
+    .register(Register {
+      name: None,
+      email: "big@example.com".into(),
+      password: huge.clone(),
+    })
+    .await;
+  assert!(
+    matches!(result, Err(AuthError::InvalidInput(ref msg)) if msg == "password must be at most 1024 bytes")
+  );
+
+  let result = auth
+    .login(Login {
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+      email: "big@example.com".into(),
+      password: huge,
+    })
+    .await;
+  assert!(
+    matches!(result, Err(AuthError::InvalidInput(ref msg)) if msg == "password must be at most 1024 bytes")
+  );
+
+  let result = auth
+    .register(Register {
+      name: Some("n".repeat(10_000)),
+      email: "big@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+
+  let result = auth
+    .register(Register {
+      name: None,
+      email: format!("{}@example.com", "a".repeat(10_000)),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn test_custom_input_limits() {
+  let auth = crate::tests::integration_tests::setup_test_auth_with(|b| {
+    b.input_limits(InputLimits::default().with_max_password_len(16))
+  })
+  .await
+  .unwrap();
+
+  let result = auth
+    .register(Register {
+      name: None,
+      email: "limits@example.com".into(),
+      password: "SecurePass1234567".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+
+  auth
+    .register(Register {
+      name: None,
+      email: "limits@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+}
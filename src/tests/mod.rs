@@ -15,10 +15,27 @@ pub(crate) mod test_helpers;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 mod email_verification_tests;
 
+#[cfg(feature = "email-queue")]
+mod email_queue_tests;
+
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 mod error_tests;
 
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 mod integration_tests;
 
+#[cfg(all(feature = "sms", any(feature = "sqlite", feature = "postgres")))]
+mod phone_verification_tests;
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+mod token_tests;
+
+mod rate_limit_tests;
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+mod retry_tests;
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+mod user_email_tests;
+
 mod validation_tests;
@@ -0,0 +1,246 @@
+//! Tests for phone verification over SMS (sms feature)
+
+use crate::prelude::*;
+use crate::tests::integration_tests::{register_and_login, setup_test_auth_with};
+use std::sync::{Arc, Mutex};
+
+/// SMS sender that records every message instead of sending it
+#[derive(Clone, Default)]
+struct RecordingSmsSender {
+  sent: Arc<Mutex<Vec<SmsContext>>>,
+}
+
+#[async_trait::async_trait]
+impl SmsSender for RecordingSmsSender {
+  async fn send_verification_sms(&self, context: SmsContext) -> Result<()> {
+    self.sent.lock().unwrap().push(context);
+    Ok(())
+  }
+}
+
+async fn setup() -> (Auth, RecordingSmsSender, String) {
+  let sender = RecordingSmsSender::default();
+  let auth = setup_test_auth_with(|builder| builder.sms_sender(Box::new(sender.clone())))
+    .await
+    .unwrap();
+  let session = register_and_login(&auth, "phone@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  (auth, sender, session.user_id)
+}
+
+#[tokio::test]
+async fn test_send_and_verify_phone() {
+  let (auth, sender, user_id) = setup().await;
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+
+  let sent = sender.sent.lock().unwrap().clone();
+  assert_eq!(sent.len(), 1);
+  assert_eq!(sent[0].phone, "+15555550123");
+  assert_eq!(sent[0].code, token.token);
+  assert_eq!(sent[0].code.len(), 6);
+
+  let verified = auth
+    .verify_phone(VerifyPhone {
+      user_id: user_id.clone(),
+      code: token.token,
+    })
+    .await
+    .unwrap();
+  assert_eq!(verified.user_id, user_id);
+  assert_eq!(verified.phone, "+15555550123");
+}
+
+#[tokio::test]
+async fn test_verify_phone_rejects_wrong_code() {
+  let (auth, _sender, user_id) = setup().await;
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+
+  let wrong = if token.token == "000000" {
+    "111111"
+  } else {
+    "000000"
+  };
+  let result = auth
+    .verify_phone(VerifyPhone {
+      user_id,
+      code: wrong.into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_phone_already_verified() {
+  let (auth, sender, user_id) = setup().await;
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+  auth
+    .verify_phone(VerifyPhone {
+      user_id: user_id.clone(),
+      code: token.token.clone(),
+    })
+    .await
+    .unwrap();
+
+  let result = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::PhoneAlreadyVerified(_))));
+
+  let result = auth
+    .verify_phone(VerifyPhone {
+      user_id,
+      code: token.token,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::PhoneAlreadyVerified(_))));
+
+  // No second SMS went out
+  assert_eq!(sender.sent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_send_phone_verification_invalid_number() {
+  let (auth, sender, user_id) = setup().await;
+
+  let result = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id,
+      phone: "555-0123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidPhoneFormat)));
+  assert!(sender.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_unverified_identifiers_include_phone() {
+  let (auth, _sender, user_id) = setup().await;
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+
+  let unverified = auth.unverified_identifiers(&user_id).await.unwrap();
+  assert_eq!(
+    unverified.last(),
+    Some(&UnverifiedIdentifier {
+      kind: IdentifierKind::Phone,
+      value: "+15555550123".into(),
+    })
+  );
+
+  auth
+    .verify_phone(VerifyPhone {
+      user_id: user_id.clone(),
+      code: token.token,
+    })
+    .await
+    .unwrap();
+  let unverified = auth.unverified_identifiers(&user_id).await.unwrap();
+  assert!(unverified
+    .iter()
+    .all(|identifier| identifier.kind == IdentifierKind::Email));
+}
+
+#[tokio::test]
+async fn test_verify_phone_locks_out_after_misses() {
+  let (auth, _sender, user_id) = setup().await;
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+
+  let wrong = if token.token == "000000" {
+    "111111"
+  } else {
+    "000000"
+  };
+  for _ in 0..5 {
+    let result = auth
+      .verify_phone(VerifyPhone {
+        user_id: user_id.clone(),
+        code: wrong.into(),
+      })
+      .await;
+    assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+  }
+
+  let result = auth
+    .verify_phone(VerifyPhone {
+      user_id,
+      code: token.token,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+}
+
+#[tokio::test]
+async fn test_send_phone_verification_survives_sender_failure() {
+  struct FailingSmsSender;
+
+  #[async_trait::async_trait]
+  impl SmsSender for FailingSmsSender {
+    async fn send_verification_sms(&self, _context: SmsContext) -> Result<()> {
+      Err(AuthError::InternalError("SMS gateway down".into()))
+    }
+  }
+
+  let auth = setup_test_auth_with(|builder| builder.sms_sender(Box::new(FailingSmsSender)))
+    .await
+    .unwrap();
+  let session = register_and_login(&auth, "phone@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  let token = auth
+    .send_phone_verification(SendPhoneVerification {
+      user_id: session.user_id.clone(),
+      phone: "+15555550123".into(),
+    })
+    .await
+    .unwrap();
+
+  // The returned code still verifies the stored number
+  let verified = auth
+    .verify_phone(VerifyPhone {
+      user_id: session.user_id,
+      code: token.token,
+    })
+    .await
+    .unwrap();
+  assert_eq!(verified.phone, "+15555550123");
+}
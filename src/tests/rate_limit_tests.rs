@@ -0,0 +1,64 @@
+use crate::prelude::*;
+use std::time::Duration;
+
+#[tokio::test(start_paused = true)]
+async fn test_in_memory_store_counts_within_window() {
+  let store = InMemoryRateLimitStore::new();
+  let window = Duration::from_secs(60);
+
+  assert_eq!(store.get("login:a").await.unwrap(), 0);
+  assert_eq!(store.incr("login:a", window).await.unwrap(), 1);
+  assert_eq!(store.incr("login:a", window).await.unwrap(), 2);
+  assert_eq!(store.get("login:a").await.unwrap(), 2);
+
+  // Keys are counted independently
+  assert_eq!(store.incr("login:b", window).await.unwrap(), 1);
+  assert_eq!(store.get("login:a").await.unwrap(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_in_memory_store_window_is_fixed() {
+  let store = InMemoryRateLimitStore::new();
+  let window = Duration::from_secs(60);
+
+  store.incr("key", window).await.unwrap();
+  tokio::time::advance(Duration::from_secs(45)).await;
+  // A hit inside the window doesn't extend it
+  assert_eq!(store.incr("key", window).await.unwrap(), 2);
+
+  tokio::time::advance(Duration::from_secs(15)).await;
+  assert_eq!(store.get("key").await.unwrap(), 0);
+  assert_eq!(store.incr("key", window).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_in_memory_store_reset() {
+  let store = InMemoryRateLimitStore::new();
+  let window = Duration::from_secs(60);
+
+  store.incr("key", window).await.unwrap();
+  store.incr("key", window).await.unwrap();
+  store.reset("key").await.unwrap();
+  assert_eq!(store.get("key").await.unwrap(), 0);
+  assert_eq!(store.incr("key", window).await.unwrap(), 1);
+
+  // Resetting an unknown key is fine
+  store.reset("missing").await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_in_memory_store_evicts_expired_keys() {
+  let store = InMemoryRateLimitStore::new();
+  let window = Duration::from_secs(60);
+
+  for i in 0..64 {
+    store.incr(&format!("login:{i}"), window).await.unwrap();
+  }
+  assert_eq!(store.key_count(), 64);
+
+  tokio::time::advance(window).await;
+  // A new key past the sweep threshold drops every expired counter
+  store.incr("login:new", window).await.unwrap();
+  assert_eq!(store.key_count(), 1);
+  assert_eq!(store.get("login:0").await.unwrap(), 0);
+}
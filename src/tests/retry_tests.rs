@@ -0,0 +1,736 @@
+//! Tests for retrying transient database errors and other backend failures
+//!
+//! `FlakyDatabase` wraps a real backend and fails its first few calls with a
+//! connection reset, standing in for a database that drops connections. It
+//! can also make session inserts collide with an existing session's token.
+
+#[cfg(feature = "persistent-queue")]
+use crate::database::models::DbEmailJob;
+#[cfg(feature = "sms")]
+use crate::database::models::DbUserPhone;
+use crate::database::models::{
+  DbAccount, DbSession, DbSessionWithUser, DbUser, DbUserEmail, DbUserWithAccount, DbVerification,
+  DbVerificationSend, DbVerificationStatus,
+};
+use crate::database::retry::RetryingDatabase;
+use crate::database::{create_database_trait, DatabaseTrait};
+use crate::prelude::*;
+use crate::tests::integration_tests::{
+  register_and_login, setup_test_auth, setup_test_auth_with, setup_test_database,
+};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Backend whose first `failures` calls fail with a connection reset
+///
+/// The first `collisions` session inserts find their token already taken.
+struct FlakyDatabase {
+  inner: Box<dyn DatabaseTrait>,
+  failures: Arc<AtomicU32>,
+  collisions: AtomicU32,
+  calls: Arc<AtomicU32>,
+}
+
+impl FlakyDatabase {
+  async fn new(failures: u32) -> (Self, Arc<AtomicU32>) {
+    let db = setup_test_database().await.unwrap();
+    let calls = Arc::new(AtomicU32::new(0));
+    let flaky = Self {
+      inner: create_database_trait(db.inner),
+      failures: Arc::new(AtomicU32::new(failures)),
+      collisions: AtomicU32::new(0),
+      calls: calls.clone(),
+    };
+    (flaky, calls)
+  }
+
+  fn fail(&self) -> Result<()> {
+    self.calls.fetch_add(1, Ordering::SeqCst);
+    let failing = self
+      .failures
+      .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+      .is_ok();
+    if failing {
+      return Err(AuthError::DatabaseError(sqlx::Error::Io(
+        std::io::ErrorKind::ConnectionReset.into(),
+      )));
+    }
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl DatabaseTrait for FlakyDatabase {
+  async fn migrate(&self) -> Result<()> {
+    self.fail()?;
+    self.inner.migrate().await
+  }
+
+  async fn migrate_email_verification(&self) -> Result<()> {
+    self.fail()?;
+    self.inner.migrate_email_verification().await
+  }
+
+  async fn ping(&self) -> Result<()> {
+    self.fail()?;
+    self.inner.ping().await
+  }
+
+  async fn find_user_by_email(&self, email: &str) -> Result<Option<DbUser>> {
+    self.fail()?;
+    self.inner.find_user_by_email(email).await
+  }
+
+  async fn find_user_by_id(&self, id: &str) -> Result<Option<User>> {
+    self.fail()?;
+    self.inner.find_user_by_id(id).await
+  }
+
+  async fn create_user(
+    &self,
+    id: &str,
+    email: &str,
+    name: Option<&str>,
+    created_at: i64,
+  ) -> Result<User> {
+    self.fail()?;
+    self.inner.create_user(id, email, name, created_at).await
+  }
+
+  async fn update_email_verified(&self, user_id: &str, verified_at: i64) -> Result<()> {
+    self.fail()?;
+    self.inner.update_email_verified(user_id, verified_at).await
+  }
+
+  async fn find_user_by_id_with_verification(&self, id: &str) -> Result<Option<User>> {
+    self.fail()?;
+    self.inner.find_user_by_id_with_verification(id).await
+  }
+
+  async fn find_user_by_email_with_verification(&self, email: &str) -> Result<Option<DbUser>> {
+    self.fail()?;
+    self.inner.find_user_by_email_with_verification(email).await
+  }
+
+  async fn find_unverified_users_created_before(&self, cutoff: i64) -> Result<Vec<User>> {
+    self.fail()?;
+    self
+      .inner
+      .find_unverified_users_created_before(cutoff)
+      .await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn set_user_phone(&self, user_id: &str, phone: &str, updated_at: i64) -> Result<()> {
+    self.fail()?;
+    self.inner.set_user_phone(user_id, phone, updated_at).await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn find_user_phone(&self, user_id: &str) -> Result<Option<DbUserPhone>> {
+    self.fail()?;
+    self.inner.find_user_phone(user_id).await
+  }
+
+  #[cfg(feature = "sms")]
+  async fn mark_phone_verified(&self, user_id: &str, verified_at: i64) -> Result<()> {
+    self.fail()?;
+    self.inner.mark_phone_verified(user_id, verified_at).await
+  }
+
+  async fn create_account(
+    &self,
+    id: &str,
+    user_id: &str,
+    provider: &str,
+    provider_account_id: &str,
+    password_hash: Option<&str>,
+    created_at: i64,
+  ) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .create_account(
+        id,
+        user_id,
+        provider,
+        provider_account_id,
+        password_hash,
+        created_at,
+      )
+      .await
+  }
+
+  async fn find_account_by_provider(
+    &self,
+    provider: &str,
+    provider_account_id: &str,
+  ) -> Result<Option<DbAccount>> {
+    self.fail()?;
+    self
+      .inner
+      .find_account_by_provider(provider, provider_account_id)
+      .await
+  }
+
+  async fn find_accounts_by_user_id(&self, user_id: &str) -> Result<Vec<DbAccount>> {
+    self.fail()?;
+    self.inner.find_accounts_by_user_id(user_id).await
+  }
+
+  async fn update_account_password_hash(
+    &self,
+    account_id: &str,
+    password_hash: &str,
+    updated_at: i64,
+  ) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .update_account_password_hash(account_id, password_hash, updated_at)
+      .await
+  }
+
+  async fn accept_invitation(
+    &self,
+    user_id: &str,
+    account_id: &str,
+    credential_provider: &str,
+    email: &str,
+    password_hash: &str,
+    verification_id: Option<&str>,
+    now: i64,
+  ) -> Result<bool> {
+    self.fail()?;
+    self
+      .inner
+      .accept_invitation(
+        user_id,
+        account_id,
+        credential_provider,
+        email,
+        password_hash,
+        verification_id,
+        now,
+      )
+      .await
+  }
+
+  async fn find_user_with_credential_account(
+    &self,
+    email: &str,
+    provider: &str,
+  ) -> Result<Option<DbUserWithAccount>> {
+    self.fail()?;
+    self
+      .inner
+      .find_user_with_credential_account(email, provider)
+      .await
+  }
+
+  async fn find_user_with_credential_account_with_verification(
+    &self,
+    email: &str,
+    provider: &str,
+  ) -> Result<Option<DbUserWithAccount>> {
+    self.fail()?;
+    self
+      .inner
+      .find_user_with_credential_account_with_verification(email, provider)
+      .await
+  }
+
+  async fn create_user_email(
+    &self,
+    id: &str,
+    user_id: &str,
+    email: &str,
+    created_at: i64,
+  ) -> Result<DbUserEmail> {
+    self.fail()?;
+    self
+      .inner
+      .create_user_email(id, user_id, email, created_at)
+      .await
+  }
+
+  async fn find_user_email(&self, email: &str) -> Result<Option<DbUserEmail>> {
+    self.fail()?;
+    self.inner.find_user_email(email).await
+  }
+
+  async fn find_user_emails_by_user_id(&self, user_id: &str) -> Result<Vec<DbUserEmail>> {
+    self.fail()?;
+    self.inner.find_user_emails_by_user_id(user_id).await
+  }
+
+  async fn mark_user_email_verified(&self, email: &str, verified_at: i64) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .mark_user_email_verified(email, verified_at)
+      .await
+  }
+
+  async fn promote_user_email(
+    &self,
+    user_id: &str,
+    email: &str,
+    demoted_id: &str,
+    credential_provider: &str,
+    now: i64,
+  ) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .promote_user_email(user_id, email, demoted_id, credential_provider, now)
+      .await
+  }
+
+  async fn create_session(&self, session: &DbSession) -> Result<()> {
+    self.fail()?;
+    let colliding = self
+      .collisions
+      .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+      .is_ok();
+    if colliding {
+      // Another session already holds this token
+      let existing = DbSession {
+        id: format!("{}-existing", session.id),
+        ..session.clone()
+      };
+      self.inner.create_session(&existing).await?;
+    }
+    self.inner.create_session(session).await
+  }
+
+  async fn find_session(&self, token: &str) -> Result<Option<DbSession>> {
+    self.fail()?;
+    self.inner.find_session(token).await
+  }
+
+  async fn find_session_with_user(&self, token: &str) -> Result<Option<DbSessionWithUser>> {
+    self.fail()?;
+    self.inner.find_session_with_user(token).await
+  }
+
+  async fn find_session_with_user_with_verification(
+    &self,
+    token: &str,
+  ) -> Result<Option<DbSessionWithUser>> {
+    self.fail()?;
+    self
+      .inner
+      .find_session_with_user_with_verification(token)
+      .await
+  }
+
+  async fn scan_sessions(&self, after_id: Option<&str>, limit: u32) -> Result<Vec<DbSession>> {
+    self.fail()?;
+    self.inner.scan_sessions(after_id, limit).await
+  }
+
+  async fn find_sessions_by_user_id(&self, user_id: &str, now: i64) -> Result<Vec<DbSession>> {
+    self.fail()?;
+    self.inner.find_sessions_by_user_id(user_id, now).await
+  }
+
+  async fn delete_user_session(&self, user_id: &str, session_id: &str) -> Result<bool> {
+    self.fail()?;
+    self.inner.delete_user_session(user_id, session_id).await
+  }
+
+  async fn touch_session(&self, id: &str, now: i64) -> Result<()> {
+    self.fail()?;
+    self.inner.touch_session(id, now).await
+  }
+
+  async fn update_session_expiry(&self, id: &str, expires_at: i64) -> Result<bool> {
+    self.fail()?;
+    self.inner.update_session_expiry(id, expires_at).await
+  }
+
+  async fn delete_session(&self, token: &str) -> Result<()> {
+    self.fail()?;
+    self.inner.delete_session(token).await
+  }
+
+  async fn rotate_session_token(&self, old_token: &str, new_token: &str, now: i64) -> Result<bool> {
+    self.fail()?;
+    self
+      .inner
+      .rotate_session_token(old_token, new_token, now)
+      .await
+  }
+
+  async fn delete_expired_sessions(&self) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_expired_sessions().await
+  }
+
+  async fn delete_user_sessions(&self, user_id: &str) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_user_sessions(user_id).await
+  }
+
+  async fn delete_all_sessions(&self) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_all_sessions().await
+  }
+
+  async fn create_verification(&self, verification: &DbVerification) -> Result<()> {
+    self.fail()?;
+    self.inner.create_verification(verification).await
+  }
+
+  async fn find_verification(
+    &self,
+    token_hash: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>> {
+    self.fail()?;
+    self.inner.find_verification(token_hash, token_type).await
+  }
+
+  async fn find_verification_by_hash(&self, token_hash: &str) -> Result<Option<DbVerification>> {
+    self.fail()?;
+    self.inner.find_verification_by_hash(token_hash).await
+  }
+
+  async fn find_latest_verification(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<DbVerification>> {
+    self.fail()?;
+    self
+      .inner
+      .find_latest_verification(identifier, token_type)
+      .await
+  }
+
+  async fn record_verification_send(
+    &self,
+    send: &DbVerificationSend,
+    prune_before: i64,
+  ) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .record_verification_send(send, prune_before)
+      .await
+  }
+
+  async fn find_latest_verification_send(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Option<i64>> {
+    self.fail()?;
+    self
+      .inner
+      .find_latest_verification_send(identifier, token_type)
+      .await
+  }
+
+  async fn find_verification_status(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerificationStatus>> {
+    self.fail()?;
+    self
+      .inner
+      .find_verification_status(user_id, token_type, now)
+      .await
+  }
+
+  #[cfg(feature = "encrypted-tokens")]
+  async fn find_valid_token_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    now: i64,
+  ) -> Result<Option<DbVerification>> {
+    self.fail()?;
+    self
+      .inner
+      .find_valid_token_for_user(user_id, token_type, now)
+      .await
+  }
+
+  async fn count_verification_sends_for_user(
+    &self,
+    user_id: &str,
+    token_type: &str,
+    since: i64,
+  ) -> Result<u64> {
+    self.fail()?;
+    self
+      .inner
+      .count_verification_sends_for_user(user_id, token_type, since)
+      .await
+  }
+
+  async fn find_verifications_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<DbVerification>> {
+    self.fail()?;
+    self
+      .inner
+      .find_verifications_by_identifier(identifier, token_type)
+      .await
+  }
+
+  async fn mark_verification_used(&self, token_hash: &str, used_at: i64) -> Result<bool> {
+    self.fail()?;
+    self.inner.mark_verification_used(token_hash, used_at).await
+  }
+
+  async fn delete_verification(&self, token_hash: &str) -> Result<()> {
+    self.fail()?;
+    self.inner.delete_verification(token_hash).await
+  }
+
+  async fn delete_tokens_for_user(&self, user_id: &str, token_type: &str) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_tokens_for_user(user_id, token_type).await
+  }
+
+  async fn delete_all_verifications(&self, token_type: Option<&str>) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_all_verifications(token_type).await
+  }
+
+  async fn delete_expired_verifications(&self) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_expired_verifications().await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn insert_email_job(&self, job: &DbEmailJob) -> Result<()> {
+    self.fail()?;
+    self.inner.insert_email_job(job).await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn claim_next_email_job(&self, now: i64) -> Result<Option<DbEmailJob>> {
+    self.fail()?;
+    self.inner.claim_next_email_job(now).await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn record_email_job_attempt(
+    &self,
+    id: &str,
+    attempts: i64,
+    last_error: Option<&str>,
+    now: i64,
+  ) -> Result<()> {
+    self.fail()?;
+    self
+      .inner
+      .record_email_job_attempt(id, attempts, last_error, now)
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn finish_email_job(&self, id: &str, status: &str, now: i64) -> Result<()> {
+    self.fail()?;
+    self.inner.finish_email_job(id, status, now).await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn requeue_stale_email_jobs(&self, claimed_before: i64, now: i64) -> Result<u64> {
+    self.fail()?;
+    self
+      .inner
+      .requeue_stale_email_jobs(claimed_before, now)
+      .await
+  }
+
+  #[cfg(feature = "persistent-queue")]
+  async fn delete_finished_email_jobs(&self, finished_before: i64) -> Result<u64> {
+    self.fail()?;
+    self.inner.delete_finished_email_jobs(finished_before).await
+  }
+}
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+  RetryPolicy {
+    max_attempts,
+    base: Duration::from_millis(1),
+    max: Duration::from_millis(5),
+    jitter: 0.0,
+  }
+}
+
+#[tokio::test]
+async fn test_transient_db_error_is_retried() {
+  let (flaky, calls) = FlakyDatabase::new(2).await;
+  let db = RetryingDatabase::new(Box::new(flaky), fast_policy(3));
+
+  let user = db
+    .create_user("retry-user", "retry@example.com", None, 1)
+    .await
+    .unwrap();
+  assert_eq!(user.email, "retry@example.com");
+  assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+  // Later calls go straight through
+  let found = db.find_user_by_email("retry@example.com").await.unwrap();
+  assert_eq!(found.unwrap().id, "retry-user");
+  assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_transient_db_error_gives_up_after_max_attempts() {
+  let (flaky, calls) = FlakyDatabase::new(5).await;
+  let db = RetryingDatabase::new(Box::new(flaky), fast_policy(3));
+
+  let err = db
+    .find_user_by_email("retry@example.com")
+    .await
+    .unwrap_err();
+  assert!(err.is_transient());
+  assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_transient_db_error_not_retried_without_policy() {
+  let (flaky, calls) = FlakyDatabase::new(1).await;
+
+  let err = flaky
+    .find_user_by_email("retry@example.com")
+    .await
+    .unwrap_err();
+  assert!(matches!(err, AuthError::DatabaseError(_)));
+  assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_bulk_session_delete_is_not_replayed() {
+  let (flaky, calls) = FlakyDatabase::new(1).await;
+  let db = RetryingDatabase::new(Box::new(flaky), fast_policy(3));
+
+  // The reset may have come after the commit; running it again could
+  // delete sessions created since
+  let err = db.delete_user_sessions("user").await.unwrap_err();
+  assert!(err.is_transient());
+  assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+  assert_eq!(db.delete_all_sessions().await.unwrap(), 0);
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_only_transient_errors_are_retryable() {
+  assert!(AuthError::DatabaseError(sqlx::Error::PoolTimedOut).is_transient());
+  assert!(!AuthError::DatabaseError(sqlx::Error::RowNotFound).is_transient());
+  assert!(!AuthError::InvalidCredentials.is_transient());
+}
+
+#[tokio::test]
+async fn test_auth_with_db_retry() {
+  let auth = setup_test_auth_with(|b| b.db_retry(fast_policy(3)))
+    .await
+    .unwrap();
+
+  let user = auth
+    .register(Register {
+      name: None,
+      email: "retry@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+  let session = auth
+    .login(Login::new("retry@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+  assert_eq!(
+    auth.verify(Verify::new(&session.token)).await.unwrap().id,
+    user.id
+  );
+}
+
+#[test]
+fn test_retry_policy_delays() {
+  let policy = RetryPolicy {
+    max_attempts: 5,
+    base: Duration::from_millis(10),
+    max: Duration::from_millis(50),
+    jitter: 0.0,
+  };
+  let delays: Vec<u128> = (1..=5).map(|n| policy.delay(n).as_millis()).collect();
+  assert_eq!(delays, vec![10, 20, 40, 50, 50]);
+}
+
+/// Auth on a backend whose first `collisions` session inserts collide
+async fn setup_colliding_auth(retries: u32, collisions: u32) -> Auth {
+  let mut auth = setup_test_auth_with(|b| b.session_collision_retries(retries))
+    .await
+    .unwrap();
+  let (flaky, _) = FlakyDatabase::new(0).await;
+  flaky.collisions.store(collisions, Ordering::SeqCst);
+  Arc::get_mut(&mut auth.inner).unwrap().db = Arc::new(Box::new(flaky));
+
+  auth
+    .register(Register {
+      name: None,
+      email: "collide@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+  auth
+}
+
+#[tokio::test]
+async fn test_login_retries_session_token_collision() {
+  let auth = setup_colliding_auth(3, 1).await;
+
+  let session = auth
+    .login(Login::new("collide@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+
+  let user = auth.verify(Verify::new(&session.token)).await.unwrap();
+  assert_eq!(user.email, "collide@example.com");
+}
+
+#[tokio::test]
+async fn test_login_surfaces_collision_after_retries() {
+  let auth = setup_colliding_auth(1, 2).await;
+
+  let err = auth
+    .login(Login::new("collide@example.com", "SecurePass123"))
+    .await
+    .unwrap_err();
+  assert!(err.is_unique_violation(), "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_verify_fails_closed_on_lookup_error() {
+  let mut auth = setup_test_auth().await.unwrap();
+  let (flaky, _) = FlakyDatabase::new(0).await;
+  let failures = flaky.failures.clone();
+  Arc::get_mut(&mut auth.inner).unwrap().db = Arc::new(Box::new(flaky));
+
+  let session = register_and_login(&auth, "closed@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  failures.store(1, Ordering::SeqCst);
+  let err = auth.verify(Verify::new(&session.token)).await.unwrap_err();
+  assert!(matches!(err, AuthError::InvalidSession), "{:?}", err);
+  assert_eq!(err.code(), "invalid_session");
+
+  // The session itself is fine once the backend recovers
+  assert!(auth.verify(Verify::new(&session.token)).await.is_ok());
+}
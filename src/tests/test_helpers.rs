@@ -1,194 +1,141 @@
 //! Test helpers for setting up database schemas in tests
 //!
 //! This module provides schema setup functions that are only available in test builds.
-//! For production, use the CLI: `authkit migrate --db-url <URL>`
-//!
-//! The schemas here mirror what the CLI generates, including all features
-//! to support comprehensive testing.
+//! Schema creation itself lives in the database backends and is shared with
+//! `Auth::migrate`.
 
 use crate::error::Result;
 use crate::types::Database;
 
-/// Set up the test database schema for SQLite
+/// Set up the test database schema
+///
+/// Runs the same migration as [`Auth::migrate`](crate::Auth::migrate), which
+/// includes the email_verification columns needed by the full test suite.
+pub(crate) async fn setup_test_schema(db: &Database) -> Result<()> {
+  crate::database::create_database_trait(db.inner.clone())
+    .migrate()
+    .await
+}
+
+/// Create the tables as the first release of AuthKit did, without any
+/// column added since
 ///
-/// Creates all tables including email_verification columns for comprehensive testing.
+/// For testing that `migrate` upgrades existing databases.
 #[cfg(feature = "sqlite")]
-pub(crate) async fn setup_sqlite_schema(db: &Database) -> Result<()> {
+pub(crate) async fn setup_baseline_sqlite_schema(db: &Database) -> Result<()> {
+  execute_sql(
+    db,
+    r#"
+    CREATE TABLE users (
+      id TEXT PRIMARY KEY,
+      email TEXT NOT NULL UNIQUE,
+      name TEXT,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL,
+      email_verified INTEGER NOT NULL DEFAULT 0,
+      email_verified_at INTEGER
+    );
+    CREATE TABLE accounts (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+      provider TEXT NOT NULL,
+      provider_account_id TEXT NOT NULL,
+      password_hash TEXT,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL,
+      UNIQUE(provider, provider_account_id)
+    );
+    CREATE TABLE sessions (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+      token TEXT NOT NULL UNIQUE,
+      expires_at INTEGER NOT NULL,
+      created_at INTEGER NOT NULL,
+      ip_address TEXT,
+      user_agent TEXT
+    );
+    CREATE TABLE verification (
+      id TEXT PRIMARY KEY,
+      user_id TEXT REFERENCES users(id) ON DELETE CASCADE,
+      identifier TEXT NOT NULL,
+      token_hash TEXT NOT NULL UNIQUE,
+      token_type TEXT NOT NULL,
+      expires_at INTEGER NOT NULL,
+      created_at INTEGER NOT NULL,
+      used_at INTEGER
+    );
+    "#,
+  )
+  .await
+}
+
+/// Execute a raw SQL statement against the test database
+///
+/// Used by tests to simulate states that can't be reached through the public
+/// API, such as expired tokens or corrupted rows.
+pub(crate) async fn execute_sql(db: &Database, sql: &str) -> Result<()> {
   use sqlx::Executor;
 
-  let pool = match &db.inner {
-    crate::types::DatabaseInner::Sqlite(sqlite_db) => &sqlite_db.pool,
+  match &db.inner {
+    #[cfg(feature = "sqlite")]
+    crate::types::DatabaseInner::Sqlite(sqlite_db) => {
+      sqlite_db.pool.execute(sql).await?;
+    }
     #[cfg(feature = "postgres")]
-    _ => panic!("Expected SQLite database"),
-  };
-
-  // Users table with email_verification columns
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS users (
-        id TEXT PRIMARY KEY,
-        email TEXT NOT NULL UNIQUE,
-        name TEXT,
-        created_at INTEGER NOT NULL,
-        updated_at INTEGER NOT NULL,
-        email_verified INTEGER NOT NULL DEFAULT 0,
-        email_verified_at INTEGER
-      )
-      "#,
-    )
-    .await?;
-
-  // Accounts table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS accounts (
-        id TEXT PRIMARY KEY,
-        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-        provider TEXT NOT NULL,
-        provider_account_id TEXT NOT NULL,
-        password_hash TEXT,
-        created_at INTEGER NOT NULL,
-        updated_at INTEGER NOT NULL,
-        UNIQUE(provider, provider_account_id)
-      )
-      "#,
-    )
-    .await?;
-
-  // Sessions table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS sessions (
-        id TEXT PRIMARY KEY,
-        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-        token TEXT NOT NULL UNIQUE,
-        expires_at INTEGER NOT NULL,
-        created_at INTEGER NOT NULL,
-        ip_address TEXT,
-        user_agent TEXT
-      )
-      "#,
-    )
-    .await?;
-
-  // Verification table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS verification (
-        id TEXT PRIMARY KEY,
-        user_id TEXT REFERENCES users(id) ON DELETE CASCADE,
-        identifier TEXT NOT NULL,
-        token_hash TEXT NOT NULL UNIQUE,
-        token_type TEXT NOT NULL,
-        expires_at INTEGER NOT NULL,
-        created_at INTEGER NOT NULL,
-        used_at INTEGER
-      )
-      "#,
-    )
-    .await?;
+    crate::types::DatabaseInner::Postgres(postgres_db) => {
+      postgres_db.pool.execute(sql).await?;
+    }
+  }
 
   Ok(())
 }
 
-/// Set up the test database schema for PostgreSQL
-///
-/// Creates all tables including email_verification columns for comprehensive testing.
-#[cfg(feature = "postgres")]
-pub(crate) async fn setup_postgres_schema(db: &Database) -> Result<()> {
-  use sqlx::Executor;
-
-  let pool = match &db.inner {
-    crate::types::DatabaseInner::Postgres(postgres_db) => &postgres_db.pool,
+/// Run a query returning a single integer against the test database
+pub(crate) async fn query_i64(db: &Database, sql: &str) -> Result<i64> {
+  let value = match &db.inner {
     #[cfg(feature = "sqlite")]
-    _ => panic!("Expected PostgreSQL database"),
+    crate::types::DatabaseInner::Sqlite(sqlite_db) => {
+      sqlx::query_scalar(sql).fetch_one(&sqlite_db.pool).await?
+    }
+    #[cfg(feature = "postgres")]
+    crate::types::DatabaseInner::Postgres(postgres_db) => {
+      sqlx::query_scalar(sql).fetch_one(&postgres_db.pool).await?
+    }
   };
 
-  // Users table with email_verification columns
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS users (
-        id TEXT PRIMARY KEY,
-        email TEXT NOT NULL UNIQUE,
-        name TEXT,
-        created_at BIGINT NOT NULL,
-        updated_at BIGINT NOT NULL,
-        email_verified BOOLEAN NOT NULL DEFAULT FALSE,
-        email_verified_at BIGINT
-      )
-      "#,
-    )
-    .await?;
-
-  // Accounts table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS accounts (
-        id TEXT PRIMARY KEY,
-        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-        provider TEXT NOT NULL,
-        provider_account_id TEXT NOT NULL,
-        password_hash TEXT,
-        created_at BIGINT NOT NULL,
-        updated_at BIGINT NOT NULL,
-        UNIQUE(provider, provider_account_id)
-      )
-      "#,
-    )
-    .await?;
+  Ok(value)
+}
 
-  // Sessions table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS sessions (
-        id TEXT PRIMARY KEY,
-        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-        token TEXT NOT NULL UNIQUE,
-        expires_at BIGINT NOT NULL,
-        created_at BIGINT NOT NULL,
-        ip_address TEXT,
-        user_agent TEXT
-      )
-      "#,
-    )
-    .await?;
+/// A SQLite database file in the temp dir, removed (with its WAL files) on drop
+///
+/// In-memory databases serialize writers behind a shared-cache lock, so
+/// concurrency tests need a real file to exercise the backend's locking.
+#[cfg(feature = "sqlite")]
+pub(crate) struct TempSqliteFile {
+  path: std::path::PathBuf,
+}
 
-  // Verification table
-  pool
-    .execute(
-      r#"
-      CREATE TABLE IF NOT EXISTS verification (
-        id TEXT PRIMARY KEY,
-        user_id TEXT REFERENCES users(id) ON DELETE CASCADE,
-        identifier TEXT NOT NULL,
-        token_hash TEXT NOT NULL UNIQUE,
-        token_type TEXT NOT NULL,
-        expires_at BIGINT NOT NULL,
-        created_at BIGINT NOT NULL,
-        used_at BIGINT
-      )
-      "#,
-    )
-    .await?;
+#[cfg(feature = "sqlite")]
+impl TempSqliteFile {
+  pub(crate) fn new(prefix: &str) -> Self {
+    let path = std::env::temp_dir().join(format!(
+      "{}-{}.db",
+      prefix,
+      crate::TokenGenerator::generate_id(&crate::OsTokenGenerator)
+    ));
+    Self { path }
+  }
 
-  Ok(())
+  pub(crate) fn path(&self) -> &str {
+    self.path.to_str().unwrap()
+  }
 }
 
-/// Set up the test database schema based on the database type
-///
-/// This function detects the database type and calls the appropriate setup function.
-pub(crate) async fn setup_test_schema(db: &Database) -> Result<()> {
-  match &db.inner {
-    #[cfg(feature = "sqlite")]
-    crate::types::DatabaseInner::Sqlite(_) => setup_sqlite_schema(db).await,
-    #[cfg(feature = "postgres")]
-    crate::types::DatabaseInner::Postgres(_) => setup_postgres_schema(db).await,
+#[cfg(feature = "sqlite")]
+impl Drop for TempSqliteFile {
+  fn drop(&mut self) {
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+    }
   }
 }
@@ -0,0 +1,926 @@
+//! Tests for the token strategy
+//!
+//! Most of these exercise the internal `TokenStrategy` directly for behavior
+//! that isn't reachable through a public operation yet.
+
+use crate::prelude::*;
+use crate::tests::integration_tests::{setup_test_auth, setup_test_auth_with, setup_test_database};
+use crate::{OsTokenGenerator, TokenGenerator, TokenHashAlgo};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const FIFTEEN_MINUTES: i64 = 15 * 60;
+
+/// Deterministic generator for reproducible tokens in tests
+struct SeededGenerator(Mutex<StdRng>);
+
+impl SeededGenerator {
+  fn new(seed: u64) -> Self {
+    Self(Mutex::new(StdRng::seed_from_u64(seed)))
+  }
+}
+
+impl TokenGenerator for SeededGenerator {
+  fn fill_bytes(&self, dest: &mut [u8]) {
+    self.0.lock().unwrap().fill_bytes(dest);
+  }
+}
+
+/// Secure tokens, but ids from a counter, like an integer primary key
+struct SequenceIds(AtomicU64);
+
+impl TokenGenerator for SequenceIds {
+  fn fill_bytes(&self, dest: &mut [u8]) {
+    OsTokenGenerator.fill_bytes(dest);
+  }
+
+  fn generate_id(&self) -> String {
+    self.0.fetch_add(1, Ordering::SeqCst).to_string()
+  }
+}
+
+async fn register(auth: &Auth, email: &str) -> User {
+  auth
+    .register(Register {
+      name: None,
+      email: email.into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_seeded_generator_reproduces_tokens() {
+  let mut tokens = Vec::new();
+
+  for _ in 0..2 {
+    let auth = setup_test_auth_with(|b| b.token_generator(SeededGenerator::new(42)))
+      .await
+      .unwrap();
+    let user = register(&auth, "seeded@example.com").await;
+    let session = auth
+      .login(Login {
+        ip_address: None,
+        user_agent: None,
+        remember_me: false,
+        email: "seeded@example.com".into(),
+        password: "SecurePass123".into(),
+      })
+      .await
+      .unwrap();
+
+    // Seeded tokens are still real, verifiable tokens
+    assert_eq!(
+      auth.verify(Verify::new(&session.token)).await.unwrap().id,
+      user.id
+    );
+    tokens.push((user.id, session.token));
+  }
+
+  assert_eq!(tokens[0], tokens[1]);
+  assert_eq!(tokens[0].1.len(), 64);
+}
+
+#[tokio::test]
+async fn test_numeric_code_verifies_for_identifier() {
+  let auth = setup_test_auth().await.unwrap();
+  let db = auth.inner.db.as_ref().as_ref();
+  let strategy = &auth.inner.token_strategy;
+  let user = register(&auth, "otp@example.com").await;
+
+  let code = strategy
+    .generate_code(
+      db,
+      &user.id,
+      &user.email,
+      TokenType::EmailVerification,
+      6,
+      FIFTEEN_MINUTES,
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(code.token.len(), 6);
+  assert!(code.token.chars().all(|c| c.is_ascii_digit()));
+
+  let verified = strategy
+    .verify_code(db, &user.email, &code.token, TokenType::EmailVerification)
+    .await
+    .unwrap();
+  assert_eq!(verified.user_id.as_deref(), Some(user.id.as_str()));
+
+  // Once used, the code is rejected
+  strategy
+    .mark_code_as_used(db, &user.email, &code.token)
+    .await
+    .unwrap();
+  let result = strategy
+    .verify_code(db, &user.email, &code.token, TokenType::EmailVerification)
+    .await;
+  assert!(matches!(
+    result.unwrap_err(),
+    AuthError::TokenAlreadyUsed(_)
+  ));
+}
+
+#[tokio::test]
+async fn test_numeric_code_wrong_code_rejected() {
+  let auth = setup_test_auth().await.unwrap();
+  let db = auth.inner.db.as_ref().as_ref();
+  let strategy = &auth.inner.token_strategy;
+  let user = register(&auth, "otp@example.com").await;
+
+  let code = strategy
+    .generate_code(
+      db,
+      &user.id,
+      &user.email,
+      TokenType::EmailVerification,
+      6,
+      FIFTEEN_MINUTES,
+    )
+    .await
+    .unwrap();
+
+  let wrong = if code.token == "000000" {
+    "111111"
+  } else {
+    "000000"
+  };
+  let result = strategy
+    .verify_code(db, &user.email, wrong, TokenType::EmailVerification)
+    .await;
+  assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+}
+
+#[tokio::test]
+async fn test_numeric_code_locked_after_failed_attempts() {
+  let auth = setup_test_auth().await.unwrap();
+  let db = auth.inner.db.as_ref().as_ref();
+  let strategy = &auth.inner.token_strategy;
+  let user = register(&auth, "otp@example.com").await;
+
+  let code = strategy
+    .generate_code(
+      db,
+      &user.id,
+      &user.email,
+      TokenType::EmailVerification,
+      6,
+      FIFTEEN_MINUTES,
+    )
+    .await
+    .unwrap();
+
+  let wrong = if code.token == "000000" {
+    "111111"
+  } else {
+    "000000"
+  };
+  for _ in 0..5 {
+    let result = strategy
+      .verify_code(db, &user.email, wrong, TokenType::EmailVerification)
+      .await;
+    assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+  }
+
+  // Even the right code is refused once the identifier is locked
+  let result = strategy
+    .verify_code(db, &user.email, &code.token, TokenType::EmailVerification)
+    .await;
+  assert!(matches!(
+    result.unwrap_err(),
+    AuthError::RateLimitExceeded(_)
+  ));
+
+  // The outstanding code was invalidated, so it stays dead after the window
+  auth
+    .rate_limit_store()
+    .reset(&format!("code:email_verification:{}", user.email))
+    .await
+    .unwrap();
+  let result = strategy
+    .verify_code(db, &user.email, &code.token, TokenType::EmailVerification)
+    .await;
+  assert!(matches!(
+    result.unwrap_err(),
+    AuthError::TokenAlreadyUsed(_)
+  ));
+}
+
+#[tokio::test]
+async fn test_numeric_code_scoped_to_identifier() {
+  let auth = setup_test_auth().await.unwrap();
+  let db = auth.inner.db.as_ref().as_ref();
+  let strategy = &auth.inner.token_strategy;
+  let alice = register(&auth, "alice@example.com").await;
+  let bob = register(&auth, "bob@example.com").await;
+
+  let code = strategy
+    .generate_code(
+      db,
+      &alice.id,
+      &alice.email,
+      TokenType::EmailVerification,
+      6,
+      FIFTEEN_MINUTES,
+    )
+    .await
+    .unwrap();
+
+  // Alice's code does not verify for Bob
+  let result = strategy
+    .verify_code(db, &bob.email, &code.token, TokenType::EmailVerification)
+    .await;
+  assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_encrypted_token_round_trip() {
+  let db = setup_test_database().await.unwrap();
+  let auth = Auth::builder()
+    .database(db)
+    .token_encryption_key([7u8; 32])
+    .build()
+    .unwrap();
+  let user = register(&auth, "encrypted@example.com").await;
+
+  let verification = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let stored = auth
+    .inner
+    .db
+    .find_latest_verification(&user.email, TokenType::EmailVerification.as_str())
+    .await
+    .unwrap()
+    .unwrap()
+    .token_hash;
+
+  // Stored value is not the plaintext, but support tooling can recover it
+  assert_ne!(stored, verification.token);
+  assert_eq!(
+    auth.decrypt_verification_token(&stored).unwrap(),
+    verification.token
+  );
+
+  let verified = auth
+    .verify_email(VerifyEmail {
+      token: verification.token,
+    })
+    .await
+    .unwrap();
+  assert!(verified.email_verified);
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_encrypted_token_wrong_key_fails() {
+  let db = setup_test_database().await.unwrap();
+  let auth = Auth::builder()
+    .database(db.clone())
+    .token_encryption_key([7u8; 32])
+    .build()
+    .unwrap();
+  let other_key = Auth::builder()
+    .database(db)
+    .token_encryption_key([9u8; 32])
+    .build()
+    .unwrap();
+  let user = register(&auth, "encrypted@example.com").await;
+
+  let verification = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let stored = auth
+    .inner
+    .db
+    .find_latest_verification(&user.email, TokenType::EmailVerification.as_str())
+    .await
+    .unwrap()
+    .unwrap()
+    .token_hash;
+
+  let result = other_key
+    .verify_email(VerifyEmail {
+      token: verification.token,
+    })
+    .await;
+  assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+
+  assert!(matches!(
+    other_key.decrypt_verification_token(&stored).unwrap_err(),
+    AuthError::InvalidToken(_)
+  ));
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_resend_reuses_valid_encrypted_token() {
+  let auth = setup_test_auth_with(|builder| {
+    builder
+      .token_encryption_key([7u8; 32])
+      .reuse_valid_verification_tokens(true)
+  })
+  .await
+  .unwrap();
+  let user = register(&auth, "reuse@example.com").await;
+
+  let first = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let resent = auth
+    .resend_email_verification(ResendEmailVerification {
+      email: user.email.clone(),
+    })
+    .await
+    .unwrap();
+  assert_eq!(resent.token, first.token);
+  assert_eq!(resent.expires_at, first.expires_at);
+
+  // The resent token is the same one, so verifying it works once
+  auth
+    .verify_email(VerifyEmail {
+      token: resent.token,
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_resend_of_reused_token_starts_cooldown() {
+  let db = setup_test_database().await.unwrap();
+  let auth = Auth::builder()
+    .database(db.clone())
+    .token_encryption_key([7u8; 32])
+    .reuse_valid_verification_tokens(true)
+    .email_send_cooldown(std::time::Duration::from_secs(60))
+    .build()
+    .unwrap();
+  let user = register(&auth, "reuse@example.com").await;
+
+  auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  // Let the cooldown on the first send pass
+  crate::tests::test_helpers::execute_sql(
+    &db,
+    "UPDATE verification SET created_at = created_at - 61;
+     UPDATE verification_sends SET sent_at = sent_at - 61",
+  )
+  .await
+  .unwrap();
+
+  let request = ResendEmailVerification {
+    email: user.email.clone(),
+  };
+  auth
+    .resend_email_verification(request.clone())
+    .await
+    .unwrap();
+
+  // The resend reused the token but still counts as a send
+  let result = auth.resend_email_verification(request).await;
+  assert!(matches!(result, Err(AuthError::Cooldown { .. })));
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_resends_of_reused_token_count_toward_limit() {
+  let auth = setup_test_auth_with(|builder| {
+    builder
+      .token_encryption_key([7u8; 32])
+      .reuse_valid_verification_tokens(true)
+      .max_verification_resends(1)
+  })
+  .await
+  .unwrap();
+  let user = register(&auth, "reuse@example.com").await;
+
+  auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let request = ResendEmailVerification {
+    email: user.email.clone(),
+  };
+  auth
+    .resend_email_verification(request.clone())
+    .await
+    .unwrap();
+
+  // Only one token exists, but two emails went out
+  let result = auth.resend_email_verification(request).await;
+  assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+}
+
+#[cfg(feature = "encrypted-tokens")]
+#[tokio::test]
+async fn test_resend_mints_new_token_by_default() {
+  let auth = setup_test_auth_with(|builder| builder.token_encryption_key([7u8; 32]))
+    .await
+    .unwrap();
+  let user = register(&auth, "fresh@example.com").await;
+
+  let first = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+  let resent = auth
+    .resend_email_verification(ResendEmailVerification { email: user.email })
+    .await
+    .unwrap();
+
+  assert_ne!(resent.token, first.token);
+}
+
+#[tokio::test]
+async fn test_verification_for_non_email_identifier() {
+  let auth = setup_test_auth().await.unwrap();
+  const WHATSAPP: TokenType = TokenType::Custom("whatsapp_verification");
+
+  let token = auth
+    .issue_verification(IssueVerification {
+      identifier: "+15555550123".into(),
+      token_type: WHATSAPP,
+      user_id: None,
+      expires_in: std::time::Duration::from_secs(600),
+    })
+    .await
+    .unwrap();
+  assert_eq!(token.identifier, "+15555550123");
+
+  // Tokens don't verify against another purpose
+  let result = auth
+    .consume_verification(ConsumeVerification {
+      token: token.token.clone(),
+      token_type: TokenType::EmailVerification,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+
+  let verified = auth
+    .consume_verification(ConsumeVerification {
+      token: token.token.clone(),
+      token_type: WHATSAPP,
+    })
+    .await
+    .unwrap();
+  assert_eq!(verified.identifier, "+15555550123");
+  assert_eq!(verified.user_id, None);
+  assert_eq!(verified.token_type, WHATSAPP);
+
+  let result = auth
+    .consume_verification(ConsumeVerification {
+      token: token.token,
+      token_type: WHATSAPP,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::TokenAlreadyUsed(_))));
+}
+
+#[tokio::test]
+async fn test_issue_verification_rejects_bad_input() {
+  let auth = setup_test_auth().await.unwrap();
+  let issue = |identifier: &str, token_type| IssueVerification {
+    identifier: identifier.into(),
+    token_type,
+    user_id: None,
+    expires_in: std::time::Duration::from_secs(600),
+  };
+
+  let result = auth
+    .issue_verification(issue(" ", TokenType::Custom("whatsapp_verification")))
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+
+  let result = auth
+    .issue_verification(issue("+15555550123", TokenType::Custom("")))
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+
+  // A custom name can't pass for a built-in type
+  let result = auth
+    .issue_verification(issue(
+      "+15555550123",
+      TokenType::Custom("email_verification"),
+    ))
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+  let result = auth
+    .consume_verification(ConsumeVerification {
+      token: "some-token".into(),
+      token_type: TokenType::Custom("password_reset"),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn test_classify_token() {
+  let auth = setup_test_auth().await.unwrap();
+  let user = register(&auth, "classify@example.com").await;
+
+  let session = auth
+    .login(Login {
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+      email: "classify@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await
+    .unwrap();
+  assert_eq!(
+    auth.classify_token(&session.token).await.unwrap(),
+    TokenKind::Session
+  );
+
+  let verification = auth
+    .send_email_verification(SendEmailVerification { user_id: user.id })
+    .await
+    .unwrap();
+  assert_eq!(
+    auth.classify_token(&verification.token).await.unwrap(),
+    TokenKind::Verification("email_verification".to_string())
+  );
+
+  // Classifying doesn't consume the token
+  auth
+    .verify_email(VerifyEmail {
+      token: verification.token,
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(
+    auth.classify_token("not-a-real-token").await.unwrap(),
+    TokenKind::Unknown
+  );
+}
+
+#[tokio::test]
+async fn test_invalidate_all_tokens() {
+  let auth = setup_test_auth().await.unwrap();
+  const WHATSAPP: TokenType = TokenType::Custom("whatsapp_verification");
+
+  let alice = register(&auth, "alice@example.com").await;
+  let bob = register(&auth, "bob@example.com").await;
+  let mut email_tokens = Vec::new();
+  for user in [&alice, &bob] {
+    let token = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+    email_tokens.push(token.token);
+  }
+  let whatsapp = auth
+    .issue_verification(IssueVerification {
+      identifier: "+15555550123".into(),
+      token_type: WHATSAPP,
+      user_id: None,
+      expires_in: std::time::Duration::from_secs(600),
+    })
+    .await
+    .unwrap();
+
+  // Only the requested type is invalidated
+  let deleted = auth
+    .invalidate_all_tokens(Some(TokenType::EmailVerification))
+    .await
+    .unwrap();
+  assert_eq!(deleted, 2);
+  for token in email_tokens {
+    let result = auth.verify_email(VerifyEmail { token }).await;
+    assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+  }
+  assert_eq!(
+    auth.classify_token(&whatsapp.token).await.unwrap(),
+    TokenKind::Verification("whatsapp_verification".to_string())
+  );
+
+  // Without a type, everything goes
+  assert_eq!(auth.invalidate_all_tokens(None).await.unwrap(), 1);
+  let result = auth
+    .consume_verification(ConsumeVerification {
+      token: whatsapp.token,
+      token_type: WHATSAPP,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidToken(_))));
+
+  assert_eq!(auth.invalidate_all_tokens(None).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_token_hash_algorithms_round_trip() {
+  let algos = [
+    TokenHashAlgo::Sha256,
+    TokenHashAlgo::Sha512,
+    TokenHashAlgo::HmacSha256 {
+      secret: vec![3u8; 32],
+    },
+  ];
+
+  for algo in algos {
+    let auth = setup_test_auth_with(|b| b.token_hash(algo.clone()))
+      .await
+      .unwrap();
+    let user = register(&auth, "hashed@example.com").await;
+
+    let verification = auth
+      .send_email_verification(SendEmailVerification {
+        user_id: user.id.clone(),
+      })
+      .await
+      .unwrap();
+
+    let stored = auth
+      .inner
+      .db
+      .find_latest_verification(&user.email, TokenType::EmailVerification.as_str())
+      .await
+      .unwrap()
+      .unwrap()
+      .token_hash;
+    assert_eq!(stored, algo.hash(&verification.token), "{:?}", algo);
+
+    let verified = auth
+      .verify_email(VerifyEmail {
+        token: verification.token,
+      })
+      .await
+      .unwrap();
+    assert!(verified.email_verified, "{:?}", algo);
+  }
+}
+
+#[tokio::test]
+async fn test_token_hash_algorithms_differ() {
+  let sha512 = TokenHashAlgo::Sha512.hash("token");
+  let hmac = TokenHashAlgo::HmacSha256 {
+    secret: vec![3u8; 32],
+  }
+  .hash("token");
+  let other_key = TokenHashAlgo::HmacSha256 {
+    secret: vec![4u8; 32],
+  }
+  .hash("token");
+
+  assert_eq!(sha512.len(), 128);
+  assert_ne!(hmac, TokenHashAlgo::Sha256.hash("token"));
+  assert_ne!(hmac, other_key);
+}
+
+#[tokio::test]
+async fn test_token_hash_wrong_key_fails() {
+  let db = setup_test_database().await.unwrap();
+  let auth = Auth::builder()
+    .database(db.clone())
+    .token_hash(TokenHashAlgo::HmacSha256 {
+      secret: vec![3u8; 32],
+    })
+    .build()
+    .unwrap();
+  let other_key = Auth::builder()
+    .database(db)
+    .token_hash(TokenHashAlgo::HmacSha256 {
+      secret: vec![4u8; 32],
+    })
+    .build()
+    .unwrap();
+  let user = register(&auth, "hashed@example.com").await;
+
+  let verification = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let result = other_key
+    .verify_email(VerifyEmail {
+      token: verification.token,
+    })
+    .await;
+  assert!(matches!(result.unwrap_err(), AuthError::InvalidToken(_)));
+}
+
+#[tokio::test]
+async fn test_numeric_string_ids() {
+  let auth = setup_test_auth_with(|b| b.token_generator(SequenceIds(AtomicU64::new(1000))))
+    .await
+    .unwrap();
+
+  let user = register(&auth, "numeric@example.com").await;
+  assert!(user.id.parse::<u64>().is_ok(), "{}", user.id);
+
+  let session = auth
+    .login(Login::new("numeric@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+  assert!(session.id.parse::<u64>().is_ok(), "{}", session.id);
+  assert_eq!(session.token.len(), 64);
+  assert_eq!(
+    auth.verify(Verify::new(&session.token)).await.unwrap().id,
+    user.id
+  );
+
+  let sessions = auth.list_sessions(&user.id).await.unwrap();
+  assert_eq!(sessions.len(), 1);
+  assert_eq!(sessions[0].id, session.id);
+
+  auth
+    .revoke_session(RevokeSession {
+      user_id: user.id.clone(),
+      session_id: session.id.clone(),
+    })
+    .await
+    .unwrap();
+  let result = auth.verify(Verify::new(&session.token)).await;
+  assert!(matches!(result, Err(AuthError::InvalidSession)));
+}
+
+/// Token store keeping everything in a vector, standing in for e.g. Redis
+#[derive(Clone, Default)]
+struct InMemoryTokenStore(std::sync::Arc<Mutex<Vec<StoredToken>>>);
+
+#[async_trait::async_trait]
+impl TokenStore for InMemoryTokenStore {
+  async fn create(&self, token: &StoredToken) -> Result<()> {
+    self.0.lock().unwrap().push(token.clone());
+    Ok(())
+  }
+
+  async fn find(&self, token_hash: &str) -> Result<Option<StoredToken>> {
+    let tokens = self.0.lock().unwrap();
+    Ok(tokens.iter().find(|t| t.token_hash == token_hash).cloned())
+  }
+
+  async fn find_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<StoredToken>> {
+    let tokens = self.0.lock().unwrap();
+    Ok(
+      tokens
+        .iter()
+        .rev()
+        .filter(|t| t.identifier == identifier && t.token_type == token_type)
+        .cloned()
+        .collect(),
+    )
+  }
+
+  async fn mark_used(&self, token_hash: &str, used_at: i64) -> Result<bool> {
+    let mut tokens = self.0.lock().unwrap();
+    match tokens
+      .iter_mut()
+      .find(|t| t.token_hash == token_hash && t.used_at.is_none())
+    {
+      Some(token) => {
+        token.used_at = Some(used_at);
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+
+  async fn delete_expired(&self, now: i64) -> Result<u64> {
+    let mut tokens = self.0.lock().unwrap();
+    let before = tokens.len();
+    tokens.retain(|t| t.expires_at >= now);
+    Ok((before - tokens.len()) as u64)
+  }
+}
+
+#[tokio::test]
+async fn test_verification_flow_with_external_token_store() {
+  let store = InMemoryTokenStore::default();
+  let auth = setup_test_auth_with(|b| b.token_store(store.clone()))
+    .await
+    .unwrap();
+  let user = auth
+    .register(Register::new("store@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+
+  let verification = auth
+    .send_email_verification(SendEmailVerification {
+      user_id: user.id.clone(),
+    })
+    .await
+    .unwrap();
+
+  // The token went to the store, not the verification table
+  let stored = store.0.lock().unwrap().clone();
+  assert_eq!(stored.len(), 1);
+  assert_eq!(stored[0].identifier, "store@example.com");
+  assert_eq!(stored[0].token_type, "email_verification");
+  assert!(auth
+    .inner
+    .db
+    .find_verification_by_hash(&stored[0].token_hash)
+    .await
+    .unwrap()
+    .is_none());
+
+  let verified = auth
+    .verify_email(VerifyEmail {
+      token: verification.token.clone(),
+    })
+    .await
+    .unwrap();
+  assert!(verified.email_verified);
+  assert!(store.0.lock().unwrap()[0].used_at.is_some());
+
+  let result = auth
+    .verify_email(VerifyEmail {
+      token: verification.token,
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::TokenAlreadyUsed(_))));
+}
+
+#[tokio::test]
+async fn test_send_limits_hold_with_external_token_store() {
+  let db = setup_test_database().await.unwrap();
+  let auth = Auth::builder()
+    .database(db.clone())
+    .token_store(InMemoryTokenStore::default())
+    .email_send_cooldown(std::time::Duration::from_secs(60))
+    .max_verification_resends(0)
+    .build()
+    .unwrap();
+  let user = register(&auth, "store@example.com").await;
+
+  let request = SendEmailVerification {
+    user_id: user.id.clone(),
+  };
+  auth.send_email_verification(request.clone()).await.unwrap();
+
+  // The token isn't in the SQL table, but the send is still on record
+  let result = auth.send_email_verification(request.clone()).await;
+  assert!(matches!(result, Err(AuthError::Cooldown { .. })));
+
+  crate::tests::test_helpers::execute_sql(
+    &db,
+    "UPDATE verification_sends SET sent_at = sent_at - 61",
+  )
+  .await
+  .unwrap();
+  let result = auth.send_email_verification_code(request).await;
+  assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+}
+
+#[tokio::test]
+async fn test_verification_code_with_external_token_store() {
+  let store = InMemoryTokenStore::default();
+  let auth = setup_test_auth_with(|b| b.token_store(store.clone()))
+    .await
+    .unwrap();
+  let user = auth
+    .register(Register::new("code@example.com", "SecurePass123"))
+    .await
+    .unwrap();
+
+  let code = auth
+    .send_email_verification_code(SendEmailVerification { user_id: user.id })
+    .await
+    .unwrap();
+  let verified = auth
+    .verify_email_with_identifier("code@example.com", &code.token)
+    .await
+    .unwrap();
+  assert!(verified.email_verified);
+  assert_eq!(store.0.lock().unwrap().len(), 1);
+}
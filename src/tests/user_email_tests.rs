@@ -0,0 +1,310 @@
+//! Tests for secondary email addresses
+//!
+//! Covers adding, verifying, listing and promoting secondary emails.
+
+use crate::prelude::*;
+use crate::tests::integration_tests::{register_and_login, setup_test_auth};
+
+#[tokio::test]
+async fn test_add_and_verify_secondary_email() {
+  let auth = setup_test_auth().await.unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  let token = auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+  assert_eq!(token.identifier, "backup@example.com");
+
+  let emails = auth.list_emails(&session.user_id).await.unwrap();
+  assert_eq!(emails.len(), 2);
+  assert!(emails[0].primary);
+  assert_eq!(emails[0].email, "primary@example.com");
+  assert!(!emails[1].primary);
+  assert!(!emails[1].verified);
+
+  let verified = auth
+    .verify_secondary_email(VerifySecondaryEmail { token: token.token })
+    .await
+    .unwrap();
+  assert_eq!(verified.email, "backup@example.com");
+  assert!(verified.verified);
+
+  let emails = auth.list_emails(&session.user_id).await.unwrap();
+  assert!(emails[1].verified);
+}
+
+#[tokio::test]
+async fn test_add_email_rejects_taken_addresses() {
+  let auth = setup_test_auth().await.unwrap();
+  let alice = register_and_login(&auth, "alice@example.com", "SecurePass123")
+    .await
+    .unwrap();
+  register_and_login(&auth, "bob@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  // Another user's primary email
+  let result = auth
+    .add_email(AddEmail {
+      user_id: alice.user_id.clone(),
+      email: "bob@example.com".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::UserAlreadyExists(_))));
+
+  // A secondary email can't be registered as a new user
+  auth
+    .add_email(AddEmail {
+      user_id: alice.user_id.clone(),
+      email: "alice.work@example.com".into(),
+    })
+    .await
+    .unwrap();
+  let result = auth
+    .register(Register {
+      name: None,
+      email: "alice.work@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::UserAlreadyExists(_))));
+  assert!(!auth
+    .is_email_available("alice.work@example.com")
+    .await
+    .unwrap());
+}
+
+#[tokio::test]
+async fn test_set_primary_email_requires_verification() {
+  let auth = setup_test_auth().await.unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+
+  let result = auth
+    .set_primary_email(SetPrimaryEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::EmailNotVerified(_))));
+
+  let result = auth
+    .set_primary_email(SetPrimaryEmail {
+      user_id: session.user_id.clone(),
+      email: "other@example.com".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::EmailNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_promote_secondary_email_to_primary() {
+  let auth = setup_test_auth().await.unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  let token = auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+  auth
+    .verify_secondary_email(VerifySecondaryEmail { token: token.token })
+    .await
+    .unwrap();
+
+  let user = auth
+    .set_primary_email(SetPrimaryEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+  assert_eq!(user.email, "backup@example.com");
+  assert!(user.email_verified);
+
+  // The old primary is kept as an (unverified) secondary address
+  let emails = auth.list_emails(&session.user_id).await.unwrap();
+  assert_eq!(emails.len(), 2);
+  assert_eq!(emails[0].email, "backup@example.com");
+  assert!(emails[0].primary);
+  assert_eq!(emails[1].email, "primary@example.com");
+  assert!(!emails[1].verified);
+
+  // Login follows the new primary email
+  let result = auth
+    .login(Login {
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+      email: "backup@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(result.is_ok());
+
+  let result = auth
+    .login(Login {
+      ip_address: None,
+      user_agent: None,
+      remember_me: false,
+      email: "primary@example.com".into(),
+      password: "SecurePass123".into(),
+    })
+    .await;
+  assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+}
+
+#[tokio::test]
+async fn test_unverified_identifiers_and_secondary_resend() {
+  let auth = setup_test_auth().await.unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+  auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+
+  let unverified = auth.unverified_identifiers(&session.user_id).await.unwrap();
+  assert_eq!(
+    unverified,
+    vec![
+      UnverifiedIdentifier {
+        kind: IdentifierKind::Email,
+        value: "primary@example.com".into(),
+      },
+      UnverifiedIdentifier {
+        kind: IdentifierKind::Email,
+        value: "backup@example.com".into(),
+      },
+    ]
+  );
+
+  // Resend for the secondary channel, then verify with the new token
+  let token = auth
+    .resend_secondary_email_verification(&session.user_id, "backup@example.com")
+    .await
+    .unwrap();
+  auth
+    .verify_secondary_email(VerifySecondaryEmail { token: token.token })
+    .await
+    .unwrap();
+
+  let unverified = auth.unverified_identifiers(&session.user_id).await.unwrap();
+  assert_eq!(unverified.len(), 1);
+  assert_eq!(unverified[0].value, "primary@example.com");
+
+  let result = auth
+    .resend_secondary_email_verification(&session.user_id, "backup@example.com")
+    .await;
+  assert!(matches!(result, Err(AuthError::EmailAlreadyVerified(_))));
+  let result = auth
+    .resend_secondary_email_verification(&session.user_id, "primary@example.com")
+    .await;
+  assert!(matches!(result, Err(AuthError::EmailNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_add_email_survives_sender_failure() {
+  use crate::tests::integration_tests::setup_test_auth_with;
+
+  struct FailingSender;
+
+  #[async_trait::async_trait]
+  impl EmailSender for FailingSender {
+    async fn send_verification_email(&self, _context: EmailContext) -> Result<()> {
+      Err(AuthError::EmailSendFailed("smtp unavailable".into()))
+    }
+  }
+
+  let auth = setup_test_auth_with(|b| b.email_sender(Box::new(FailingSender)))
+    .await
+    .unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+
+  // The address and token exist, so the token is returned despite the failure
+  let token = auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+  auth
+    .verify_secondary_email(VerifySecondaryEmail { token: token.token })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_secondary_resend_respects_send_limits() {
+  use crate::tests::integration_tests::setup_test_auth_with;
+  use std::time::Duration;
+
+  let auth = setup_test_auth_with(|b| b.email_send_cooldown(Duration::from_secs(60)))
+    .await
+    .unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+  auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+
+  // add_email's send starts the cooldown for the address
+  let result = auth
+    .resend_secondary_email_verification(&session.user_id, "backup@example.com")
+    .await;
+  assert!(matches!(result, Err(AuthError::Cooldown { .. })));
+
+  let auth = setup_test_auth_with(|b| b.max_verification_resends(1))
+    .await
+    .unwrap();
+  let session = register_and_login(&auth, "primary@example.com", "SecurePass123")
+    .await
+    .unwrap();
+  auth
+    .add_email(AddEmail {
+      user_id: session.user_id.clone(),
+      email: "backup@example.com".into(),
+    })
+    .await
+    .unwrap();
+
+  auth
+    .resend_secondary_email_verification(&session.user_id, "backup@example.com")
+    .await
+    .unwrap();
+  let result = auth
+    .resend_secondary_email_verification(&session.user_id, "backup@example.com")
+    .await;
+  assert!(matches!(result, Err(AuthError::RateLimitExceeded(_))));
+}
@@ -1,7 +1,9 @@
-//! Validation tests for email and password validation
+//! Validation tests for email, password and provider validation
 
 use crate::error::AuthError;
-use crate::validation::{email, password};
+use crate::validation::email::EmailValidator;
+use crate::validation::password::PasswordPolicy;
+use crate::validation::{email, provider};
 
 #[test]
 fn test_valid_email() {
@@ -42,15 +44,15 @@ fn test_invalid_email() {
 
 #[test]
 fn test_valid_password() {
-  assert!(password::validate("Password123").is_ok());
-  assert!(password::validate("Abcdefgh1").is_ok());
-  assert!(password::validate("MyP@ssw0rd").is_ok());
-  assert!(password::validate("Str0ngP@ssw0rd!").is_ok());
+  assert!(PasswordPolicy::default().check("Password123").is_ok());
+  assert!(PasswordPolicy::default().check("Abcdefgh1").is_ok());
+  assert!(PasswordPolicy::default().check("MyP@ssw0rd").is_ok());
+  assert!(PasswordPolicy::default().check("Str0ngP@ssw0rd!").is_ok());
 }
 
 #[test]
 fn test_password_too_short() {
-  let result = password::validate("Short1");
+  let result = PasswordPolicy::default().check("Short1");
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
   if let Err(AuthError::WeakPassword(msg)) = result {
     assert!(msg.contains("at least 8 characters"));
@@ -60,7 +62,7 @@ fn test_password_too_short() {
 #[test]
 fn test_password_too_long() {
   let long_password = "A1".to_string() + &"a".repeat(127);
-  let result = password::validate(&long_password);
+  let result = PasswordPolicy::default().check(&long_password);
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
   if let Err(AuthError::WeakPassword(msg)) = result {
     assert!(msg.contains("at most 128 characters"));
@@ -69,7 +71,7 @@ fn test_password_too_long() {
 
 #[test]
 fn test_password_no_uppercase() {
-  let result = password::validate("password123");
+  let result = PasswordPolicy::default().check("password123");
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
   if let Err(AuthError::WeakPassword(msg)) = result {
     assert!(msg.contains("uppercase"));
@@ -78,7 +80,7 @@ fn test_password_no_uppercase() {
 
 #[test]
 fn test_password_no_lowercase() {
-  let result = password::validate("PASSWORD123");
+  let result = PasswordPolicy::default().check("PASSWORD123");
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
   if let Err(AuthError::WeakPassword(msg)) = result {
     assert!(msg.contains("lowercase"));
@@ -87,7 +89,7 @@ fn test_password_no_lowercase() {
 
 #[test]
 fn test_password_no_digit() {
-  let result = password::validate("Password");
+  let result = PasswordPolicy::default().check("Password");
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
   if let Err(AuthError::WeakPassword(msg)) = result {
     assert!(msg.contains("digit"));
@@ -96,36 +98,51 @@ fn test_password_no_digit() {
 
 #[test]
 fn test_password_exactly_min_length() {
-  assert!(password::validate("Passwor1").is_ok());
+  assert!(PasswordPolicy::default().check("Passwor1").is_ok());
 }
 
 #[test]
 fn test_password_exactly_max_length() {
   let max_password = "A1".to_string() + &"a".repeat(126);
   assert_eq!(max_password.len(), 128);
-  assert!(password::validate(&max_password).is_ok());
+  assert!(PasswordPolicy::default().check(&max_password).is_ok());
 }
 
 #[test]
 fn test_password_with_special_characters() {
-  assert!(password::validate("P@ssw0rd!").is_ok());
-  assert!(password::validate("My$ecureP@ss1").is_ok());
-  assert!(password::validate("Test#Pass123").is_ok());
+  assert!(PasswordPolicy::default().check("P@ssw0rd!").is_ok());
+  assert!(PasswordPolicy::default().check("My$ecureP@ss1").is_ok());
+  assert!(PasswordPolicy::default().check("Test#Pass123").is_ok());
 }
 
 #[test]
 fn test_password_unicode_characters() {
   // Unicode characters should work as long as requirements are met
-  assert!(password::validate("Pässw0rd").is_ok());
-  assert!(password::validate("P4ssw0rd™").is_ok());
+  assert!(PasswordPolicy::default().check("Pässw0rd").is_ok());
+  assert!(PasswordPolicy::default().check("P4ssw0rd™").is_ok());
 }
 
 #[test]
 fn test_empty_password() {
-  let result = password::validate("");
+  let result = PasswordPolicy::default().check("");
   assert!(matches!(result, Err(AuthError::WeakPassword(_))));
 }
 
+#[test]
+fn test_password_policy_without_complexity() {
+  let policy = PasswordPolicy::default().without_complexity();
+
+  assert!(policy.check("aaaaaaaa").is_ok());
+  assert!(matches!(
+    policy.check("aaa"),
+    Err(AuthError::WeakPassword(_))
+  ));
+  assert!(matches!(
+    PasswordPolicy::default().check("aaaaaaaa"),
+    Err(AuthError::WeakPassword(_))
+  ));
+}
+
 #[test]
 fn test_email_edge_cases() {
   // Multiple dots in local part
@@ -137,3 +154,77 @@ fn test_email_edge_cases() {
   // Subdomain
   assert!(email::validate("user@mail.example.com").is_ok());
 }
+
+#[test]
+fn test_provider_normalized_to_lowercase() {
+  assert_eq!(provider::normalize("Google").unwrap(), "google");
+  assert_eq!(provider::normalize("github").unwrap(), "github");
+}
+
+#[test]
+fn test_invalid_provider() {
+  for bad in ["", "   ", "goo\ngle", &"x".repeat(65)] {
+    assert!(
+      matches!(provider::normalize(bad), Err(AuthError::InvalidInput(_))),
+      "{:?}",
+      bad
+    );
+  }
+}
+
+#[test]
+fn test_invalid_provider_account_id() {
+  assert!(provider::validate_account_id("1234567890").is_ok());
+  for bad in ["", "\t", "12\u{0}34", &"1".repeat(256)] {
+    assert!(
+      matches!(
+        provider::validate_account_id(bad),
+        Err(AuthError::InvalidInput(_))
+      ),
+      "{:?}",
+      bad
+    );
+  }
+}
+
+#[cfg(feature = "strict-email")]
+#[test]
+fn test_strict_email_validator_differs_from_basic() {
+  let basic = EmailValidator::Basic;
+  let strict = EmailValidator::Strict;
+
+  // RFC 5322 allows these; the basic pattern doesn't
+  for address in [
+    "user@localhost",
+    "\"john doe\"@example.com",
+    "o'brien@example.com",
+  ] {
+    assert!(basic.validate(address).is_err(), "{}", address);
+    assert!(strict.validate(address).is_ok(), "{}", address);
+  }
+
+  // The basic pattern allows these; RFC 5322 doesn't
+  for address in ["user..name@example.com", ".user@example.com"] {
+    assert!(basic.validate(address).is_ok(), "{}", address);
+    assert!(
+      matches!(strict.validate(address), Err(AuthError::InvalidEmailFormat)),
+      "{}",
+      address
+    );
+  }
+
+  // Display names are never accepted
+  assert!(strict.validate("User <user@example.com>").is_err());
+  assert!(strict.validate("user@example.com").is_ok());
+}
+
+#[test]
+fn test_custom_email_validator() {
+  let corporate = EmailValidator::Custom(|email| email.ends_with("@corp.example"));
+
+  assert!(corporate.validate("alice@corp.example").is_ok());
+  assert!(matches!(
+    corporate.validate("alice@example.com"),
+    Err(AuthError::InvalidEmailFormat)
+  ));
+}
@@ -0,0 +1,168 @@
+use crate::database::models::DbVerification;
+use crate::database::DatabaseTrait;
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// A verification token as held by a [`TokenStore`]
+///
+/// Only the protected form of the token (`token_hash`, a hash or ciphertext)
+/// is ever stored; the plaintext is not available to stores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredToken {
+  pub id: String,
+  pub user_id: Option<String>,
+  /// What is being verified: an email address or phone number
+  pub identifier: String,
+  pub token_hash: String,
+  /// Purpose, as in `TokenType::as_str`
+  pub token_type: String,
+  pub expires_at: i64,
+  pub created_at: i64,
+  pub used_at: Option<i64>,
+}
+
+/// Storage for verification tokens (email verification, password reset,
+/// one-time codes, ...)
+///
+/// By default tokens live in the SQL `verification` table next to users and
+/// sessions. Implement this trait to keep them somewhere else, e.g. Redis
+/// with key expiry, and install it with `AuthBuilder::token_store`.
+///
+/// Implementations must satisfy:
+///
+/// - `create` stores a token. `token_hash` is unique across all tokens.
+/// - `find` returns the token with `token_hash`, used or expired ones
+///   included, or `None`.
+/// - `find_by_identifier` returns every token for `identifier` of
+///   `token_type`, newest first.
+/// - `mark_used` sets `used_at` on an unused token and returns `true`, or
+///   returns `false` if it was already used or doesn't exist. It must be
+///   atomic: of several concurrent calls for one token, only one sees `true`.
+/// - `delete_expired` removes tokens with `expires_at` before `now` and
+///   returns how many were removed. A store that expires keys on its own may
+///   return 0.
+///
+/// Send cooldowns and resend limits work from a log of sends in the main
+/// database, so they hold with any store. `Auth::verification_status` and
+/// the bulk token revocation and cleanup operations still query the SQL
+/// table, so they don't see tokens held in an external store.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use authkit::{Result, StoredToken, TokenStore};
+/// use async_trait::async_trait;
+///
+/// struct RedisTokenStore {
+///     client: redis::Client,
+/// }
+///
+/// #[async_trait]
+/// impl TokenStore for RedisTokenStore {
+///     async fn create(&self, token: &StoredToken) -> Result<()> {
+///         // SET token:<hash> with a TTL of expires_at - now, and add the
+///         // hash to the identifier's set
+///     }
+///     // ...
+/// }
+///
+/// let auth = Auth::builder()
+///     .database(Database::postgres(&url).await?)
+///     .token_store(RedisTokenStore { client })
+///     .build()?;
+/// ```
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+  /// Store a newly issued token
+  async fn create(&self, token: &StoredToken) -> Result<()>;
+
+  /// Find a token by its protected value, whatever its state
+  async fn find(&self, token_hash: &str) -> Result<Option<StoredToken>>;
+
+  /// Find all tokens for an identifier and purpose, newest first
+  async fn find_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<StoredToken>>;
+
+  /// Mark an unused token as used; `false` if it was already used or missing
+  async fn mark_used(&self, token_hash: &str, used_at: i64) -> Result<bool>;
+
+  /// Delete tokens that expired before `now`
+  async fn delete_expired(&self, now: i64) -> Result<u64>;
+}
+
+/// The default store: the `verification` table of the main database
+pub(crate) struct SqlTokenStore<'a>(pub(crate) &'a dyn DatabaseTrait);
+
+#[async_trait]
+impl TokenStore for SqlTokenStore<'_> {
+  async fn create(&self, token: &StoredToken) -> Result<()> {
+    self.0.create_verification(&token.clone().into()).await
+  }
+
+  async fn find(&self, token_hash: &str) -> Result<Option<StoredToken>> {
+    Ok(
+      self
+        .0
+        .find_verification_by_hash(token_hash)
+        .await?
+        .map(StoredToken::from),
+    )
+  }
+
+  async fn find_by_identifier(
+    &self,
+    identifier: &str,
+    token_type: &str,
+  ) -> Result<Vec<StoredToken>> {
+    Ok(
+      self
+        .0
+        .find_verifications_by_identifier(identifier, token_type)
+        .await?
+        .into_iter()
+        .map(StoredToken::from)
+        .collect(),
+    )
+  }
+
+  async fn mark_used(&self, token_hash: &str, used_at: i64) -> Result<bool> {
+    self.0.mark_verification_used(token_hash, used_at).await
+  }
+
+  async fn delete_expired(&self, _now: i64) -> Result<u64> {
+    self.0.delete_expired_verifications().await
+  }
+}
+
+impl From<StoredToken> for DbVerification {
+  fn from(token: StoredToken) -> Self {
+    Self {
+      id: token.id,
+      user_id: token.user_id,
+      identifier: token.identifier,
+      token_hash: token.token_hash,
+      token_type: token.token_type,
+      expires_at: token.expires_at,
+      created_at: token.created_at,
+      used_at: token.used_at,
+    }
+  }
+}
+
+impl From<DbVerification> for StoredToken {
+  fn from(verification: DbVerification) -> Self {
+    Self {
+      id: verification.id,
+      user_id: verification.user_id,
+      identifier: verification.identifier,
+      token_hash: verification.token_hash,
+      token_type: verification.token_type,
+      expires_at: verification.expires_at,
+      created_at: verification.created_at,
+      used_at: verification.used_at,
+    }
+  }
+}
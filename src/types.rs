@@ -13,6 +13,8 @@ pub struct User {
 }
 
 /// Account represents an authentication provider linked to a user
+///
+/// Never carries credentials; the password hash stays in the database.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
   pub id: String,
@@ -23,14 +25,72 @@ pub struct Account {
   pub updated_at: i64,
 }
 
-#[allow(dead_code)]
+/// An email address belonging to a user
+///
+/// The primary address is the one stored on [`User`] and used for login;
+/// secondary addresses can be added with `Auth::add_email`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserEmail {
+  pub email: String,
+  pub primary: bool,
+  pub verified: bool,
+  pub verified_at: Option<i64>,
+  pub created_at: i64,
+}
+
+/// The kind of contact channel an identifier belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierKind {
+  Email,
+  Phone,
+}
+
+/// An email address or phone number of a user that isn't verified yet,
+/// from `Auth::unverified_identifiers`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnverifiedIdentifier {
+  pub kind: IdentifierKind,
+  pub value: String,
+}
+
+/// A freshly issued verification token, returned so the application can
+/// deliver it
+///
+/// Only a hash (or ciphertext) of the token is stored; this is the one place
+/// the plaintext is available.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VerificationToken {
+  /// Plaintext token (or one-time code) to send to the user
   pub token: String,
+  /// What is being verified: an email address or phone number
   pub identifier: String,
+  /// Unix timestamp (seconds) after which the token no longer verifies
   pub expires_at: i64,
 }
 
+impl VerificationToken {
+  /// Whether the token has expired as of `now` (Unix seconds)
+  pub fn is_expired(&self, now: i64) -> bool {
+    self.expires_at < now
+  }
+
+  /// The link for the user to follow, built from `template`
+  ///
+  /// `{token}` in the template is replaced with the percent-encoded token. A
+  /// template without the placeholder is taken as a base URL and gets
+  /// `?token=...` appended (`&token=...` if it already has a query).
+  ///
+  /// # Example
+  ///
+  /// ```rust,ignore
+  /// let url = verification.verification_url("https://myapp.com/verify-email?token={token}");
+  /// ```
+  pub fn verification_url(&self, template: &str) -> String {
+    crate::email::verification_url(template, &self.token)
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
   pub id: String,
@@ -40,6 +100,25 @@ pub struct Session {
   pub created_at: i64,
   pub ip_address: Option<String>,
   pub user_agent: Option<String>,
+  /// Whether the session was created with "remember me" (longer lifetime)
+  pub remember_me: bool,
+  /// Last time the session was used; only tracked with an inactivity timeout
+  pub last_activity_at: i64,
+}
+
+/// A session as shown in listings, without its secret token
+///
+/// Use `id` to refer to the session, e.g. with `Auth::revoke_session`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+  pub id: String,
+  pub user_id: String,
+  pub expires_at: i64,
+  pub created_at: i64,
+  pub ip_address: Option<String>,
+  pub user_agent: Option<String>,
+  pub remember_me: bool,
+  pub last_activity_at: i64,
 }
 
 pub struct Database {
@@ -47,9 +126,55 @@ pub struct Database {
 }
 
 impl Database {
+  /// Connect to a database, choosing the backend from the URL scheme
+  ///
+  /// `sqlite:` URLs use the SQLite backend and `postgres://` or
+  /// `postgresql://` URLs use the PostgreSQL backend. The matching feature
+  /// must be enabled.
+  ///
+  /// # Example
+  ///
+  /// ```no_run
+  /// # use authkit::Database;
+  /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// let db = Database::connect(&std::env::var("DATABASE_URL")?).await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn connect(url: &str) -> crate::Result<Self> {
+    #[cfg(feature = "sqlite")]
+    if url.starts_with("sqlite:") {
+      return Self::sqlite(url).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+      return Self::postgres(url).await;
+    }
+
+    let scheme = url.split(':').next().unwrap_or_default();
+    Err(crate::AuthError::ConfigError(format!(
+      "Unsupported database URL scheme '{}' (is the backend feature enabled?)",
+      scheme
+    )))
+  }
+
+  /// Open a SQLite database with the default settings
+  ///
+  /// `":memory:"` opens an in-memory database shared by every connection
+  /// of the pool, so all clones of an `Auth` built on it see the same data.
+  /// It lives as long as the `Database`.
   #[cfg(feature = "sqlite")]
   pub async fn sqlite(path: &str) -> crate::Result<Self> {
-    let inner = crate::database::sqlite::SqliteDatabase::new(path).await?;
+    Self::sqlite_with_config(path, crate::SqliteConfig::default()).await
+  }
+
+  /// Open a SQLite database with explicit connection settings
+  ///
+  /// `Database::sqlite` uses `SqliteConfig::default()` (WAL, 5s busy timeout).
+  #[cfg(feature = "sqlite")]
+  pub async fn sqlite_with_config(path: &str, config: crate::SqliteConfig) -> crate::Result<Self> {
+    let inner = crate::database::sqlite::SqliteDatabase::new(path, &config).await?;
     Ok(Database {
       inner: DatabaseInner::Sqlite(inner),
     })
@@ -17,3 +17,48 @@ pub fn validate(email: &str) -> Result<()> {
     Err(AuthError::InvalidEmailFormat)
   }
 }
+
+/// How email addresses are checked on registration and when adding emails
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let auth = Auth::builder()
+///     .database(Database::sqlite("auth.db").await?)
+///     .email_validator(EmailValidator::Custom(|email| email.ends_with("@corp.example")))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub enum EmailValidator {
+  /// A simple `local@domain.tld` pattern; requires a TLD and rejects quoted
+  /// local parts
+  #[default]
+  Basic,
+  /// Full RFC 5322 addresses (no display names), via the `email_address`
+  /// crate
+  #[cfg(feature = "strict-email")]
+  Strict,
+  /// Your own check; `false` fails with `AuthError::InvalidEmailFormat`
+  Custom(fn(&str) -> bool),
+}
+
+impl EmailValidator {
+  pub(crate) fn validate(&self, email: &str) -> Result<()> {
+    let valid = match self {
+      EmailValidator::Basic => return validate(email),
+      #[cfg(feature = "strict-email")]
+      EmailValidator::Strict => email_address::EmailAddress::parse_with_options(
+        email,
+        email_address::Options::default().without_display_text(),
+      )
+      .is_ok(),
+      EmailValidator::Custom(check) => check(email),
+    };
+
+    if valid {
+      Ok(())
+    } else {
+      Err(AuthError::InvalidEmailFormat)
+    }
+  }
+}
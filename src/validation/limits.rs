@@ -0,0 +1,92 @@
+use crate::error::{AuthError, Result};
+
+/// Maximum accepted sizes for user-supplied inputs
+///
+/// Checked at the start of each operation, before any hashing or database
+/// work, so oversized inputs (a 1MB "password", say) are rejected cheaply
+/// with `AuthError::InvalidInput`. Lengths are in bytes.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let auth = Auth::builder()
+///     .database(Database::sqlite("auth.db").await?)
+///     .input_limits(InputLimits::default().with_max_password_len(256))
+///     .build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct InputLimits {
+  pub max_email_len: usize,
+  pub max_password_len: usize,
+  pub max_name_len: usize,
+  pub max_token_len: usize,
+}
+
+impl Default for InputLimits {
+  fn default() -> Self {
+    Self {
+      max_email_len: 320,
+      max_password_len: 1024,
+      max_name_len: 256,
+      max_token_len: 1024,
+    }
+  }
+}
+
+impl InputLimits {
+  /// Set the longest accepted email address (default 320 bytes, the
+  /// RFC 5321 limit)
+  pub fn with_max_email_len(mut self, len: usize) -> Self {
+    self.max_email_len = len;
+    self
+  }
+
+  /// Set the longest accepted password (default 1024 bytes)
+  ///
+  /// Applies to login as well as registration and password changes, so
+  /// lowering it below existing passwords locks those users out.
+  pub fn with_max_password_len(mut self, len: usize) -> Self {
+    self.max_password_len = len;
+    self
+  }
+
+  /// Set the longest accepted display name (default 256 bytes)
+  pub fn with_max_name_len(mut self, len: usize) -> Self {
+    self.max_name_len = len;
+    self
+  }
+
+  /// Set the longest accepted session or verification token (default 1024
+  /// bytes)
+  pub fn with_max_token_len(mut self, len: usize) -> Self {
+    self.max_token_len = len;
+    self
+  }
+
+  pub(crate) fn check_email(&self, email: &str) -> Result<()> {
+    check("email", email, self.max_email_len)
+  }
+
+  pub(crate) fn check_password(&self, password: &str) -> Result<()> {
+    check("password", password, self.max_password_len)
+  }
+
+  pub(crate) fn check_name(&self, name: Option<&str>) -> Result<()> {
+    name.map_or(Ok(()), |name| check("name", name, self.max_name_len))
+  }
+
+  pub(crate) fn check_token(&self, token: &str) -> Result<()> {
+    check("token", token, self.max_token_len)
+  }
+}
+
+fn check(field: &str, value: &str, max: usize) -> Result<()> {
+  if value.len() > max {
+    return Err(AuthError::InvalidInput(format!(
+      "{} must be at most {} bytes",
+      field, max
+    )));
+  }
+
+  Ok(())
+}
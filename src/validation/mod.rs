@@ -1,2 +1,6 @@
 pub mod email;
+pub mod limits;
 pub mod password;
+#[cfg(feature = "sms")]
+pub mod phone;
+pub mod provider;
@@ -3,50 +3,98 @@ use crate::error::{AuthError, Result};
 const MIN_PASSWORD_LENGTH: usize = 8;
 const MAX_PASSWORD_LENGTH: usize = 128;
 
-/// Validate password strength
+/// Password requirements checked on registration
 ///
-/// Requirements:
-/// - At least 8 characters
-/// - At most 128 characters
-/// - Contains at least one uppercase letter
-/// - Contains at least one lowercase letter
-/// - Contains at least one digit
-pub fn validate(password: &str) -> Result<()> {
-  if password.len() < MIN_PASSWORD_LENGTH {
-    return Err(AuthError::WeakPassword(format!(
-      "Password must be at least {} characters",
-      MIN_PASSWORD_LENGTH
-    )));
-  }
+/// The default requires 8 to 128 characters with at least one uppercase
+/// letter, one lowercase letter and one digit.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // Length floor only, no character classes
+/// let auth = Auth::builder()
+///     .database(Database::sqlite("auth.db").await?)
+///     .password_policy(PasswordPolicy::default().without_complexity())
+///     .build()?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordPolicy {
+  pub min_length: usize,
+  pub max_length: usize,
+  pub require_uppercase: bool,
+  pub require_lowercase: bool,
+  pub require_digit: bool,
+}
 
-  if password.len() > MAX_PASSWORD_LENGTH {
-    return Err(AuthError::WeakPassword(format!(
-      "Password must be at most {} characters",
-      MAX_PASSWORD_LENGTH
-    )));
+impl Default for PasswordPolicy {
+  fn default() -> Self {
+    Self {
+      min_length: MIN_PASSWORD_LENGTH,
+      max_length: MAX_PASSWORD_LENGTH,
+      require_uppercase: true,
+      require_lowercase: true,
+      require_digit: true,
+    }
   }
+}
 
-  let has_uppercase = password.chars().any(|c| c.is_uppercase());
-  let has_lowercase = password.chars().any(|c| c.is_lowercase());
-  let has_digit = password.chars().any(|c| c.is_ascii_digit());
-
-  if !has_uppercase {
-    return Err(AuthError::WeakPassword(
-      "Password must contain at least one uppercase letter".into(),
-    ));
+impl PasswordPolicy {
+  /// Set the minimum password length in bytes (default 8)
+  pub fn with_min_length(mut self, len: usize) -> Self {
+    self.min_length = len;
+    self
   }
 
-  if !has_lowercase {
-    return Err(AuthError::WeakPassword(
-      "Password must contain at least one lowercase letter".into(),
-    ));
+  /// Set the maximum password length in bytes (default 128)
+  ///
+  /// `InputLimits::max_password_len` still rejects longer input first.
+  pub fn with_max_length(mut self, len: usize) -> Self {
+    self.max_length = len;
+    self
   }
 
-  if !has_digit {
-    return Err(AuthError::WeakPassword(
-      "Password must contain at least one digit".into(),
-    ));
+  /// Drop the uppercase, lowercase and digit requirements, keeping lengths
+  pub fn without_complexity(mut self) -> Self {
+    self.require_uppercase = false;
+    self.require_lowercase = false;
+    self.require_digit = false;
+    self
   }
 
-  Ok(())
+  /// Check a password against the policy
+  pub fn check(&self, password: &str) -> Result<()> {
+    if password.len() < self.min_length {
+      return Err(AuthError::WeakPassword(format!(
+        "Password must be at least {} characters",
+        self.min_length
+      )));
+    }
+
+    if password.len() > self.max_length {
+      return Err(AuthError::WeakPassword(format!(
+        "Password must be at most {} characters",
+        self.max_length
+      )));
+    }
+
+    if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+      return Err(AuthError::WeakPassword(
+        "Password must contain at least one uppercase letter".into(),
+      ));
+    }
+
+    if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+      return Err(AuthError::WeakPassword(
+        "Password must contain at least one lowercase letter".into(),
+      ));
+    }
+
+    if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+      return Err(AuthError::WeakPassword(
+        "Password must contain at least one digit".into(),
+      ));
+    }
+
+    Ok(())
+  }
 }
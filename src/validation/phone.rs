@@ -0,0 +1,18 @@
+use crate::error::{AuthError, Result};
+use regex::Regex;
+use std::sync::OnceLock;
+
+static PHONE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn phone_regex() -> &'static Regex {
+  PHONE_REGEX.get_or_init(|| Regex::new(r"^\+[1-9][0-9]{6,14}$").unwrap())
+}
+
+/// Validate phone number format (E.164)
+pub fn validate(phone: &str) -> Result<()> {
+  if phone_regex().is_match(phone) {
+    Ok(())
+  } else {
+    Err(AuthError::InvalidPhoneFormat)
+  }
+}
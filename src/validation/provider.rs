@@ -0,0 +1,52 @@
+use crate::error::{AuthError, Result};
+
+/// Longest accepted provider name, in bytes
+const MAX_PROVIDER_LEN: usize = 64;
+
+/// Longest accepted provider account ID, in bytes
+const MAX_PROVIDER_ACCOUNT_ID_LEN: usize = 255;
+
+/// Validate a provider name (`"google"`, `"github"`, ...) and lowercase it
+///
+/// Providers are compared case-sensitively by the `UNIQUE(provider,
+/// provider_account_id)` index, so `Google` and `google` must be stored the
+/// same way.
+pub fn normalize(provider: &str) -> Result<String> {
+  check("provider", provider, MAX_PROVIDER_LEN)?;
+  Ok(provider.to_lowercase())
+}
+
+/// Validate a provider's account ID
+///
+/// IDs are opaque and stored as given; only blank, oversized and
+/// control-character values are rejected.
+pub fn validate_account_id(provider_account_id: &str) -> Result<()> {
+  check(
+    "provider_account_id",
+    provider_account_id,
+    MAX_PROVIDER_ACCOUNT_ID_LEN,
+  )
+}
+
+fn check(field: &str, value: &str, max: usize) -> Result<()> {
+  if value.trim().is_empty() {
+    return Err(AuthError::InvalidInput(format!(
+      "{} must not be blank",
+      field
+    )));
+  }
+  if value.len() > max {
+    return Err(AuthError::InvalidInput(format!(
+      "{} must be at most {} bytes",
+      field, max
+    )));
+  }
+  if value.chars().any(char::is_control) {
+    return Err(AuthError::InvalidInput(format!(
+      "{} must not contain control characters",
+      field
+    )));
+  }
+
+  Ok(())
+}